@@ -0,0 +1,65 @@
+use crate::{Hotkey, HotkeyManager, Result};
+use std::{
+  collections::HashMap,
+  hash::Hash,
+  sync::{Arc, Mutex},
+};
+
+type ActionHandler<A> = Arc<Mutex<Box<dyn 'static + FnMut(A) + Send>>>;
+
+/// Binds hotkeys to the variants of an action type, usually an enum, and dispatches
+/// every press to a single handler receiving the action.
+pub struct ActionRegistry<A> {
+  manager: HotkeyManager,
+  handler: ActionHandler<A>,
+  bindings: HashMap<A, Hotkey>,
+}
+
+impl<A> ActionRegistry<A>
+where
+  A: 'static + Copy + Eq + Hash + Send,
+{
+  pub fn new<F>(handler: F) -> Self
+  where
+    F: 'static + FnMut(A) + Send,
+  {
+    Self {
+      manager: HotkeyManager::new(),
+      handler: Arc::new(Mutex::new(Box::new(handler))),
+      bindings: HashMap::new(),
+    }
+  }
+
+  /// Binds the action to a hotkey, replacing its previous hotkey.
+  ///
+  /// The previous hotkey is kept if the new one can't be registered.
+  pub fn bind(&mut self, action: A, hotkey: Hotkey) -> Result<()> {
+    if self.bindings.get(&action) == Some(&hotkey) {
+      return Ok(());
+    }
+    let handler = self.handler.clone();
+    self
+      .manager
+      .register(hotkey.clone(), move || (handler.lock().unwrap())(action))?;
+    if let Some(previous) = self.bindings.insert(action, hotkey) {
+      self.manager.unregister(&previous)?;
+    }
+    Ok(())
+  }
+
+  /// Unbinds the action, returning its hotkey.
+  pub fn unbind(&mut self, action: A) -> Result<Option<Hotkey>> {
+    match self.bindings.remove(&action) {
+      Some(hotkey) => {
+        self.manager.unregister(&hotkey)?;
+        Ok(Some(hotkey))
+      }
+      None => Ok(None),
+    }
+  }
+
+  /// Returns the hotkey bound to the action.
+  pub fn hotkey(&self, action: A) -> Option<&Hotkey> {
+    self.bindings.get(&action)
+  }
+}
@@ -0,0 +1,42 @@
+//! Serializes a [`Hotkey`] as its canonical string form, e.g. `"CTRL+SHIFT+P"`, instead
+//! of the `modifiers` and `keys` arrays, for config files edited by hand:
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! # use tauri_hotkey::Hotkey;
+//! #[derive(Deserialize, Serialize)]
+//! struct Config {
+//!   #[serde(with = "tauri_hotkey::as_string")]
+//!   toggle: Hotkey,
+//! }
+//! ```
+//!
+//! With the `string-serde` feature, `Hotkey`'s own `Serialize` and `Deserialize` use this
+//! form.
+
+use crate::{parse_hotkey, Hotkey};
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(hotkey: &Hotkey, serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.collect_str(&hotkey.normalize())
+}
+
+/// Parses the string with [`parse_hotkey`], reporting its error as a serde error.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hotkey, D::Error> {
+  let accelerator = String::deserialize(deserializer)?;
+  parse_hotkey(&accelerator).map_err(D::Error::custom)
+}
+
+#[cfg(feature = "string-serde")]
+impl serde::Serialize for Hotkey {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize(self, serializer)
+  }
+}
+
+#[cfg(feature = "string-serde")]
+impl<'de> Deserialize<'de> for Hotkey {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Hotkey, D::Error> {
+    deserialize(deserializer)
+  }
+}
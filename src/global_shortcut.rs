@@ -0,0 +1,34 @@
+use crate::{parse_hotkey, HotkeyManager, Result};
+
+/// Wrapper around [`HotkeyManager`] matching the older tauri `GlobalShortcutManager` API,
+/// where hotkeys are given as accelerator strings parsed with [`parse_hotkey`].
+#[derive(Default)]
+pub struct GlobalShortcutManager {
+  manager: HotkeyManager,
+}
+
+impl GlobalShortcutManager {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Determines whether the given accelerator is registered or not.
+  pub fn is_registered(&self, accelerator: &str) -> Result<bool> {
+    Ok(self.manager.is_registered(&parse_hotkey(accelerator)?))
+  }
+
+  pub fn register<F>(&mut self, accelerator: &str, handler: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.manager.register(parse_hotkey(accelerator)?, handler)
+  }
+
+  pub fn unregister(&mut self, accelerator: &str) -> Result<()> {
+    self.manager.unregister(&parse_hotkey(accelerator)?)
+  }
+
+  pub fn unregister_all(&mut self) -> Result<()> {
+    self.manager.unregister_all()
+  }
+}
@@ -0,0 +1,90 @@
+use crate::{parse_hotkey, Error, Hotkey, HotkeyManager, Modifier, Result};
+use serde::{Deserialize, Serialize};
+
+/// A binding of a keymap document, see [`HotkeyManager::export_bindings`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct Binding {
+  action_label: String,
+  accelerator: String,
+}
+
+/// Outcome of [`HotkeyManager::import_bindings`], listing the bindings by action label.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+  pub registered: Vec<String>,
+  /// Bindings whose hotkey is already registered by the manager, or grabs the same OS
+  /// shortcut as a registered one.
+  pub conflicts: Vec<String>,
+  /// Bindings that couldn't be parsed or registered, with the error message.
+  pub failed: Vec<(String, String)>,
+}
+
+// the platform's primary modifier becomes `CommandOrControl`, so `CTRL+S` exported on
+// Windows is `CMD+S` once imported on macOS
+fn portable(hotkey: &Hotkey) -> Hotkey {
+  let mut hotkey = hotkey.normalize();
+  for modifier in &mut hotkey.modifiers {
+    if *modifier == Modifier::primary() {
+      *modifier = Modifier::COMMAND_OR_CONTROL;
+    }
+  }
+  hotkey.normalize()
+}
+
+impl HotkeyManager {
+  /// Exports the labeled hotkeys of this manager, see [`HotkeyManager::register_labeled`],
+  /// as a JSON array of `{ "action_label", "accelerator" }` objects to share a keymap.
+  ///
+  /// Accelerators use the canonical string form, with the platform's primary modifier
+  /// written as `COMMANDORCONTROL` so the document works across platforms.
+  pub fn export_bindings(&self) -> String {
+    let map = self.registry.hotkeys.lock().unwrap();
+    let bindings = self
+      .registered_hotkeys
+      .iter()
+      .filter_map(|hotkey| {
+        let label = map.get(hotkey)?.get(&self.id)?.label.clone()?;
+        Some(Binding {
+          action_label: label,
+          accelerator: portable(hotkey).to_string(),
+        })
+      })
+      .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&bindings).expect("bindings are always serializable")
+  }
+
+  /// Registers the bindings of a document written by [`HotkeyManager::export_bindings`],
+  /// with `dispatch` receiving the action label of the pressed hotkey.
+  ///
+  /// Bindings are imported one by one, so a conflicting or invalid binding is reported
+  /// without preventing the others. Fails with [`Error::InvalidKeymap`] if the document
+  /// isn't a keymap at all.
+  pub fn import_bindings<F>(&mut self, json: &str, dispatch: F) -> Result<ImportReport>
+  where
+    F: 'static + Fn(&str) + Clone + Send,
+  {
+    let bindings: Vec<Binding> =
+      serde_json::from_str(json).map_err(|err| Error::InvalidKeymap(err.to_string()))?;
+    let mut report = ImportReport::default();
+    for binding in bindings {
+      let label = binding.action_label;
+      let hotkey = match parse_hotkey(&binding.accelerator) {
+        Ok(hotkey) => hotkey,
+        Err(err) => {
+          report.failed.push((label, err.to_string()));
+          continue;
+        }
+      };
+      let dispatch = dispatch.clone();
+      let action = label.clone();
+      match self.register_labeled(&label, hotkey, move || dispatch(&action)) {
+        Ok(()) => report.registered.push(label),
+        Err(Error::HotkeyAlreadyRegistered(_)) | Err(Error::HotkeyConflict(..)) => {
+          report.conflicts.push(label)
+        }
+        Err(err) => report.failed.push((label, err.to_string())),
+      }
+    }
+    Ok(report)
+  }
+}
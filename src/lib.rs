@@ -1,658 +1,5966 @@
-use log::{error, info};
+use log::{debug, error, info};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
 
 use std::{
-  collections::{hash_map::Entry, HashMap},
+  collections::{hash_map::Entry, HashMap, HashSet},
+  convert::TryFrom,
   fmt,
-  hash::Hash,
+  hash::{Hash, Hasher},
   str::FromStr,
   sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    mpsc::{channel, Receiver, RecvTimeoutError},
     Arc,
   },
+  thread,
+  time::{Duration, Instant},
 };
 
+use strum::IntoEnumIterator;
+pub use tauri_hotkey_sys::KeyState;
 use tauri_hotkey_sys::*;
 
-type GlobalListener = Lazy<Arc<Mutex<Listener>>>;
-type GlobalHotkeyMap =
-  Arc<Mutex<HashMap<Hotkey, HashMap<usize, Box<dyn 'static + FnMut() + Send>>>>>;
+mod action_registry;
+pub mod as_string;
+pub use action_registry::ActionRegistry;
+mod global_shortcut;
+pub use global_shortcut::GlobalShortcutManager;
+#[cfg(feature = "keymap")]
+mod keymap;
+#[cfg(feature = "keymap")]
+pub use keymap::ImportReport;
+mod localization;
+pub use localization::set_localized_key_name;
+#[cfg(feature = "rpc")]
+mod rpc;
+#[cfg(feature = "rpc")]
+pub use rpc::{RpcRequest, RpcResponse, RpcServer};
+mod standard_shortcuts;
+mod suggestions;
+pub use standard_shortcuts::{StandardShortcut, StandardShortcuts};
+pub use suggestions::{is_reserved, suggest_alternatives};
+#[cfg(feature = "chrono")]
+mod schedule;
+#[cfg(feature = "chrono")]
+pub use schedule::Schedule;
+#[cfg(test)]
+mod test_listener;
+#[cfg(test)]
+use test_listener::Listener;
 
-static GLOBAL_LISTENER: GlobalListener = Lazy::new(|| Arc::new(Mutex::new(Listener::new())));
-static GLOBAL_HOTKEY_MAP: Lazy<GlobalHotkeyMap> = Lazy::new(GlobalHotkeyMap::default);
+type HotkeyMap = HashMap<Hotkey, HashMap<usize, Handler>>;
+type Callback = Box<dyn 'static + FnMut() + Send>;
+type HotkeyLostHandler = Box<dyn 'static + Fn(Hotkey) + Send>;
+type ErrorSink = Box<dyn 'static + Fn(&Hotkey, &(dyn std::error::Error + Send)) + Send>;
+type RecoveryObserver = Box<dyn 'static + Fn(RecoveryEvent) + Send>;
+type HotkeyObserver = Box<dyn 'static + Fn(&Hotkey) + Send + Sync>;
+type RegistrationPolicy = Box<dyn 'static + Fn(&Hotkey) -> std::result::Result<(), String> + Send>;
+type ResourceAlive = Box<dyn 'static + Fn() -> bool + Send + Sync>;
+
+static GLOBAL_REGISTRY: Lazy<Arc<HotkeyRegistry>> = Lazy::new(Default::default);
 static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+static HOTKEY_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+static HOTKEY_LOST_HANDLER: Lazy<Mutex<Option<HotkeyLostHandler>>> = Lazy::new(Default::default);
+static RECOVERY_OBSERVER: Lazy<Mutex<Option<RecoveryObserver>>> = Lazy::new(Default::default);
+static HOTKEY_OBSERVER: Lazy<Mutex<Option<HotkeyObserver>>> = Lazy::new(Default::default);
+static INACTIVE_CONTEXTS: Lazy<Mutex<HashSet<String>>> = Lazy::new(Default::default);
+static TOTAL_TRIGGERS: AtomicU64 = AtomicU64::new(0);
+static TOTAL_CALLBACKS: AtomicU64 = AtomicU64::new(0);
+static DROPPED_TRIGGERS: AtomicU64 = AtomicU64::new(0);
+static DISPATCH_PAUSED: AtomicBool = AtomicBool::new(false);
+static APP_FOCUS: Lazy<Mutex<FocusState>> = Lazy::new(|| {
+  Mutex::new(FocusState {
+    focused: true,
+    session: 0,
+  })
+});
+static TRIGGER_LOG_BUCKETS: Lazy<Mutex<HashMap<Hotkey, LogBucket>>> = Lazy::new(Default::default);
+
+/// Maximum number of trigger log lines per hotkey and second, so a hotkey firing in a
+/// tight loop doesn't flood the log.
+const TRIGGER_LOGS_PER_SECOND: f64 = 10.0;
+
+struct FocusState {
+  focused: bool,
+  // incremented every time the app gains focus
+  session: u64,
+}
+
+struct Handler {
+  callback: Callback,
+  context: Option<String>,
+  label: Option<String>,
+  // runs the handlers of this hotkey instead of the callback, see `HotkeyManager::alias`
+  alias: Option<Hotkey>,
+  // skipped by the dispatcher when false, see `HotkeyManager::set_enabled`
+  enabled: bool,
+  // asks the OS for key repeats when grabbing the hotkey, see `HotkeyManager::register_with_repeat`
+  allow_repeat: bool,
+  // never lets the OS report key repeats, see `HotkeyManager::register_no_repeat`
+  no_repeat: bool,
+  // called when the hotkey is released, see `HotkeyManager::register_with_state`
+  on_release: Option<Callback>,
+  // matched by the keyboard hook instead of grabbed, with whether its key events are
+  // swallowed, see `HotkeyManager::register_with_hook`
+  hook: Option<bool>,
+}
+
+// the handler of every hotkey of a sequence, see `HotkeyManager::register_sequence`
+type SequencePress = dyn 'static + Fn(&Hotkey) + Send + Sync;
+
+struct SequenceState<F> {
+  // the number of hotkeys pressed so far
+  progress: usize,
+  last: Instant,
+  callback: F,
+  // asks `arm_sequence` to grab the hotkeys the sequence waits for next
+  arm: std::sync::mpsc::Sender<Vec<Hotkey>>,
+}
+
+// shared by the handler of `HotkeyManager::register_momentary` and its release timeouts
+struct Momentary<P, R> {
+  held: bool,
+  // incremented on every press, so a stale timeout doesn't release a later press
+  press: u64,
+  // dropped on release, which wakes up the timeout of the press
+  cancel: Option<std::sync::mpsc::Sender<()>>,
+  on_press: P,
+  on_release: R,
+}
 
 pub struct HotkeyManager {
   registered_hotkeys: Vec<Hotkey>,
   id: usize,
+  registry: Arc<HotkeyRegistry>,
+  // hotkeys registered with `register_weak`, with a check whether their resource is alive
+  weak_hotkeys: Vec<(Hotkey, ResourceAlive)>,
+  // handles returned by `register_with_id`
+  hotkey_ids: Vec<(HotkeyId, Hotkey)>,
+  // receives the errors of `register_fallible` handlers, see `HotkeyManager::on_error`
+  error_sink: Arc<Mutex<Option<ErrorSink>>>,
+  #[cfg(feature = "devices")]
+  device_hotkeys: Vec<(String, Hotkey, tauri_hotkey_sys::devices::DeviceWatch)>,
 }
 
-#[derive(thiserror::Error, Debug)]
-pub enum Error {
-  #[error("Hotkey system error: {0}")]
-  System(#[from] HotkeyError),
-  #[error("Hotkey already registered")]
-  HotkeyAlreadyRegistered(Hotkey),
-  #[error("Hotkey is not registered")]
-  HotkeyNotRegistered(Hotkey),
-  #[error("failed to parse hotkey: {0}")]
-  InvalidHotkey(String),
+/// The hotkey map and listener shared by [`HotkeyManager`]s.
+///
+/// Managers use the process-wide registry by default. A separate registry keeps its hotkeys
+/// and listener isolated from the other registries, e.g. one per test, but the OS still
+/// grabs the hotkeys of every registry for this process. Contexts, dispatch metrics and the
+/// dispatch pause are always process-wide.
+#[derive(Default)]
+pub struct HotkeyRegistry {
+  listener: once_cell::sync::OnceCell<Mutex<Listener>>,
+  config: Mutex<ListenerConfig>,
+  // locked before the listener
+  hotkeys: Mutex<HotkeyMap>,
+  // locked after the hotkeys
+  usage: Mutex<HashMap<Hotkey, HotkeyUsage>>,
+  usage_sequence: AtomicU64,
+  // only changed while the hotkeys are locked
+  inactive: AtomicBool,
+  policy: Mutex<Option<RegistrationPolicy>>,
+  // set while a `LearnMode` of this registry is alive
+  learning: AtomicBool,
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
-
-impl Default for HotkeyManager {
-  fn default() -> Self {
-    Self {
-      registered_hotkeys: Vec::new(),
-      id: ID_COUNTER.fetch_add(1, Ordering::Relaxed),
-    }
-  }
+/// How often and how recently a hotkey ran a callback.
+struct HotkeyUsage {
+  fire_count: usize,
+  last_fired: Instant,
+  // orders hotkeys fired within the same clock tick
+  last_sequence: u64,
 }
 
-impl HotkeyManager {
+impl HotkeyRegistry {
   pub fn new() -> Self {
     Default::default()
   }
 
-  /// Determines whether the given hotkey is registered or not.
-  pub fn is_registered(&self, hotkey: &Hotkey) -> bool {
-    self.registered_hotkeys.contains(&hotkey)
+  /// Returns the process-wide registry.
+  pub fn global() -> Arc<HotkeyRegistry> {
+    GLOBAL_REGISTRY.clone()
   }
 
-  pub fn register<F>(&mut self, hotkey: Hotkey, callback: F) -> Result<()>
+  /// Configures the listener of this registry.
+  ///
+  /// The listener is created when the first hotkey is registered, so this must be called
+  /// before that, otherwise [`Error::ListenerInitialized`] is returned. An id base outside
+  /// `0..0xBFFF` fails with [`HotkeyError::InvalidIdBase`].
+  pub fn configure(&self, config: ListenerConfig) -> Result<()> {
+    let mut current = self.config.lock().unwrap();
+    if self.listener.get().is_some() {
+      return Err(Error::ListenerInitialized);
+    }
+    ListenerBuilder::new().id_base(config.id_base)?;
+    *current = config;
+    Ok(())
+  }
+
+  /// Sets a policy consulted before every registration in this registry, e.g. to require
+  /// at least one modifier. A rejected hotkey fails with [`Error::PolicyRejected`] holding
+  /// the returned reason. Everything is allowed by default.
+  pub fn set_registration_policy<F>(&self, policy: F)
   where
-    F: 'static + FnMut() + Send,
+    F: 'static + Fn(&Hotkey) -> std::result::Result<(), String> + Send,
   {
-    if self.is_registered(&hotkey) {
-      return Err(Error::HotkeyAlreadyRegistered(hotkey));
+    self.policy.lock().unwrap().replace(Box::new(policy));
+  }
+
+  /// Determines whether the hotkeys of this registry are registered with the OS,
+  /// see [`HotkeyRegistry::set_active`].
+  pub fn is_active(&self) -> bool {
+    !self.inactive.load(Ordering::SeqCst)
+  }
+
+  /// Releases every OS hotkey of this registry when deactivated, e.g. while the app is
+  /// minimized, and registers them again when reactivated. The registered hotkeys and
+  /// their callbacks are kept, and hotkeys registered while inactive are only grabbed
+  /// once the registry is reactivated.
+  ///
+  /// Every hotkey is tried and the first error is returned. Reactivating reports
+  /// [`RecoveryEvent::RegistrationsReapplied`], see [`set_backend_recovery_observer`].
+  pub fn set_active(self: &Arc<Self>, active: bool) -> Result<()> {
+    if active {
+      self.reactivate().0
+    } else {
+      self.suspend()
     }
+  }
 
-    let hotkey_ = hotkey.clone();
-    match GLOBAL_HOTKEY_MAP.lock().unwrap().entry(hotkey.clone()) {
-      Entry::Occupied(mut entry) => {
-        let entry = entry.get_mut();
-        entry.insert(self.id, Box::new(callback));
-      }
-      Entry::Vacant(entry) => {
-        GLOBAL_LISTENER.lock().unwrap().register_hotkey(
-          ListenerHotkey::new(hotkey.modifiers_as_flag(), hotkey.keys_as_flag()),
-          move || {
-            if let Some(entry) = GLOBAL_HOTKEY_MAP.lock().unwrap().get_mut(&hotkey) {
-              for (_, cb) in entry.iter_mut() {
-                cb();
-              }
-            }
-          },
-        )?;
-        let mut new_map: HashMap<usize, Box<dyn 'static + FnMut() + Send>> = HashMap::new();
-        new_map.insert(self.id, Box::new(callback));
-        entry.insert(new_map);
-      }
+  /// Releases every OS hotkey of the registry while keeping the registered hotkeys and
+  /// their callbacks, e.g. while a full-screen game or a screen recorder runs. Same as
+  /// `set_active(false)`.
+  pub fn suspend(self: &Arc<Self>) -> Result<()> {
+    let hotkeys = self.hotkeys.lock().unwrap();
+    if !self.is_active() {
+      return Ok(());
     }
+    self.inactive.store(true, Ordering::SeqCst);
+    let released: Vec<ListenerHotkey> = hotkeys.keys().map(Hotkey::listener_hotkey).collect();
+    self
+      .listener()
+      .lock()
+      .unwrap()
+      .unregister_hotkeys(&released)?;
+    Ok(())
+  }
 
-    info!("register hotkey {}", hotkey_);
-    self.registered_hotkeys.push(hotkey_);
+  /// Grabs again the hotkeys released by [`HotkeyRegistry::suspend`], returning the ones
+  /// that couldn't be, e.g. because another application took them in the meantime. They
+  /// stay registered, and are tried again after the next suspend.
+  pub fn resume(self: &Arc<Self>) -> Vec<Hotkey> {
+    self.reactivate().1
+  }
+
+  // grabs every hotkey, returning the first error and the hotkeys that failed
+  fn reactivate(self: &Arc<Self>) -> (Result<()>, Vec<Hotkey>) {
+    let hotkeys = self.hotkeys.lock().unwrap();
+    if self.is_active() {
+      return (Ok(()), Vec::new());
+    }
+    self.inactive.store(false, Ordering::SeqCst);
+    let mut result = Ok(());
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    for (hotkey, handlers) in hotkeys.iter() {
+      match self.grab_handlers(hotkey, handlers) {
+        Ok(()) => restored.push(hotkey.clone()),
+        Err(err) => {
+          failed.push(hotkey.clone());
+          if result.is_ok() {
+            result = Err(err);
+          }
+        }
+      }
+    }
+    // the observer may use the registry
+    drop(hotkeys);
+    notify_recovery(RecoveryEvent::RegistrationsReapplied {
+      restored,
+      failed: failed.clone(),
+    });
+    (result, failed)
+  }
 
+  /// Registers the hotkey with the OS, dispatching its presses to this registry, and its
+  /// releases too if `with_release` is set. `no_repeat` takes precedence over `allow_repeat`.
+  /// With a `hook`, the hotkey is matched by the keyboard hook instead, swallowing its key
+  /// events if set, and the other options are ignored.
+  fn grab(
+    self: &Arc<Self>,
+    hotkey: &Hotkey,
+    allow_repeat: bool,
+    no_repeat: bool,
+    with_release: bool,
+    hook: Option<bool>,
+  ) -> Result<()> {
+    // the listener is owned by the registry, a strong reference would leak both
+    let registry = Arc::downgrade(self);
+    let pressed = hotkey.clone();
+    let mut listener = self.listener().lock().unwrap();
+    if let Some(suppress) = hook {
+      listener.register_hook_hotkey(hotkey.listener_hotkey(), suppress, move || {
+        if let Some(registry) = registry.upgrade() {
+          registry.dispatch(&pressed);
+        }
+      })?;
+    } else if with_release {
+      listener.register_hotkey_with_state(hotkey.listener_hotkey(), move |state| {
+        if let Some(registry) = registry.upgrade() {
+          match state {
+            KeyState::Pressed => registry.dispatch(&pressed),
+            KeyState::Released => registry.dispatch_release(&pressed),
+          }
+        }
+      })?;
+    } else if no_repeat {
+      listener.register_hotkey_without_repeat(hotkey.listener_hotkey(), move || {
+        if let Some(registry) = registry.upgrade() {
+          registry.dispatch(&pressed);
+        }
+      })?;
+    } else {
+      listener.register_hotkey_with_repeat(hotkey.listener_hotkey(), allow_repeat, move || {
+        if let Some(registry) = registry.upgrade() {
+          registry.dispatch(&pressed);
+        }
+      })?;
+    }
     Ok(())
   }
 
-  pub fn unregister(&mut self, hotkey: &Hotkey) -> Result<()> {
-    match self.registered_hotkeys.iter().position(|h| h == hotkey) {
-      Some(index) => {
-        self.registered_hotkeys.remove(index);
+  // grabs the hotkey with the options its handlers ask for
+  fn grab_handlers(
+    self: &Arc<Self>,
+    hotkey: &Hotkey,
+    handlers: &HashMap<usize, Handler>,
+  ) -> Result<()> {
+    let allow_repeat = handlers.values().any(|handler| handler.allow_repeat);
+    let no_repeat = handlers.values().any(|handler| handler.no_repeat);
+    let with_release = handlers
+      .values()
+      .any(|handler| handler.on_release.is_some());
+    // the handlers of a hotkey agree on the hook, see `HotkeyManager::register_handler`
+    let hook = handlers.values().find_map(|handler| handler.hook);
+    self.grab(hotkey, allow_repeat, no_repeat, with_release, hook)
+  }
+
+  // Adds the handler of a hotkey grabbed outside of a manager, see `arm_sequence`, grabbing
+  // the hotkey unless another handler already did.
+  fn add_handler(self: &Arc<Self>, id: usize, hotkey: &Hotkey, handler: Handler) -> Result<()> {
+    let mut hotkeys = self.hotkeys.lock().unwrap();
+    if !hotkeys.contains_key(hotkey) {
+      let flags = hotkey.listener_hotkey();
+      if let Some(existing) = hotkeys.keys().find(|h| h.listener_hotkey() == flags) {
+        return Err(Error::HotkeyConflict(hotkey.clone(), existing.clone()));
+      }
+      if self.is_active() {
+        self.grab(hotkey, false, false, false, None)?;
       }
-      None => return Err(Error::HotkeyNotRegistered(hotkey.clone())),
     }
+    hotkeys
+      .entry(hotkey.clone())
+      .or_default()
+      .insert(id, handler);
+    Ok(())
+  }
 
-    match GLOBAL_HOTKEY_MAP.lock().unwrap().entry(hotkey.clone()) {
-      std::collections::hash_map::Entry::Occupied(mut occ_entry) => {
-        let entry = occ_entry.get_mut();
-        if entry.remove(&self.id).is_none() {
-          panic!("should never be vacant");
-        }
-        if entry.is_empty() {
-          occ_entry.remove_entry();
-          GLOBAL_LISTENER
+  // Removes a handler added with `add_handler`, releasing the hotkey once no handler is left.
+  fn remove_handler(&self, id: usize, hotkey: &Hotkey) -> Result<()> {
+    let mut hotkeys = self.hotkeys.lock().unwrap();
+    if let Entry::Occupied(mut entry) = hotkeys.entry(hotkey.clone()) {
+      entry.get_mut().remove(&id);
+      if entry.get().is_empty() {
+        entry.remove();
+        if self.is_active() {
+          self
+            .listener()
             .lock()
             .unwrap()
-            .unregister_hotkey(ListenerHotkey::new(
-              hotkey.modifiers_as_flag(),
-              hotkey.keys_as_flag(),
-            ))?;
+            .unregister_hotkey(hotkey.listener_hotkey())?;
         }
       }
-      std::collections::hash_map::Entry::Vacant(_) => {
-        panic!("should never be vacant");
-      }
     }
-    info!("unregister hotkey {}", hotkey);
     Ok(())
   }
 
-  pub fn unregister_all(&mut self) -> Result<()> {
-    let mut result = Ok(());
-    for hotkey in self.registered_hotkeys.clone().iter() {
-      result = self.unregister(hotkey);
-    }
-    result
+  fn listener(&self) -> &Mutex<Listener> {
+    self
+      .listener
+      .get_or_init(|| Mutex::new(build_listener(&self.config.lock().unwrap())))
   }
-}
 
-impl Drop for HotkeyManager {
-  fn drop(&mut self) {
-    if let Err(err) = self.unregister_all() {
-      error!("drop: failed to unregister all hotkeys {:?}", err);
+  fn dispatch(&self, hotkey: &Hotkey) {
+    TOTAL_TRIGGERS.fetch_add(1, Ordering::Relaxed);
+    log_trigger(hotkey);
+    if DISPATCH_PAUSED.load(Ordering::SeqCst) {
+      DROPPED_TRIGGERS.fetch_add(1, Ordering::Relaxed);
+      return;
+    }
+    if let Some(observer) = HOTKEY_OBSERVER.lock().unwrap().as_ref() {
+      observer(hotkey);
+    }
+    let mut callbacks = 0;
+    for target in self.run_handlers(hotkey, &mut callbacks) {
+      // aliases of aliases aren't followed, so aliasing each other can't loop
+      self.run_handlers(&target, &mut callbacks);
+    }
+    TOTAL_CALLBACKS.fetch_add(callbacks, Ordering::Relaxed);
+    if callbacks == 0 {
+      DROPPED_TRIGGERS.fetch_add(1, Ordering::Relaxed);
+    } else {
+      self.record_usage(hotkey);
     }
   }
-}
 
-pub fn parse_hotkey(hotkey_string: &str) -> Result<Hotkey> {
-  let mut modifiers = Vec::new();
-  let mut keys = Vec::new();
-  let mut shifted = false;
-  for raw in hotkey_string.to_uppercase().split('+') {
-    let mut token = raw.trim().to_string();
-    if token.is_empty() {
-      continue;
+  // runs the release callbacks of the hotkey, regardless of dispatch being paused so a
+  // press delivered before pausing still sees its release
+  fn dispatch_release(&self, hotkey: &Hotkey) {
+    if let Some(entry) = self.hotkeys.lock().unwrap().get_mut(hotkey) {
+      let inactive_contexts = INACTIVE_CONTEXTS.lock().unwrap();
+      for handler in entry.values_mut() {
+        if !handler.enabled {
+          continue;
+        }
+        if let Some(context) = &handler.context {
+          if inactive_contexts.contains(context) {
+            continue;
+          }
+        }
+        if let Some(on_release) = &mut handler.on_release {
+          on_release();
+        }
+      }
     }
+  }
 
-    match token.as_str() {
-      // command aliases
-      "COMMAND" | "CMD" => {
-        modifiers.push(Modifier::SUPER);
-        continue;
-      }
-      "CONTROL" => {
-        modifiers.push(Modifier::CTRL);
-        continue;
-      }
-      #[cfg(target_os = "macos")]
-      "OPTION" => {
-        modifiers.push(Modifier::ALT);
-        continue;
-      }
-      "COMMANDORCONTROL" | "COMMANDORCTRL" | "CMDORCTRL" | "CMDORCONTROL" => {
-        #[cfg(target_os = "macos")]
-        modifiers.push(Modifier::SUPER);
-        #[cfg(not(target_os = "macos"))]
-        modifiers.push(Modifier::CTRL);
-        continue;
-      }
-      _ => {
-        if let Ok(res) = Modifier::from_str(&token) {
-          modifiers.push(res);
+  // runs the callbacks of the hotkey, returning the targets of its aliases
+  fn run_handlers(&self, hotkey: &Hotkey, callbacks: &mut u64) -> Vec<Hotkey> {
+    let mut targets = Vec::new();
+    if let Some(entry) = self.hotkeys.lock().unwrap().get_mut(hotkey) {
+      let inactive_contexts = INACTIVE_CONTEXTS.lock().unwrap();
+      for handler in entry.values_mut() {
+        if !handler.enabled {
+          continue;
+        }
+        if let Some(context) = &handler.context {
+          if inactive_contexts.contains(context) {
+            continue;
+          }
+        }
+        if let Some(target) = &handler.alias {
+          targets.push(target.clone());
           continue;
         }
+        (handler.callback)();
+        *callbacks += 1;
       }
     }
+    targets
+  }
 
-    let mut key = None;
+  fn record_usage(&self, hotkey: &Hotkey) {
+    let sequence = self.usage_sequence.fetch_add(1, Ordering::Relaxed);
+    let mut usage = self.usage.lock().unwrap();
+    let entry = usage.entry(hotkey.clone()).or_insert(HotkeyUsage {
+      fire_count: 0,
+      last_fired: Instant::now(),
+      last_sequence: sequence,
+    });
+    entry.fire_count += 1;
+    entry.last_fired = Instant::now();
+    entry.last_sequence = sequence;
+  }
 
-    if token.parse::<usize>().is_ok() {
-      token = format!("KEY_{}", token);
-    }
+  /// Returns the hotkeys registered in this registry by any manager, sorted for display
+  /// with [`Hotkey::ui_sort_key`].
+  pub fn registered_hotkeys(&self) -> Vec<Hotkey> {
+    let mut hotkeys = self
+      .hotkeys
+      .lock()
+      .unwrap()
+      .keys()
+      .cloned()
+      .collect::<Vec<_>>();
+    hotkeys.sort_by_key(Hotkey::ui_sort_key);
+    hotkeys
+  }
 
-    // shift conversions
-    match token.as_str() {
-      ")" => {
-        shifted = true;
-        key = Some(Key::KEY_0);
-      }
-      "!" => {
-        shifted = true;
-        key = Some(Key::KEY_1);
-      }
-      "@" => {
-        shifted = true;
-        key = Some(Key::KEY_2);
-      }
-      "#" => {
-        shifted = true;
-        key = Some(Key::KEY_3);
-      }
-      "$" => {
-        shifted = true;
-        key = Some(Key::KEY_4);
-      }
-      "%" => {
-        shifted = true;
-        key = Some(Key::KEY_5);
-      }
-      "^" => {
-        shifted = true;
-        key = Some(Key::KEY_6);
-      }
-      "&" => {
-        shifted = true;
-        key = Some(Key::KEY_7);
-      }
-      "*" => {
-        shifted = true;
-        key = Some(Key::KEY_8);
-      }
-      "(" => {
-        shifted = true;
-        key = Some(Key::KEY_9);
-      }
-      ":" => {
-        shifted = true;
-        key = Some(Key::SEMICOLON);
-      }
-      "<" => {
-        shifted = true;
-        key = Some(Key::COMMA);
-      }
-      ">" => {
-        shifted = true;
-        key = Some(Key::PERIOD);
-      }
-      "_" => {
-        shifted = true;
-        key = Some(Key::MINUS);
-      }
-      "?" => {
-        shifted = true;
-        key = Some(Key::SLASH);
-      }
-      "~" => {
-        shifted = true;
-        key = Some(Key::OPENQUOTE);
-      }
-      "{" => {
-        shifted = true;
-        key = Some(Key::OPENBRACKET)
-      }
-      "|" => {
-        shifted = true;
-        key = Some(Key::BACKSLASH);
-      }
-      "}" => {
-        shifted = true;
-        key = Some(Key::CLOSEBRACKET);
-      }
-      "+" | "PLUS" => {
-        shifted = true;
-        key = Some(Key::EQUAL);
-      }
-      "\"" => {
-        shifted = true;
-        key = Some(Key::SINGLEQUOTE);
-      }
-      _ => {}
+  /// Runs the handlers of a registered hotkey as if it was pressed and released, returning
+  /// whether the hotkey is registered with the listener. With the `mock` feature no key is
+  /// grabbed from the OS, so this is the only way hotkeys fire, e.g. in tests on CI.
+  #[cfg(feature = "mock")]
+  pub fn trigger(&self, hotkey: &Hotkey) -> bool {
+    let grabbed = self
+      .listener()
+      .lock()
+      .unwrap()
+      .registered_hotkeys()
+      .contains(&hotkey.listener_hotkey());
+    if grabbed {
+      self.dispatch(hotkey);
+      self.dispatch_release(hotkey);
     }
+    grabbed
+  }
 
-    // aliases
-    if key.is_none() {
-      key = match token.as_str() {
-        "RETURN" => Some(Key::ENTER),
-        "=" => Some(Key::EQUAL),
-        "-" => Some(Key::MINUS),
-        "'" => Some(Key::SINGLEQUOTE),
-        "," => Some(Key::COMMA),
-        "." => Some(Key::PERIOD),
-        ";" => Some(Key::SEMICOLON),
-        "/" => Some(Key::SLASH),
-        "`" => Some(Key::OPENQUOTE),
-        "[" => Some(Key::OPENBRACKET),
-        "\\" => Some(Key::BACKSLASH),
-        "]" => Some(Key::CLOSEBRACKET),
-        _ => None,
-      };
-    }
+  /// Returns the registered hotkeys whose key and modifiers are physically held right now.
+  ///
+  /// This polls the OS key state of every hotkey, so it's a snapshot that can race with
+  /// presses and releases happening meanwhile. It's meant for debugging stuck keys and
+  /// polling-style integrations, not for detecting presses.
+  pub fn currently_held_hotkeys(&self) -> Vec<Hotkey> {
+    self.held_hotkeys_with(tauri_hotkey_sys::is_hotkey_held)
+  }
 
-    match key {
-      Some(key) => {
-        if keys.contains(&key) {
-          return Err(crate::Error::InvalidHotkey(format!(
-            "duplicated key {}",
-            raw
-          )));
-        }
-        keys.push(key);
-      }
-      None => {
-        if let Ok(key) = Key::from_str(&token) {
-          if keys.contains(&key) {
-            return Err(crate::Error::InvalidHotkey(format!(
-              "duplicated key {}",
-              raw
-            )));
-          }
-          keys.push(key);
-        } else {
-          return Err(crate::Error::InvalidHotkey(format!(
-            "unknown key {}",
-            token
-          )));
+  /// Reports the canonical hotkey of every combination pressed until the returned
+  /// [`LearnMode`] is dropped, e.g. so a settings UI can show "you pressed CTRL+SHIFT+P"
+  /// live while the user experiments.
+  ///
+  /// Modifier keys alone, key repeats and keys without a [`Key`] aren't reported, and the
+  /// keys still reach the focused application. The callback runs on the listener thread.
+  /// While the OS sticky keys are on, modifiers tapped one after the other before a key
+  /// count as held with it, see `HotkeyListener::start_learning`. Only one learn mode can be active per registry, starting another one fails with
+  /// [`Error::LearnModeActive`]. Learning needs a keyboard hook, which only Windows has,
+  /// the other platforms fail with `HotkeyError::Unsupported`.
+  pub fn learn_mode<F>(self: &Arc<Self>, callback: F) -> Result<LearnMode>
+  where
+    F: 'static + Fn(Hotkey) + Send,
+  {
+    if self.learning.swap(true, Ordering::SeqCst) {
+      return Err(Error::LearnModeActive);
+    }
+    let started = self
+      .listener()
+      .lock()
+      .unwrap()
+      .start_learning(move |pressed: ListenerHotkey| {
+        if let Some(hotkey) = Hotkey::from_flags(pressed.modifiers, pressed.key).hotkey {
+          callback(hotkey.normalize());
         }
-      }
+      });
+    if let Err(err) = started {
+      self.learning.store(false, Ordering::SeqCst);
+      return Err(err.into());
     }
+    Ok(LearnMode {
+      registry: Arc::downgrade(self),
+    })
   }
 
-  if shifted && !modifiers.contains(&Modifier::SHIFT) {
-    modifiers.push(Modifier::SHIFT);
+  fn held_hotkeys_with(&self, is_held: impl Fn(ListenerHotkey) -> bool) -> Vec<Hotkey> {
+    let mut held = self
+      .hotkeys
+      .lock()
+      .unwrap()
+      .keys()
+      .filter(|hotkey| is_held(hotkey.listener_hotkey()))
+      .cloned()
+      .collect::<Vec<_>>();
+    held.sort_by_key(Hotkey::ui_sort_key);
+    held
   }
 
-  match keys.len() {
-    0 => Err(Error::InvalidHotkey(
-      "hotkey has no key specified".to_string(),
-    )),
-    _ => Ok(Hotkey { modifiers, keys }),
+  /// Returns the registered hotkeys that fired, most recently used first.
+  pub fn most_recently_used(&self, limit: usize) -> Vec<(Hotkey, Instant)> {
+    let hotkeys = self.hotkeys.lock().unwrap();
+    let usage = self.usage.lock().unwrap();
+    let mut used = usage
+      .iter()
+      .filter(|(hotkey, _)| hotkeys.contains_key(hotkey))
+      .collect::<Vec<_>>();
+    used.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.last_sequence));
+    used
+      .into_iter()
+      .take(limit)
+      .map(|(hotkey, usage)| (hotkey.clone(), usage.last_fired))
+      .collect()
   }
-}
 
-#[derive(Debug, Deserialize, Clone, Serialize, PartialEq, Hash, Eq)]
-pub struct Hotkey {
-  pub modifiers: Vec<Modifier>,
-  pub keys: Vec<Key>,
-}
-
-impl Hotkey {
-  pub fn modifiers_as_flag(&self) -> u32 {
-    self.modifiers.iter().fold(0, |acc, x| acc | (*x as u32)) as u32
+  /// Returns the registered hotkeys that fired, most frequently used first.
+  /// Hotkeys fired as often are ordered by their last use.
+  pub fn most_frequently_used(&self, limit: usize) -> Vec<(Hotkey, usize)> {
+    let hotkeys = self.hotkeys.lock().unwrap();
+    let usage = self.usage.lock().unwrap();
+    let mut used = usage
+      .iter()
+      .filter(|(hotkey, _)| hotkeys.contains_key(hotkey))
+      .collect::<Vec<_>>();
+    used.sort_by_key(|(_, usage)| {
+      (
+        std::cmp::Reverse(usage.fire_count),
+        std::cmp::Reverse(usage.last_sequence),
+      )
+    });
+    used
+      .into_iter()
+      .take(limit)
+      .map(|(hotkey, usage)| (hotkey.clone(), usage.fire_count))
+      .collect()
   }
 
-  pub fn keys_as_flag(&self) -> u32 {
-    self.keys.iter().fold(0, |acc, x| acc | (*x as u32)) as u32
+  // The listener's own bookkeeping doesn't notice another application taking a hotkey over,
+  // so every hotkey is released and grabbed again: the OS refuses the grab, e.g. with
+  // `HotkeyError::HotkeyTakenBySystem`, once someone else owns the combination.
+  fn check_registrations(self: &Arc<Self>) -> Vec<Hotkey> {
+    let hotkeys = self.hotkeys.lock().unwrap();
+    if !self.is_active() {
+      // nothing is expected to be held
+      return Vec::new();
+    }
+    let held = self.listener().lock().unwrap().registered_hotkeys();
+    let mut lost = Vec::new();
+    for (hotkey, handlers) in hotkeys.iter() {
+      let listener_hotkey = hotkey.listener_hotkey();
+      // the listener refuses to grab a hotkey it already holds
+      if held.contains(&listener_hotkey) {
+        let _ = self
+          .listener()
+          .lock()
+          .unwrap()
+          .unregister_hotkey(listener_hotkey);
+      }
+      if let Err(err) = self.grab_handlers(hotkey, handlers) {
+        debug!("hotkey {} could not be grabbed again: {}", hotkey, err);
+        lost.push(hotkey.clone());
+      }
+    }
+    lost
   }
 }
 
-#[allow(clippy::upper_case_acronyms)]
-#[derive(
-  Debug, Deserialize, Copy, Clone, Serialize, strum_macros::EnumString, PartialEq, Hash, Eq,
-)]
-#[repr(u32)]
-pub enum Modifier {
-  ALT = modifiers::ALT,
-  ALTGR = modifiers::ALT_GR,
-  CTRL = modifiers::CONTROL,
-  SHIFT = modifiers::SHIFT,
-  SUPER = modifiers::SUPER,
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+  #[error("Hotkey system error: {0}")]
+  System(#[from] HotkeyError),
+  #[error("Hotkey already registered")]
+  HotkeyAlreadyRegistered(Hotkey),
+  #[error("Hotkey is not registered")]
+  HotkeyNotRegistered(Hotkey),
+  #[error("failed to parse hotkey: {0}")]
+  InvalidHotkey(String),
+  #[error("the hotkey listener is already initialized")]
+  ListenerInitialized,
+  #[error("Hotkey {0} has too many handlers")]
+  TooManyHandlers(Hotkey),
+  #[error("Hotkey rejected by the registration policy: {0}")]
+  PolicyRejected(String),
+  /// The hotkey grabs the same OS shortcut as the registered one, see [`Modifier::ALTGR`].
+  #[error("Hotkey {0} is the same shortcut as the registered {1} on this platform")]
+  HotkeyConflict(Hotkey, Hotkey),
+  #[error("no hotkey is registered with {0:?}")]
+  UnknownHotkeyId(HotkeyId),
+  /// The hotkey has a key without an equivalent on this platform, see
+  /// [`Key::is_supported_on_platform`].
+  #[error("key {key} is not supported on {platform}")]
+  Unsupported { key: String, platform: &'static str },
+  /// An entry of [`HotkeyManager::register_map`] failed, e.g. it doesn't parse.
+  #[error("hotkey {accelerator}: {source}")]
+  MapEntry {
+    accelerator: String,
+    #[source]
+    source: Box<Error>,
+  },
+  #[cfg(feature = "keymap")]
+  #[error("invalid keymap document: {0}")]
+  InvalidKeymap(String),
+  #[cfg(feature = "simulate")]
+  #[error("the simulated hotkey was not received")]
+  SimulatedHotkeyTimeout,
+  #[error("learn mode is already active")]
+  LearnModeActive,
 }
 
-impl fmt::Display for Modifier {
-  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{:?}", self)
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Default for HotkeyManager {
+  fn default() -> Self {
+    Self::with_registry(HotkeyRegistry::global())
   }
 }
 
-#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
-#[derive(
-  Debug, Deserialize, Copy, Clone, Serialize, strum_macros::EnumString, PartialEq, Hash, Eq,
-)]
-#[repr(u32)]
-pub enum Key {
-  BACKSPACE = keys::BACKSPACE,
-  TAB = keys::TAB,
-  ENTER = keys::ENTER,
-  CAPSLOCK = keys::CAPS_LOCK,
-  ESCAPE = keys::ESCAPE,
-  SPACE = keys::SPACEBAR,
-  PAGEUP = keys::PAGE_UP,
-  PAGEDOWN = keys::PAGE_DOWN,
-  END = keys::END,
-  HOME = keys::HOME,
-  LEFT = keys::ARROW_LEFT,
-  RIGHT = keys::ARROW_RIGHT,
-  UP = keys::ARROW_UP,
-  DOWN = keys::ARROW_DOWN,
-  PRINTSCREEN = keys::PRINT_SCREEN,
-  #[cfg(not(target_os = "macos"))]
-  INSERT = keys::INSERT,
-  CLEAR = keys::CLEAR,
-  DELETE = keys::DELETE,
-  SCROLLLOCK = keys::SCROLL_LOCK,
-  HELP = keys::HELP,
-  #[cfg(not(target_os = "macos"))]
-  NUMLOCK = keys::NUMLOCK,
-  // Media
-  VOLUMEMUTE = keys::VOLUME_MUTE,
-  VOLUMEDOWN = keys::VOLUME_DOWN,
-  VOLUMEUP = keys::VOLUME_UP,
-  #[cfg(not(target_os = "macos"))]
-  MEDIANEXTTRACK = keys::MEDIA_NEXT,
+impl HotkeyManager {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Creates a manager registering its hotkeys in the given registry instead of the
+  /// process-wide one.
+  pub fn with_registry(registry: Arc<HotkeyRegistry>) -> Self {
+    Self {
+      registered_hotkeys: Vec::new(),
+      id: ID_COUNTER.fetch_add(1, Ordering::Relaxed),
+      registry,
+      weak_hotkeys: Vec::new(),
+      hotkey_ids: Vec::new(),
+      error_sink: Default::default(),
+      #[cfg(feature = "devices")]
+      device_hotkeys: Vec::new(),
+    }
+  }
+
+  /// Determines whether the given hotkey is registered or not.
+  pub fn is_registered(&self, hotkey: &Hotkey) -> bool {
+    self.registered_hotkeys.contains(hotkey)
+  }
+
+  /// Enables or disables the callback of a hotkey registered by this manager, e.g. while
+  /// the user types in a text field.
+  ///
+  /// Unlike [`HotkeyManager::unregister`], the hotkey stays registered with the OS, so no
+  /// other application can grab it meanwhile, and the callback is kept. Presses of a
+  /// disabled hotkey are dropped.
+  pub fn set_enabled(&mut self, hotkey: &Hotkey, enabled: bool) -> Result<()> {
+    if !self.is_registered(hotkey) {
+      return Err(Error::HotkeyNotRegistered(hotkey.clone()));
+    }
+    if let Some(handler) = self
+      .registry
+      .hotkeys
+      .lock()
+      .unwrap()
+      .get_mut(hotkey)
+      .and_then(|entry| entry.get_mut(&self.id))
+    {
+      handler.enabled = enabled;
+    }
+    Ok(())
+  }
+
+  /// Enables or disables the callbacks of every hotkey registered by this manager,
+  /// see [`HotkeyManager::set_enabled`].
+  pub fn set_all_enabled(&mut self, enabled: bool) {
+    self.set_enabled_where(|_| true, enabled)
+  }
+
+  /// Enables or disables the callbacks of the hotkeys registered by this manager that
+  /// match the predicate, e.g. every hotkey without modifiers while a text field is
+  /// focused. See [`HotkeyManager::set_enabled`].
+  pub fn set_enabled_where<P>(&mut self, predicate: P, enabled: bool)
+  where
+    P: Fn(&Hotkey) -> bool,
+  {
+    let mut map = self.registry.hotkeys.lock().unwrap();
+    for hotkey in self.registered_hotkeys.iter().filter(|h| predicate(h)) {
+      if let Some(handler) = map
+        .get_mut(hotkey)
+        .and_then(|entry| entry.get_mut(&self.id))
+      {
+        handler.enabled = enabled;
+      }
+    }
+  }
+
+  /// Returns the hotkeys registered by this manager, in registration order.
+  pub fn registered_hotkeys(&self) -> &[Hotkey] {
+    &self.registered_hotkeys
+  }
+
+  pub fn register<F>(&mut self, hotkey: Hotkey, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(callback),
+        context: None,
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: None,
+        hook: None,
+      },
+    )
+  }
+
+  /// Registers a hotkey like [`HotkeyManager::register`], returning a handle to unregister
+  /// it with [`HotkeyManager::unregister_id`] without keeping the hotkey around.
+  pub fn register_with_id<F>(&mut self, hotkey: Hotkey, callback: F) -> Result<HotkeyId>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register(hotkey.clone(), callback)?;
+    let id = HotkeyId(HOTKEY_ID_COUNTER.fetch_add(1, Ordering::Relaxed));
+    self.hotkey_ids.push((id, hotkey));
+    Ok(id)
+  }
+
+  /// Unregisters a hotkey registered with [`HotkeyManager::register_with_id`].
+  pub fn unregister_id(&mut self, id: HotkeyId) -> Result<()> {
+    let hotkey = match self.hotkey_ids.iter().find(|(i, _)| *i == id) {
+      Some((_, hotkey)) => hotkey.clone(),
+      None => return Err(Error::UnknownHotkeyId(id)),
+    };
+    self.unregister(&hotkey)
+  }
+
+  /// Registers a hotkey whose callback can fail. Errors go to the sink set with
+  /// [`HotkeyManager::on_error`], or are logged if there is none.
+  pub fn register_fallible<F>(&mut self, hotkey: Hotkey, mut callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() -> std::result::Result<(), Box<dyn std::error::Error + Send>> + Send,
+  {
+    let sink = self.error_sink.clone();
+    self.register_with_hotkey(hotkey, move |hotkey| {
+      if let Err(err) = callback() {
+        match &*sink.lock().unwrap() {
+          Some(sink) => sink(hotkey, err.as_ref()),
+          None => error!("hotkey {} handler failed: {}", hotkey, err),
+        }
+      }
+    })
+  }
+
+  /// Sets the sink receiving the errors of the handlers registered with
+  /// [`HotkeyManager::register_fallible`], replacing the previous one. It runs on the
+  /// listener thread.
+  pub fn on_error<F>(&mut self, sink: F)
+  where
+    F: 'static + Fn(&Hotkey, &(dyn std::error::Error + Send)) + Send,
+  {
+    self.error_sink.lock().unwrap().replace(Box::new(sink));
+  }
+
+  /// Registers a hotkey with a callback receiving it, so a single handler shared by
+  /// several hotkeys can tell which one was pressed.
+  pub fn register_with_hotkey<F>(&mut self, hotkey: Hotkey, mut callback: F) -> Result<()>
+  where
+    F: 'static + FnMut(&Hotkey) + Send,
+  {
+    let pressed = hotkey.clone();
+    self.register(hotkey, move || callback(&pressed))
+  }
+
+  /// Registers a hotkey whose callback is told both when it's pressed and when it's
+  /// released, e.g. for push-to-talk.
+  ///
+  /// X11 and macOS report the release of the key itself, Windows also counts releasing a
+  /// modifier first, see [`HotkeyListener::register_hotkey_with_state`].
+  /// Presses and releases are reported to every handler of the hotkey, and registering
+  /// the first handler with state grabs the hotkey from the OS again.
+  pub fn register_with_state<F>(&mut self, hotkey: Hotkey, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut(KeyState) + Send,
+  {
+    let callback = Arc::new(Mutex::new(callback));
+    let released = callback.clone();
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(move || (callback.lock().unwrap())(KeyState::Pressed)),
+        context: None,
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: Some(Box::new(move || {
+          (released.lock().unwrap())(KeyState::Released)
+        })),
+        hook: None,
+      },
+    )
+  }
+
+  /// Registers a hotkey calling `on_press` when it goes down and `on_release` when it goes
+  /// up, built on [`HotkeyManager::register_with_state`].
+  ///
+  /// Presses while the hotkey is held are ignored, so the callbacks always alternate. The
+  /// OS may never report a release, e.g. when the focus changes while the key is held;
+  /// with a `release_timeout`, `on_release` is called anyway once the hotkey has been held
+  /// that long. The timeout runs `on_release` on its own thread.
+  pub fn register_momentary<P, R>(
+    &mut self,
+    hotkey: Hotkey,
+    on_press: P,
+    on_release: R,
+    release_timeout: Option<Duration>,
+  ) -> Result<()>
+  where
+    P: 'static + FnMut() + Send,
+    R: 'static + FnMut() + Send,
+  {
+    let state = Arc::new(Mutex::new(Momentary {
+      held: false,
+      press: 0,
+      cancel: None,
+      on_press,
+      on_release,
+    }));
+    self.register_with_state(hotkey, move |key_state| {
+      let mut momentary = state.lock().unwrap();
+      match key_state {
+        KeyState::Pressed if !momentary.held => {
+          momentary.held = true;
+          momentary.press += 1;
+          (momentary.on_press)();
+          if let Some(timeout) = release_timeout {
+            let (tx, rx) = channel::<()>();
+            momentary.cancel = Some(tx);
+            let press = momentary.press;
+            let state = state.clone();
+            thread::spawn(move || {
+              if rx.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                let mut momentary = state.lock().unwrap();
+                if momentary.held && momentary.press == press {
+                  debug!("no release within {:?}, releasing the hotkey", timeout);
+                  momentary.held = false;
+                  momentary.cancel = None;
+                  (momentary.on_release)();
+                }
+              }
+            });
+          }
+        }
+        KeyState::Released if momentary.held => {
+          momentary.held = false;
+          momentary.cancel = None;
+          (momentary.on_release)();
+        }
+        _ => {}
+      }
+    })
+  }
+
+  /// Registers a hotkey like [`HotkeyManager::register`], choosing whether holding it
+  /// fires the callback again at the OS key repeat rate.
+  ///
+  /// Hotkeys don't repeat by default, see [`ListenerConfig::no_repeat`]. Only Windows
+  /// reports key repeats, the hotkey fires once per press on Linux and macOS. Repeats are
+  /// requested when the hotkey is grabbed from the OS, so `allow_repeat` has no effect if
+  /// another manager of the registry already registered the same hotkey.
+  pub fn register_with_repeat<F>(
+    &mut self,
+    hotkey: Hotkey,
+    allow_repeat: bool,
+    callback: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(callback),
+        context: None,
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat,
+        no_repeat: false,
+        on_release: None,
+        hook: None,
+      },
+    )
+  }
+
+  /// Registers a hotkey that fires once per press even if the listener allows key repeats,
+  /// see [`ListenerConfig::no_repeat`], e.g. so holding a bare `VOLUMEUP` doesn't flood the
+  /// callback.
+  ///
+  /// Only Windows reports key repeats, where the hotkey is registered with `MOD_NOREPEAT`.
+  /// Like [`HotkeyManager::register_with_repeat`], this only affects how the hotkey is
+  /// grabbed from the OS, and takes precedence over the handlers allowing repeats.
+  pub fn register_no_repeat<F>(&mut self, hotkey: Hotkey, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(callback),
+        context: None,
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: true,
+        on_release: None,
+        hook: None,
+      },
+    )
+  }
+
+  /// Registers a hotkey with a user-facing label describing its action, listed by
+  /// [`HotkeyManager::to_cheatsheet`] and [`cheatsheet`].
+  pub fn register_labeled<F>(&mut self, label: &str, hotkey: Hotkey, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(callback),
+        context: None,
+        label: Some(label.to_string()),
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: None,
+        hook: None,
+      },
+    )
+  }
+
+  /// Renders the labeled hotkeys of this manager as a cheat sheet, in registration order.
+  pub fn to_cheatsheet(&self, format: CheatsheetFormat) -> String {
+    let map = self.registry.hotkeys.lock().unwrap();
+    let entries = self
+      .registered_hotkeys
+      .iter()
+      .filter_map(|hotkey| {
+        let label = map.get(hotkey)?.get(&self.id)?.label.clone()?;
+        Some((label, hotkey.normalize().to_string()))
+      })
+      .collect::<Vec<_>>();
+    render_cheatsheet(&entries, format)
+  }
+
+  /// Registers a hotkey tagged with a named context.
+  ///
+  /// The callback only fires while the context is active, see [`set_context_active`].
+  /// Contexts are global, so a single call toggles the hotkeys of every manager using it.
+  pub fn register_in_context<F>(&mut self, context: &str, hotkey: Hotkey, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(callback),
+        context: Some(context.to_string()),
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: None,
+        hook: None,
+      },
+    )
+  }
+
+  /// Registers `alias_hotkey` to do whatever `target` does, e.g. making `F2` act like `F5`.
+  ///
+  /// The handlers of `target` are looked up on every press, so the alias follows the live
+  /// target: handlers registered or unregistered later, by any manager of the registry,
+  /// are picked up, and the alias does nothing while `target` has no handlers. Aliases
+  /// aren't followed transitively, so aliasing a hotkey to an alias does nothing.
+  pub fn alias(&mut self, alias_hotkey: Hotkey, target: &Hotkey) -> Result<()> {
+    self.register_handler(
+      alias_hotkey,
+      Handler {
+        callback: Box::new(|| {}),
+        context: None,
+        label: None,
+        alias: Some(target.clone()),
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: None,
+        hook: None,
+      },
+    )
+  }
+
+  /// Registers a hotkey that only fires while the given window is focused, e.g. a
+  /// shortcut of a tool window. `window` is the raw `HWND` of the window.
+  ///
+  /// No platform supports this yet, it always fails with `HotkeyError::Unsupported`.
+  /// `RegisterHotKey` only binds a hotkey to a window created by the calling thread and
+  /// posts its `WM_HOTKEY` to that window instead of the listener thread, while filtering
+  /// a global hotkey on the focused window would take the key away from every other
+  /// application.
+  pub fn register_for_window<F>(
+    &mut self,
+    _hotkey: Hotkey,
+    _window: isize,
+    _callback: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    Err(Error::System(HotkeyError::Unsupported))
+  }
+
+  /// Registers a hotkey matched by a low-level keyboard hook instead of being grabbed from
+  /// the OS. With `suppress`, its key events are swallowed like those of other hotkeys;
+  /// without it they still reach the focused application, e.g. for an overlay reacting to
+  /// a key without blocking normal typing.
+  ///
+  /// Only Windows has such a hook, the other platforms fail with
+  /// `HotkeyError::Unsupported`. The callback runs inside the hook, where Windows drops
+  /// the hook if the callback takes too long. Other handlers of the same hotkey must be
+  /// registered with the same `suppress` through this method, otherwise registering fails
+  /// with [`Error::HotkeyAlreadyRegistered`].
+  pub fn register_with_hook<F>(&mut self, hotkey: Hotkey, suppress: bool, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_handler(
+      hotkey,
+      Handler {
+        callback: Box::new(callback),
+        context: None,
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: None,
+        hook: Some(suppress),
+      },
+    )
+  }
+
+  /// Registers a hotkey that only fires when pressed on the given keyboard, see
+  /// [`list_keyboards`].
+  ///
+  /// Only Linux and Windows support this, macOS fails with `HotkeyError::Unsupported`. The
+  /// hotkey isn't grabbed, so the press still reaches the focused application. See the
+  /// `devices` module of `tauri-hotkey-sys` for the platform details.
+  #[cfg(feature = "devices")]
+  pub fn register_on_device<F>(
+    &mut self,
+    device_id: &str,
+    hotkey: Hotkey,
+    mut callback: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    if self
+      .device_hotkeys
+      .iter()
+      .any(|(device, h, _)| device == device_id && *h == hotkey)
+    {
+      return Err(Error::HotkeyAlreadyRegistered(hotkey));
+    }
+    let watch =
+      tauri_hotkey_sys::devices::watch_device(device_id, hotkey.listener_hotkey(), move || {
+        if !DISPATCH_PAUSED.load(Ordering::SeqCst) {
+          callback();
+        }
+      })?;
+    info!("register hotkey {} on {}", hotkey, device_id);
+    self
+      .device_hotkeys
+      .push((device_id.to_string(), hotkey, watch));
+    Ok(())
+  }
+
+  /// Unregisters a hotkey registered with [`HotkeyManager::register_on_device`].
+  #[cfg(feature = "devices")]
+  pub fn unregister_on_device(&mut self, device_id: &str, hotkey: &Hotkey) -> Result<()> {
+    match self
+      .device_hotkeys
+      .iter()
+      .position(|(device, h, _)| device == device_id && h == hotkey)
+    {
+      Some(index) => {
+        self.device_hotkeys.remove(index);
+        info!("unregister hotkey {} on {}", hotkey, device_id);
+        Ok(())
+      }
+      None => Err(Error::HotkeyNotRegistered(hotkey.clone())),
+    }
+  }
+
+  /// Registers a hotkey without a callback, returning a flag that can be polled instead,
+  /// e.g. once per frame in a game loop.
+  pub fn register_polled(&mut self, hotkey: Hotkey) -> Result<PressFlag> {
+    let flag = PressFlag::default();
+    let pressed = flag.0.clone();
+    self.register(hotkey, move || pressed.store(true, Ordering::Release))?;
+    Ok(flag)
+  }
+
+  /// Registers a hotkey that fires at most once per focus session of the app, i.e. on its
+  /// first press after the app gains focus, and never while the app is unfocused.
+  ///
+  /// The crate doesn't watch windows itself: the app reports its focus changes with
+  /// [`set_app_focused`], typically from the focus events of its main window.
+  pub fn register_once_per_focus<F>(&mut self, hotkey: Hotkey, mut callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    let mut fired_session = None;
+    self.register(hotkey, move || {
+      let session = {
+        let focus = APP_FOCUS.lock().unwrap();
+        if !focus.focused || fired_session == Some(focus.session) {
+          return;
+        }
+        focus.session
+      };
+      fired_session = Some(session);
+      callback();
+    })
+  }
+
+  /// Registers a hotkey whose callback borrows a resource held weakly, e.g. UI state.
+  ///
+  /// Once every strong reference to the resource is dropped, the callback stops firing.
+  /// The hotkey itself is released the next time a hotkey is registered with this manager,
+  /// or with [`HotkeyManager::release_dropped`]. The callback runs on the listener thread,
+  /// where the resource is dropped if its last strong reference goes away meanwhile.
+  pub fn register_weak<T, F>(
+    &mut self,
+    hotkey: Hotkey,
+    resource: &Arc<T>,
+    mut callback: F,
+  ) -> Result<()>
+  where
+    T: 'static + Send + Sync,
+    F: 'static + FnMut(&T) + Send,
+  {
+    let weak = Arc::downgrade(resource);
+    self.register(hotkey.clone(), move || {
+      if let Some(resource) = weak.upgrade() {
+        callback(&resource);
+      }
+    })?;
+    let weak = Arc::downgrade(resource);
+    self
+      .weak_hotkeys
+      .push((hotkey, Box::new(move || weak.strong_count() > 0)));
+    Ok(())
+  }
+
+  /// Unregisters the hotkeys registered with [`HotkeyManager::register_weak`] whose
+  /// resource was dropped, returning them.
+  pub fn release_dropped(&mut self) -> Result<Vec<Hotkey>> {
+    let dropped: Vec<Hotkey> = self
+      .weak_hotkeys
+      .iter()
+      .filter(|(_, alive)| !alive())
+      .map(|(hotkey, _)| hotkey.clone())
+      .collect();
+    for hotkey in &dropped {
+      self.unregister(hotkey)?;
+    }
+    Ok(dropped)
+  }
+
+  /// Registers a hotkey whose presses are sent to the returned receiver instead of running a
+  /// callback on the listener thread, e.g. for a thread blocked on `recv` or a loop polling
+  /// several receivers.
+  pub fn register_channel(&mut self, hotkey: Hotkey) -> Result<Receiver<()>> {
+    let (tx, rx) = channel();
+    self.register(hotkey, move || {
+      let _ = tx.send(());
+    })?;
+    Ok(rx)
+  }
+
+  /// Registers a hotkey like [`HotkeyManager::register_channel`], sending the hotkey itself
+  /// and calling `waker` after every press.
+  ///
+  /// The waker nudges an event loop to drain the receiver on its own thread, which is
+  /// useful with UI toolkits that require the main thread. With winit or tao, create an
+  /// `EventLoopProxy` with `event_loop.create_proxy()`, call `proxy.send_event(..)` from the
+  /// waker and drain the receiver when the user event arrives.
+  pub fn register_channel_with_waker<W>(
+    &mut self,
+    hotkey: Hotkey,
+    waker: W,
+  ) -> Result<Receiver<Hotkey>>
+  where
+    W: 'static + Fn() + Send,
+  {
+    let (tx, rx) = channel();
+    let pressed = hotkey.clone();
+    self.register(hotkey, move || {
+      if tx.send(pressed.clone()).is_ok() {
+        waker();
+      }
+    })?;
+    Ok(rx)
+  }
+
+  /// Registers a hotkey whose callback only fires while the local time is inside the schedule.
+  #[cfg(feature = "chrono")]
+  pub fn register_scheduled<F>(
+    &mut self,
+    hotkey: Hotkey,
+    schedule: Schedule,
+    callback: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_scheduled_with_clock(hotkey, schedule, || chrono::Local::now().time(), callback)
+  }
+
+  #[cfg(feature = "chrono")]
+  fn register_scheduled_with_clock<C, F>(
+    &mut self,
+    hotkey: Hotkey,
+    schedule: Schedule,
+    clock: C,
+    mut callback: F,
+  ) -> Result<()>
+  where
+    C: 'static + Fn() -> chrono::NaiveTime + Send,
+    F: 'static + FnMut() + Send,
+  {
+    self.register(hotkey, move || {
+      if schedule.is_active_at(clock()) {
+        callback();
+      }
+    })
+  }
+
+  /// Registers hotkeys that must all be pressed, in any order, within a rolling time window
+  /// for the callback to fire, e.g. `CTRL+A` and `CTRL+B` within 500ms.
+  ///
+  /// Each hotkey's last press is tracked, pressing it again only refreshes its time and
+  /// listing it twice doesn't require two presses. The callback fires on the press
+  /// completing the combo, after which every press is forgotten, so the next combo needs
+  /// all hotkeys again. Either all hotkeys are registered or none of them are.
+  pub fn register_combo_window<F>(
+    &mut self,
+    hotkeys: Vec<Hotkey>,
+    within: Duration,
+    callback: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_combo_window_with_clock(hotkeys, within, Instant::now, callback)
+  }
+
+  fn register_combo_window_with_clock<C, F>(
+    &mut self,
+    hotkeys: Vec<Hotkey>,
+    within: Duration,
+    clock: C,
+    callback: F,
+  ) -> Result<()>
+  where
+    C: 'static + Fn() -> Instant + Send + Sync,
+    F: 'static + FnMut() + Send,
+  {
+    let mut unique = Vec::new();
+    for hotkey in hotkeys {
+      if !unique.contains(&hotkey) {
+        unique.push(hotkey);
+      }
+    }
+    if unique.is_empty() {
+      return Err(Error::InvalidHotkey("combo has no hotkeys".to_string()));
+    }
+    let count = unique.len();
+    let clock = Arc::new(clock);
+    let state = Arc::new(Mutex::new((HashMap::<Hotkey, Instant>::new(), callback)));
+    self.register_alternatives(unique, move |hotkey| {
+      let now = clock();
+      let mut state = state.lock().unwrap();
+      let (pressed, callback) = &mut *state;
+      pressed.insert(hotkey.clone(), now);
+      pressed.retain(|_, at| now.duration_since(*at) <= within);
+      if pressed.len() == count {
+        pressed.clear();
+        callback();
+      }
+    })
+  }
+
+  /// Registers a hotkey firing only when pressed twice within `within`, e.g. double-tapping
+  /// `CTRL+SPACE` to open a palette.
+  ///
+  /// The press completing a double press is forgotten, so a third press starts over.
+  pub fn register_double<F>(&mut self, hotkey: Hotkey, within: Duration, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_double_with_clock(hotkey, within, Instant::now, callback)
+  }
+
+  fn register_double_with_clock<C, F>(
+    &mut self,
+    hotkey: Hotkey,
+    within: Duration,
+    clock: C,
+    mut callback: F,
+  ) -> Result<()>
+  where
+    C: 'static + Fn() -> Instant + Send,
+    F: 'static + FnMut() + Send,
+  {
+    let mut last_press: Option<Instant> = None;
+    self.register(hotkey, move || {
+      let now = clock();
+      match last_press.take() {
+        Some(at) if now.duration_since(at) <= within => callback(),
+        _ => last_press = Some(now),
+      }
+    })
+  }
+
+  /// Registers a sequence of hotkeys pressed one after the other, e.g. `CTRL+K` then
+  /// `CTRL+S` like in VS Code, firing the callback on the last one if each hotkey follows
+  /// the previous one within `timeout`.
+  ///
+  /// Only the first hotkey is registered up front. Callbacks run while the registry is
+  /// locked, so once it fires, a thread of the sequence grabs the hotkeys it waits for
+  /// next, and releases them when the sequence completes, breaks or times out. They only
+  /// reach other applications outside of the sequence, and a following hotkey pressed
+  /// before the thread grabbed it isn't seen.
+  ///
+  /// Pressing a grabbed hotkey out of order restarts the sequence from the longest prefix
+  /// the last presses still match, e.g. `K K K S` completes `K K S`. Other keys aren't
+  /// grabbed, so pressing them doesn't break the sequence.
+  ///
+  /// A binding of the first hotkey by this manager fails with
+  /// [`Error::HotkeyAlreadyRegistered`]. Bindings of the following hotkeys, and bindings
+  /// of other managers, keep firing alongside the sequence while it grabs them.
+  pub fn register_sequence<F>(
+    &mut self,
+    sequence: Sequence,
+    timeout: Duration,
+    callback: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_sequence_with_clock(sequence, timeout, Instant::now, callback)
+  }
+
+  fn register_sequence_with_clock<C, F>(
+    &mut self,
+    sequence: Sequence,
+    timeout: Duration,
+    clock: C,
+    callback: F,
+  ) -> Result<()>
+  where
+    C: 'static + Fn() -> Instant + Send + Sync,
+    F: 'static + FnMut() + Send,
+  {
+    let hotkeys = sequence.hotkeys;
+    if hotkeys.is_empty() {
+      return Err(Error::InvalidHotkey("sequence has no hotkeys".to_string()));
+    }
+    if let Some(key) = hotkeys
+      .iter()
+      .flat_map(|hotkey| &hotkey.keys)
+      .find(|key| !key.is_supported_on_platform())
+    {
+      return Err(Error::Unsupported {
+        key: key.to_string(),
+        platform: std::env::consts::OS,
+      });
+    }
+    // for each prefix, the length of its longest proper prefix that's also its suffix, so a
+    // mismatch falls back to the longest prefix that still matches (the KMP failure function)
+    let mut fallback = vec![0; hotkeys.len()];
+    let mut matched = 0;
+    for i in 1..hotkeys.len() {
+      while matched > 0 && hotkeys[i] != hotkeys[matched] {
+        matched = fallback[matched - 1];
+      }
+      if hotkeys[i] == hotkeys[matched] {
+        matched += 1;
+      }
+      fallback[i] = matched;
+    }
+    // for each number of hotkeys pressed so far, the hotkeys changing it when pressed: the
+    // next one and those a mismatch falls back to, apart from the first which stays grabbed
+    let first = hotkeys[0].clone();
+    let waiting_for = (0..hotkeys.len())
+      .map(|progress| {
+        let mut waiting = Vec::new();
+        let mut matched = progress;
+        while matched > 0 {
+          if hotkeys[matched] != first && !waiting.contains(&hotkeys[matched]) {
+            waiting.push(hotkeys[matched].clone());
+          }
+          matched = fallback[matched - 1];
+        }
+        waiting
+      })
+      .collect::<Vec<_>>();
+
+    let (arm, requests) = channel();
+    let state = Mutex::new(SequenceState {
+      progress: 0,
+      last: clock(),
+      callback,
+      arm,
+    });
+    let press: Arc<SequencePress> = Arc::new(move |hotkey: &Hotkey| {
+      let now = clock();
+      let mut state = state.lock().unwrap();
+      if now.duration_since(state.last) > timeout {
+        state.progress = 0;
+      }
+      while state.progress > 0 && hotkeys[state.progress] != *hotkey {
+        state.progress = fallback[state.progress - 1];
+      }
+      if hotkeys[state.progress] == *hotkey {
+        state.progress += 1;
+      }
+      state.last = now;
+      let completed = state.progress == hotkeys.len();
+      if completed {
+        state.progress = 0;
+      }
+      // the thread only exits once the sequence is unregistered
+      let _ = state.arm.send(waiting_for[state.progress].clone());
+      if completed {
+        (state.callback)();
+      }
+    });
+
+    let pressed = press.clone();
+    let first_ = first.clone();
+    self.register(first, move || pressed(&first_))?;
+    let registry = Arc::downgrade(&self.registry);
+    let id = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let press = Arc::downgrade(&press);
+    thread::spawn(move || arm_sequence(registry, id, press, requests, timeout));
+    Ok(())
+  }
+
+  /// Registers several alternative hotkeys for the same action, e.g. `UP` and `W`.
+  /// The callback receives the hotkey that was pressed.
+  ///
+  /// Either all hotkeys are registered or none of them are.
+  pub fn register_alternatives<F>(&mut self, hotkeys: Vec<Hotkey>, callback: F) -> Result<()>
+  where
+    F: 'static + FnMut(&Hotkey) + Clone + Send,
+  {
+    let mut registered = Vec::new();
+    for hotkey in hotkeys {
+      if let Err(err) = self.register_with_hotkey(hotkey.clone(), callback.clone()) {
+        for hotkey in registered {
+          let _ = self.unregister(&hotkey);
+        }
+        return Err(err);
+      }
+      registered.push(hotkey);
+    }
+    Ok(())
+  }
+
+  /// Registers the same callback for several hotkeys, e.g. `ESCAPE` or `Q` to close an
+  /// overlay, returning their ids for [`HotkeyManager::unregister_id`].
+  ///
+  /// Either all hotkeys are registered or none of them are.
+  pub fn register_many<F>(&mut self, hotkeys: Vec<Hotkey>, callback: F) -> Result<Vec<HotkeyId>>
+  where
+    F: 'static + FnMut() + Clone + Send,
+  {
+    let mut ids = Vec::new();
+    for hotkey in hotkeys {
+      match self.register_with_id(hotkey, callback.clone()) {
+        Ok(id) => ids.push(id),
+        Err(err) => {
+          for id in ids {
+            let _ = self.unregister_id(id);
+          }
+          return Err(err);
+        }
+      }
+    }
+    Ok(ids)
+  }
+
+  /// Registers hotkeys defined in a configuration table keyed by action name, with a
+  /// single dispatcher receiving the action of the pressed hotkey.
+  ///
+  /// The entries are registered with their labels, see [`HotkeyManager::register_labeled`].
+  /// Either all entries are registered or none of them are.
+  pub fn register_entries<F>(
+    &mut self,
+    entries: &HashMap<String, HotkeyEntry>,
+    dispatcher: F,
+  ) -> Result<()>
+  where
+    F: 'static + FnMut(&str) + Clone + Send,
+  {
+    let mut parsed = entries
+      .iter()
+      .map(|(action, entry)| Ok((action, entry, parse_hotkey(&entry.accelerator)?)))
+      .collect::<Result<Vec<_>>>()?;
+    parsed.sort_by_key(|(action, _, _)| *action);
+
+    let mut registered = Vec::new();
+    for (action, entry, hotkey) in parsed {
+      let mut dispatcher = dispatcher.clone();
+      let action = action.clone();
+      if let Err(err) =
+        self.register_labeled(&entry.label, hotkey.clone(), move || dispatcher(&action))
+      {
+        for hotkey in registered {
+          let _ = self.unregister(&hotkey);
+        }
+        return Err(err);
+      }
+      registered.push(hotkey);
+    }
+    Ok(())
+  }
+
+  /// Registers a keymap of hotkey strings, parsed with [`parse_hotkey`], and their
+  /// callbacks.
+  ///
+  /// Either all entries are registered or none of them are. A failing entry is reported
+  /// with [`Error::MapEntry`], naming its hotkey string.
+  pub fn register_map<F>(&mut self, map: HashMap<String, F>) -> Result<()>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    let entry_error = |accelerator: &str, err| Error::MapEntry {
+      accelerator: accelerator.to_string(),
+      source: Box::new(err),
+    };
+    let mut parsed = Vec::new();
+    for (accelerator, callback) in map {
+      match parse_hotkey(&accelerator) {
+        Ok(hotkey) => parsed.push((accelerator, hotkey, callback)),
+        Err(err) => return Err(entry_error(&accelerator, err)),
+      }
+    }
+    parsed.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+
+    let mut registered = Vec::new();
+    for (accelerator, hotkey, callback) in parsed {
+      if let Err(err) = self.register(hotkey.clone(), callback) {
+        for hotkey in registered {
+          let _ = self.unregister(&hotkey);
+        }
+        return Err(entry_error(&accelerator, err));
+      }
+      registered.push(hotkey);
+    }
+    Ok(())
+  }
+
+  /// Registers every function key available on the platform with a single callback,
+  /// which receives the key that was pressed.
+  ///
+  /// Either all function keys are registered or none of them are.
+  pub fn register_function_keys<F>(&mut self, mut callback: F) -> Result<()>
+  where
+    F: 'static + FnMut(Key) + Clone + Send,
+  {
+    let hotkeys = FUNCTION_KEYS
+      .iter()
+      .map(|key| Hotkey {
+        modifiers: Vec::new(),
+        keys: vec![*key],
+      })
+      .collect();
+    self.register_alternatives(hotkeys, move |hotkey| callback(hotkey.keys[0]))
+  }
+
+  /// Returns the hotkey the OS actually bound for a hotkey registered by this manager.
+  ///
+  /// This is the requested hotkey on Windows, macOS and X11, but may differ on backends where
+  /// the compositor assigns the trigger or lets the user rebind it. Returns `None` if the
+  /// hotkey isn't registered by this manager or isn't currently held, e.g. while the registry
+  /// is inactive.
+  pub fn effective_accelerator(&self, hotkey: &Hotkey) -> Option<Hotkey> {
+    if !self.is_registered(hotkey) {
+      return None;
+    }
+    let requested = hotkey.listener_hotkey();
+    let effective = self
+      .registry
+      .listener()
+      .lock()
+      .unwrap()
+      .effective_hotkey(requested)?;
+    if effective == requested {
+      // keeps logical modifiers such as `COMMAND_OR_CONTROL`
+      return Some(hotkey.clone());
+    }
+    Hotkey::from_flags(effective.modifiers, effective.key).hotkey
+  }
+
+  /// Returns the hotkeys currently bound by this manager, e.g. to revert a settings change later.
+  ///
+  /// Callbacks can't be captured, so the snapshot only describes which hotkeys are bound.
+  pub fn snapshot_config(&self) -> Vec<Hotkey> {
+    self.registered_hotkeys.clone()
+  }
+
+  /// Compares the hotkeys bound by this manager with another configuration,
+  /// e.g. one returned by [`HotkeyManager::snapshot_config`].
+  ///
+  /// `added` holds the hotkeys that would have to be registered and `removed` the ones
+  /// that would have to be unregistered to match `other`.
+  pub fn diff_config(&self, other: &[Hotkey]) -> ConfigDiff {
+    ConfigDiff {
+      added: other
+        .iter()
+        .filter(|hotkey| !self.is_registered(hotkey))
+        .cloned()
+        .collect(),
+      removed: self
+        .registered_hotkeys
+        .iter()
+        .filter(|hotkey| !other.contains(hotkey))
+        .cloned()
+        .collect(),
+    }
+  }
+
+  fn register_handler(&mut self, hotkey: Hotkey, handler: Handler) -> Result<()> {
+    if let Err(err) = self.release_dropped() {
+      error!("failed to release hotkeys of dropped resources {:?}", err);
+    }
+    if self.is_registered(&hotkey) {
+      return Err(Error::HotkeyAlreadyRegistered(hotkey));
+    }
+    if let Some(key) = hotkey
+      .keys
+      .iter()
+      .find(|key| !key.is_supported_on_platform())
+    {
+      return Err(Error::Unsupported {
+        key: key.to_string(),
+        platform: std::env::consts::OS,
+      });
+    }
+    if let Some(policy) = self.registry.policy.lock().unwrap().as_ref() {
+      policy(&hotkey).map_err(Error::PolicyRejected)?;
+    }
+
+    let max_handlers = self.registry.config.lock().unwrap().max_handlers_per_hotkey;
+    let hotkey_ = hotkey.clone();
+    let mut hotkeys = self.registry.hotkeys.lock().unwrap();
+    if !hotkeys.contains_key(&hotkey) {
+      let flags = hotkey.listener_hotkey();
+      if let Some(existing) = hotkeys.keys().find(|h| h.listener_hotkey() == flags) {
+        return Err(Error::HotkeyConflict(hotkey, existing.clone()));
+      }
+    }
+    match hotkeys.entry(hotkey.clone()) {
+      Entry::Occupied(mut entry) => {
+        let entry = entry.get_mut();
+        if matches!(max_handlers, Some(max) if entry.len() >= max) {
+          return Err(Error::TooManyHandlers(hotkey));
+        }
+        // the hotkey is either grabbed or hooked, suppressing its key events or not
+        if entry.values().any(|h| h.hook != handler.hook) {
+          return Err(Error::HotkeyAlreadyRegistered(hotkey));
+        }
+        let grabbed_with_release = entry.values().any(|h| h.on_release.is_some());
+        if handler.on_release.is_some() && !grabbed_with_release && self.registry.is_active() {
+          // grabbed again, this time reporting releases
+          let allow_repeat = entry.values().any(|h| h.allow_repeat);
+          let no_repeat = entry.values().any(|h| h.no_repeat);
+          self
+            .registry
+            .listener()
+            .lock()
+            .unwrap()
+            .unregister_hotkey(hotkey.listener_hotkey())?;
+          if let Err(err) = self
+            .registry
+            .grab(&hotkey, allow_repeat, no_repeat, true, None)
+          {
+            // the existing handlers keep their grab
+            if let Err(err) = self
+              .registry
+              .grab(&hotkey, allow_repeat, no_repeat, false, None)
+            {
+              error!("failed to grab hotkey {} again {:?}", hotkey, err);
+            }
+            return Err(err);
+          }
+        }
+        entry.insert(self.id, handler);
+      }
+      Entry::Vacant(entry) => {
+        if max_handlers == Some(0) {
+          return Err(Error::TooManyHandlers(hotkey));
+        }
+        if self.registry.is_active() {
+          self.registry.grab(
+            &hotkey,
+            handler.allow_repeat,
+            handler.no_repeat,
+            handler.on_release.is_some(),
+            handler.hook,
+          )?;
+        }
+        let mut new_map = HashMap::new();
+        new_map.insert(self.id, handler);
+        entry.insert(new_map);
+      }
+    }
+
+    info!("register hotkey {}", hotkey_);
+    self.registered_hotkeys.push(hotkey_);
+
+    Ok(())
+  }
+
+  pub fn unregister(&mut self, hotkey: &Hotkey) -> Result<()> {
+    match self.registered_hotkeys.iter().position(|h| h == hotkey) {
+      Some(index) => {
+        self.registered_hotkeys.remove(index);
+      }
+      None => return Err(Error::HotkeyNotRegistered(hotkey.clone())),
+    }
+    self.weak_hotkeys.retain(|(h, _)| h != hotkey);
+    self.hotkey_ids.retain(|(_, h)| h != hotkey);
+
+    match self.registry.hotkeys.lock().unwrap().entry(hotkey.clone()) {
+      std::collections::hash_map::Entry::Occupied(mut occ_entry) => {
+        let entry = occ_entry.get_mut();
+        if entry.remove(&self.id).is_none() {
+          panic!("should never be vacant");
+        }
+        if entry.is_empty() {
+          occ_entry.remove_entry();
+          if self.registry.is_active() {
+            self
+              .registry
+              .listener()
+              .lock()
+              .unwrap()
+              .unregister_hotkey(hotkey.listener_hotkey())?;
+          }
+        }
+      }
+      std::collections::hash_map::Entry::Vacant(_) => {
+        panic!("should never be vacant");
+      }
+    }
+    info!("unregister hotkey {}", hotkey);
+    Ok(())
+  }
+
+  /// Unregisters every hotkey of this manager.
+  ///
+  /// The global map is locked once and the hotkeys no other manager uses are released
+  /// in a single batch.
+  pub fn unregister_all(&mut self) -> Result<()> {
+    let hotkeys = std::mem::take(&mut self.registered_hotkeys);
+    self.weak_hotkeys.clear();
+    self.hotkey_ids.clear();
+    #[cfg(feature = "devices")]
+    self.device_hotkeys.clear();
+    let mut map = self.registry.hotkeys.lock().unwrap();
+    let mut released = Vec::new();
+    for hotkey in &hotkeys {
+      if let Entry::Occupied(mut entry) = map.entry(hotkey.clone()) {
+        if entry.get_mut().remove(&self.id).is_none() {
+          continue;
+        }
+        info!("unregister hotkey {}", hotkey);
+        if entry.get().is_empty() {
+          entry.remove_entry();
+          released.push(hotkey.listener_hotkey());
+        }
+      }
+    }
+    if self.registry.is_active() {
+      self
+        .registry
+        .listener()
+        .lock()
+        .unwrap()
+        .unregister_hotkeys(&released)?;
+    }
+    Ok(())
+  }
+}
+
+/// Hotkeys pressed one after the other, e.g. `CTRL+K` then `CTRL+S`, see
+/// [`HotkeyManager::register_sequence`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sequence {
+  pub hotkeys: Vec<Hotkey>,
+}
+
+impl Sequence {
+  pub fn new(hotkeys: Vec<Hotkey>) -> Self {
+    Self { hotkeys }
+  }
+}
+
+/// A hotkey defined in a configuration file, see [`HotkeyManager::register_entries`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HotkeyEntry {
+  /// The hotkey string, e.g. `CmdOrCtrl+P`.
+  pub accelerator: String,
+  /// User-facing description of the action.
+  pub label: String,
+}
+
+/// Difference between two hotkey configurations, see [`HotkeyManager::diff_config`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConfigDiff {
+  pub added: Vec<Hotkey>,
+  pub removed: Vec<Hotkey>,
+}
+
+/// Recovery of the backend, reported to the observer set with
+/// [`set_backend_recovery_observer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RecoveryEvent {
+  /// The hotkeys of a registry were grabbed from the OS again after it was reactivated,
+  /// see [`HotkeyRegistry::set_active`]. `failed` lists the ones the OS refused.
+  RegistrationsReapplied {
+    restored: Vec<Hotkey>,
+    failed: Vec<Hotkey>,
+  },
+}
+
+/// Handle of a hotkey registered with [`HotkeyManager::register_with_id`], unique within
+/// the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HotkeyId(usize);
+
+/// Press state of a hotkey registered with [`HotkeyManager::register_polled`].
+#[derive(Debug, Default, Clone)]
+pub struct PressFlag(Arc<AtomicBool>);
+
+impl PressFlag {
+  /// Returns whether the hotkey was pressed since the last poll and resets the flag.
+  pub fn take_pressed(&self) -> bool {
+    self.0.swap(false, Ordering::AcqRel)
+  }
+}
+
+impl Drop for HotkeyManager {
+  fn drop(&mut self) {
+    if let Err(err) = self.unregister_all() {
+      error!("drop: failed to unregister all hotkeys {:?}", err);
+    }
+  }
+}
+
+/// Options of the process-wide hotkey listener, see [`configure`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerConfig {
+  /// How often the listener thread polls for events and requests. Only used on
+  /// Linux, the other backends wait for events.
+  pub poll_interval: Duration,
+  /// Name of the listener thread.
+  pub thread_name: Option<String>,
+  /// Reports a held hotkey once instead of on every key repeat (`MOD_NOREPEAT` on Windows),
+  /// the default. Hotkeys registered with [`HotkeyManager::register_with_repeat`] can still
+  /// ask for repeats.
+  pub no_repeat: bool,
+  /// Offset of the hotkey ids registered with the OS on Windows and macOS, so they live in
+  /// a range distinct from the ids of hotkeys the embedding application registers itself.
+  /// It must be within `0..0xBFFF`, the range of application ids on Windows, the ids are
+  /// then assigned from `id_base + 1` up to `0xBFFF`.
+  pub id_base: i32,
+  /// How many handlers a single hotkey may have across managers before
+  /// [`Error::TooManyHandlers`] is returned, e.g. to catch a plugin re-registering in a loop.
+  /// `None`, the default, means unlimited.
+  pub max_handlers_per_hotkey: Option<usize>,
+}
+
+impl Default for ListenerConfig {
+  fn default() -> Self {
+    let options = ListenerOptions::default();
+    Self {
+      poll_interval: options.poll_interval,
+      thread_name: options.thread_name,
+      no_repeat: options.no_repeat,
+      id_base: options.id_base,
+      max_handlers_per_hotkey: None,
+    }
+  }
+}
+
+/// Configures the process-wide hotkey listener.
+///
+/// The listener is created when the first hotkey is registered, so this must be called
+/// before that, otherwise [`Error::ListenerInitialized`] is returned.
+pub fn configure(config: ListenerConfig) -> Result<()> {
+  GLOBAL_REGISTRY.configure(config)
+}
+
+// Grabs the hotkeys a sequence waits for next, as requested by its handler, releasing them
+// if no request follows within the timeout. Exits once the sequence is unregistered.
+fn arm_sequence(
+  registry: std::sync::Weak<HotkeyRegistry>,
+  id: usize,
+  press: std::sync::Weak<SequencePress>,
+  requests: Receiver<Vec<Hotkey>>,
+  timeout: Duration,
+) {
+  let mut armed: Vec<Hotkey> = Vec::new();
+  loop {
+    let request = if armed.is_empty() {
+      requests.recv().map_err(|_| RecvTimeoutError::Disconnected)
+    } else {
+      requests.recv_timeout(timeout)
+    };
+    let (waiting, exit) = match request {
+      Ok(waiting) => (waiting, false),
+      Err(RecvTimeoutError::Timeout) => (Vec::new(), false),
+      Err(RecvTimeoutError::Disconnected) => (Vec::new(), true),
+    };
+    let registry = match registry.upgrade() {
+      Some(registry) => registry,
+      None => return,
+    };
+    for hotkey in armed.iter().filter(|hotkey| !waiting.contains(hotkey)) {
+      if let Err(err) = registry.remove_handler(id, hotkey) {
+        error!("failed to release sequence hotkey {} {:?}", hotkey, err);
+      }
+    }
+    armed.retain(|hotkey| waiting.contains(hotkey));
+    for hotkey in waiting {
+      let press = match press.upgrade() {
+        Some(press) => press,
+        None => break,
+      };
+      if armed.contains(&hotkey) {
+        continue;
+      }
+      let pressed = hotkey.clone();
+      let handler = Handler {
+        callback: Box::new(move || press(&pressed)),
+        context: None,
+        label: None,
+        alias: None,
+        enabled: true,
+        allow_repeat: false,
+        no_repeat: false,
+        on_release: None,
+        hook: None,
+      };
+      match registry.add_handler(id, &hotkey, handler) {
+        Ok(()) => armed.push(hotkey),
+        Err(err) => error!("failed to grab sequence hotkey {} {:?}", hotkey, err),
+      }
+    }
+    if exit {
+      return;
+    }
+  }
+}
+
+fn build_listener(config: &ListenerConfig) -> Listener {
+  let mut builder = ListenerBuilder::new()
+    .poll_interval(config.poll_interval)
+    .no_repeat(config.no_repeat)
+    .id_base(config.id_base)
+    .expect("the id base is checked by configure");
+  if let Some(thread_name) = &config.thread_name {
+    builder = builder.thread_name(thread_name.as_str());
+  }
+  builder.build()
+}
+
+/// Token bucket limiting the trigger log lines of a hotkey.
+struct LogBucket {
+  tokens: f64,
+  refilled_at: Instant,
+  suppressed: u64,
+}
+
+impl LogBucket {
+  fn new(now: Instant) -> Self {
+    Self {
+      tokens: TRIGGER_LOGS_PER_SECOND,
+      refilled_at: now,
+      suppressed: 0,
+    }
+  }
+
+  fn take(&mut self, now: Instant) -> bool {
+    let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+    self.tokens = (self.tokens + elapsed * TRIGGER_LOGS_PER_SECOND).min(TRIGGER_LOGS_PER_SECOND);
+    self.refilled_at = now;
+    if self.tokens >= 1.0 {
+      self.tokens -= 1.0;
+      true
+    } else {
+      false
+    }
+  }
+}
+
+fn log_trigger(hotkey: &Hotkey) {
+  if !log::log_enabled!(log::Level::Debug) {
+    return;
+  }
+  let now = Instant::now();
+  let mut buckets = TRIGGER_LOG_BUCKETS.lock().unwrap();
+  let bucket = buckets
+    .entry(hotkey.clone())
+    .or_insert_with(|| LogBucket::new(now));
+  if !bucket.take(now) {
+    bucket.suppressed += 1;
+  } else if bucket.suppressed > 0 {
+    debug!(
+      "hotkey {} triggered ({} log lines suppressed)",
+      hotkey, bucket.suppressed
+    );
+    bucket.suppressed = 0;
+  } else {
+    debug!("hotkey {} triggered", hotkey);
+  }
+}
+
+/// Counters of the hotkey dispatch path, useful to tell whether a missed hotkey
+/// was never delivered by the OS or was received without running a callback.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchMetrics {
+  /// Number of hotkey triggers received from the backend.
+  pub total_triggers: u64,
+  /// Number of callbacks invoked.
+  pub total_callbacks: u64,
+  /// Number of triggers that didn't invoke any callback.
+  pub dropped: u64,
+}
+
+/// Returns the process-wide dispatch counters.
+pub fn dispatch_metrics() -> DispatchMetrics {
+  DispatchMetrics {
+    total_triggers: TOTAL_TRIGGERS.load(Ordering::Relaxed),
+    total_callbacks: TOTAL_CALLBACKS.load(Ordering::Relaxed),
+    dropped: DROPPED_TRIGGERS.load(Ordering::Relaxed),
+  }
+}
+
+/// Estimates the hotkey input latency by injecting a key press and timing how long it takes
+/// until the hotkey callback runs.
+///
+/// The hotkey is registered for the duration of the measurement, so it must not already be
+/// registered. The injected key must not be intercepted elsewhere (e.g. by another application
+/// or a remote desktop client), otherwise [`Error::SimulatedHotkeyTimeout`] is returned.
+#[cfg(feature = "simulate")]
+pub fn measure_latency(hotkey: &Hotkey) -> Result<Duration> {
+  let (tx, rx) = channel();
+  let mut manager = HotkeyManager::new();
+  manager.register(hotkey.clone(), move || {
+    let _ = tx.send(std::time::Instant::now());
+  })?;
+  let start = std::time::Instant::now();
+  let result = simulate_hotkey(hotkey.listener_hotkey())
+    .map_err(Error::from)
+    .and_then(|_| {
+      rx.recv_timeout(Duration::from_secs(1))
+        .map_err(|_| Error::SimulatedHotkeyTimeout)
+    })
+    .map(|fired| fired - start);
+  manager.unregister(hotkey)?;
+  result
+}
+
+/// Pauses the dispatch of every hotkey. Triggers received while paused are dropped.
+///
+/// Prefer [`with_hotkeys_disabled`], which can't leave the dispatch paused.
+pub fn pause_dispatch() {
+  DISPATCH_PAUSED.store(true, Ordering::SeqCst);
+}
+
+/// Resumes the dispatch paused by [`pause_dispatch`].
+pub fn resume_dispatch() {
+  DISPATCH_PAUSED.store(false, Ordering::SeqCst);
+}
+
+/// Determines whether the hotkey dispatch is paused.
+pub fn is_dispatch_paused() -> bool {
+  DISPATCH_PAUSED.load(Ordering::SeqCst)
+}
+
+/// Runs the closure with the hotkey dispatch paused.
+///
+/// The previous dispatch state is restored when the closure returns or panics.
+pub fn with_hotkeys_disabled<R, F: FnOnce() -> R>(f: F) -> R {
+  struct RestoreGuard(bool);
+
+  impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+      DISPATCH_PAUSED.store(self.0, Ordering::SeqCst);
+    }
+  }
+
+  let _guard = RestoreGuard(DISPATCH_PAUSED.swap(true, Ordering::SeqCst));
+  f()
+}
+
+/// Output format of a hotkey cheat sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatsheetFormat {
+  /// One `label  hotkey` line per hotkey, with the hotkeys aligned.
+  PlainText,
+  /// A markdown table with an action and a shortcut column.
+  Markdown,
+}
+
+/// Renders the labeled hotkeys of every manager as a cheat sheet, sorted by label.
+pub fn cheatsheet(format: CheatsheetFormat) -> String {
+  let mut entries = Vec::new();
+  for (hotkey, handlers) in GLOBAL_REGISTRY.hotkeys.lock().unwrap().iter() {
+    for handler in handlers.values() {
+      if let Some(label) = &handler.label {
+        entries.push((label.clone(), hotkey.normalize().to_string()));
+      }
+    }
+  }
+  entries.sort();
+  render_cheatsheet(&entries, format)
+}
+
+fn render_cheatsheet(entries: &[(String, String)], format: CheatsheetFormat) -> String {
+  let mut output = String::new();
+  match format {
+    CheatsheetFormat::PlainText => {
+      let width = entries
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+      for (label, hotkey) in entries {
+        output.push_str(&format!("{:width$}  {}\n", label, hotkey, width = width));
+      }
+    }
+    CheatsheetFormat::Markdown => {
+      output.push_str("| Action | Shortcut |\n| --- | --- |\n");
+      for (label, hotkey) in entries {
+        output.push_str(&format!(
+          "| {} | `{}` |\n",
+          label.replace('|', "\\|"),
+          hotkey
+        ));
+      }
+    }
+  }
+  output
+}
+
+/// Enables or disables every hotkey registered in the given context, across all managers.
+///
+/// Contexts are active by default.
+pub fn set_context_active(context: &str, active: bool) {
+  let mut inactive_contexts = INACTIVE_CONTEXTS.lock().unwrap();
+  if active {
+    inactive_contexts.remove(context);
+  } else {
+    inactive_contexts.insert(context.to_string());
+  }
+}
+
+/// Reports whether the app is focused, starting a new focus session for the hotkeys
+/// registered with [`HotkeyManager::register_once_per_focus`] when it gains focus.
+///
+/// The app is considered focused until told otherwise.
+pub fn set_app_focused(focused: bool) {
+  let mut focus = APP_FOCUS.lock().unwrap();
+  if focused && !focus.focused {
+    focus.session += 1;
+  }
+  focus.focused = focused;
+}
+
+/// Sets the policy of the process-wide registry, see
+/// [`HotkeyRegistry::set_registration_policy`].
+pub fn set_registration_policy<F>(policy: F)
+where
+  F: 'static + Fn(&Hotkey) -> std::result::Result<(), String> + Send,
+{
+  GLOBAL_REGISTRY.set_registration_policy(policy)
+}
+
+/// Sets the callback fired when a hotkey registered by this process is detected
+/// as no longer ours, e.g. because another application took it over.
+///
+/// Detection only happens while a [`RegistrationMonitor`] is running.
+pub fn on_hotkey_lost<F>(callback: F)
+where
+  F: 'static + Fn(Hotkey) + Send,
+{
+  HOTKEY_LOST_HANDLER
+    .lock()
+    .unwrap()
+    .replace(Box::new(callback));
+}
+
+/// Sets the observer notified when the backend recovers, e.g. to tell the user their
+/// shortcuts were restored. Replaces the previous observer.
+pub fn set_backend_recovery_observer<F>(observer: F)
+where
+  F: 'static + Fn(RecoveryEvent) + Send,
+{
+  RECOVERY_OBSERVER
+    .lock()
+    .unwrap()
+    .replace(Box::new(observer));
+}
+
+/// Sets the observer called with every hotkey press dispatched by any registry, before the
+/// hotkey's callbacks, e.g. to log or count which shortcuts are used. Replaces the
+/// previous observer. It runs on the listener thread and isn't called while dispatch is
+/// paused.
+pub fn set_global_observer<F>(observer: F)
+where
+  F: 'static + Fn(&Hotkey) + Send + Sync,
+{
+  HOTKEY_OBSERVER.lock().unwrap().replace(Box::new(observer));
+}
+
+fn notify_recovery(event: RecoveryEvent) {
+  if let Some(observer) = RECOVERY_OBSERVER.lock().unwrap().as_ref() {
+    observer(event);
+  }
+}
+
+/// Releases or grabs again every OS hotkey of the process-wide registry, see
+/// [`HotkeyRegistry::set_active`].
+pub fn set_global_active(active: bool) -> Result<()> {
+  GLOBAL_REGISTRY.set_active(active)
+}
+
+/// Releases every OS hotkey of the process-wide registry until [`resume_all`], see
+/// [`HotkeyRegistry::suspend`].
+pub fn suspend_all() -> Result<()> {
+  GLOBAL_REGISTRY.suspend()
+}
+
+/// Grabs again the hotkeys of the process-wide registry released by [`suspend_all`],
+/// returning the ones that couldn't be re-acquired, see [`HotkeyRegistry::resume`].
+pub fn resume_all() -> Vec<Hotkey> {
+  GLOBAL_REGISTRY.resume()
+}
+
+/// Lists the connected keyboards, see [`HotkeyManager::register_on_device`].
+#[cfg(feature = "devices")]
+pub fn list_keyboards() -> Vec<tauri_hotkey_sys::devices::DeviceInfo> {
+  tauri_hotkey_sys::devices::list_keyboards()
+}
+
+/// Returns the hotkeys registered process-wide by every [`HotkeyManager`] sharing the
+/// global registry, e.g. to surface conflicts between subsystems before registering.
+pub fn all_registered_hotkeys() -> Vec<Hotkey> {
+  GLOBAL_REGISTRY.registered_hotkeys()
+}
+
+/// Runs the handlers of a hotkey of the process-wide registry as if it was pressed and
+/// released, see [`HotkeyRegistry::trigger`].
+#[cfg(feature = "mock")]
+pub fn trigger(hotkey: &Hotkey) -> bool {
+  GLOBAL_REGISTRY.trigger(hotkey)
+}
+
+/// Returns the hotkeys of the process-wide registry that are physically held right now,
+/// see [`HotkeyRegistry::currently_held_hotkeys`].
+pub fn currently_held_hotkeys() -> Vec<Hotkey> {
+  GLOBAL_REGISTRY.currently_held_hotkeys()
+}
+
+/// Returns up to `limit` hotkeys of the process-wide registry, most recently used first,
+/// see [`HotkeyRegistry::most_recently_used`].
+pub fn most_recently_used(limit: usize) -> Vec<(Hotkey, Instant)> {
+  GLOBAL_REGISTRY.most_recently_used(limit)
+}
+
+/// Returns up to `limit` hotkeys of the process-wide registry, most frequently used first,
+/// see [`HotkeyRegistry::most_frequently_used`].
+pub fn most_frequently_used(limit: usize) -> Vec<(Hotkey, usize)> {
+  GLOBAL_REGISTRY.most_frequently_used(limit)
+}
+
+/// Re-validates every registered hotkey against the OS and returns the ones that are no
+/// longer held by this process, e.g. because another application took them over.
+///
+/// Each hotkey is released and grabbed again, so a press landing in between is missed.
+/// Lost hotkeys are grabbed again once the OS allows it.
+pub fn check_registrations() -> Vec<Hotkey> {
+  GLOBAL_REGISTRY.check_registrations()
+}
+
+/// Reports the canonical hotkey of every combination pressed until the returned
+/// [`LearnMode`] is dropped, see [`HotkeyRegistry::learn_mode`].
+pub fn learn_mode<F>(callback: F) -> Result<LearnMode>
+where
+  F: 'static + Fn(Hotkey) + Send,
+{
+  GLOBAL_REGISTRY.learn_mode(callback)
+}
+
+/// Active learn mode of a registry, see [`HotkeyRegistry::learn_mode`].
+///
+/// Learning stops when dropped.
+pub struct LearnMode {
+  registry: std::sync::Weak<HotkeyRegistry>,
+}
+
+impl Drop for LearnMode {
+  fn drop(&mut self) {
+    if let Some(registry) = self.registry.upgrade() {
+      if let Err(err) = registry.listener().lock().unwrap().stop_learning() {
+        error!("failed to stop learning hotkeys {:?}", err);
+      }
+      registry.learning.store(false, Ordering::SeqCst);
+    }
+  }
+}
+
+/// Background thread periodically running [`check_registrations`] and notifying
+/// the [`on_hotkey_lost`] callback once for each newly lost hotkey.
+///
+/// The monitor stops when dropped.
+pub struct RegistrationMonitor {
+  stop: Arc<AtomicBool>,
+}
+
+impl RegistrationMonitor {
+  pub fn start(interval: Duration) -> Self {
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_ = stop.clone();
+    thread::spawn(move || {
+      let mut reported: Vec<Hotkey> = Vec::new();
+      while !stop_.load(Ordering::Relaxed) {
+        let lost = check_registrations();
+        // forget hotkeys that were re-acquired so losing them again is reported
+        reported.retain(|hotkey| lost.contains(hotkey));
+        for hotkey in lost {
+          if reported.contains(&hotkey) {
+            continue;
+          }
+          info!("hotkey {} is no longer registered", hotkey);
+          if let Some(handler) = HOTKEY_LOST_HANDLER.lock().unwrap().as_ref() {
+            handler(hotkey.clone());
+          }
+          reported.push(hotkey);
+        }
+        thread::sleep(interval);
+      }
+    });
+    Self { stop }
+  }
+}
+
+impl Drop for RegistrationMonitor {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+  }
+}
+
+const MODIFIER_ALIASES: &[(&str, Modifier)] = &[
+  ("COMMAND", Modifier::SUPER),
+  ("CMD", Modifier::SUPER),
+  ("CONTROL", Modifier::CTRL),
+  ("WIN", Modifier::SUPER),
+  ("WINDOWS", Modifier::SUPER),
+  ("META", Modifier::SUPER),
+  #[cfg(target_os = "macos")]
+  ("OPTION", Modifier::ALT),
+  #[cfg(target_os = "macos")]
+  ("FN", Modifier::GLOBE),
+  ("COMMANDORCONTROL", Modifier::COMMAND_OR_CONTROL),
+  ("COMMANDORCTRL", Modifier::COMMAND_OR_CONTROL),
+  ("CMDORCTRL", Modifier::COMMAND_OR_CONTROL),
+  ("CMDORCONTROL", Modifier::COMMAND_OR_CONTROL),
+];
+
+// characters typed with Shift, parsed as their unshifted key plus Shift
+const SHIFTED_KEYS: &[(&str, Key)] = &[
+  (")", Key::KEY_0),
+  ("!", Key::KEY_1),
+  ("@", Key::KEY_2),
+  ("#", Key::KEY_3),
+  ("$", Key::KEY_4),
+  ("%", Key::KEY_5),
+  ("^", Key::KEY_6),
+  ("&", Key::KEY_7),
+  ("*", Key::KEY_8),
+  ("(", Key::KEY_9),
+  (":", Key::SEMICOLON),
+  ("<", Key::COMMA),
+  (">", Key::PERIOD),
+  ("_", Key::MINUS),
+  ("?", Key::SLASH),
+  ("~", Key::OPENQUOTE),
+  ("{", Key::OPENBRACKET),
+  ("|", Key::BACKSLASH),
+  ("}", Key::CLOSEBRACKET),
+  ("+", Key::EQUAL),
+  ("PLUS", Key::EQUAL),
+  ("\"", Key::SINGLEQUOTE),
+];
+
+const KEY_ALIASES: &[(&str, Key)] = &[
+  ("RETURN", Key::ENTER),
+  ("ESC", Key::ESCAPE),
+  ("DEL", Key::DELETE),
+  #[cfg(not(target_os = "macos"))]
+  ("INS", Key::INSERT),
+  ("PGUP", Key::PAGEUP),
+  ("PGDN", Key::PAGEDOWN),
+  ("SPC", Key::SPACE),
+  ("=", Key::EQUAL),
+  ("-", Key::MINUS),
+  ("'", Key::SINGLEQUOTE),
+  (",", Key::COMMA),
+  (".", Key::PERIOD),
+  (";", Key::SEMICOLON),
+  ("/", Key::SLASH),
+  ("`", Key::OPENQUOTE),
+  ("[", Key::OPENBRACKET),
+  ("\\", Key::BACKSLASH),
+  ("]", Key::CLOSEBRACKET),
+  #[cfg(not(target_os = "macos"))]
+  ("APPS", Key::MENU),
+  #[cfg(not(target_os = "macos"))]
+  ("CONTEXTMENU", Key::MENU),
+  #[cfg(target_os = "macos")]
+  ("KPEQUAL", Key::NUMEQUAL),
+  #[cfg(target_os = "macos")]
+  ("F14", Key::SCROLLLOCK),
+];
+
+// Tokens of `parse_hotkey`, built once so importing large keymaps doesn't match every
+// token against each name in turn.
+static MODIFIER_TOKENS: Lazy<HashMap<String, Modifier>> = Lazy::new(|| {
+  Modifier::iter()
+    .map(|modifier| (format!("{:?}", modifier), modifier))
+    .chain(
+      MODIFIER_ALIASES
+        .iter()
+        .map(|(alias, modifier)| (alias.to_string(), *modifier)),
+    )
+    .collect()
+});
+
+// keys by token, with whether the token implies Shift
+static KEY_TOKENS: Lazy<HashMap<String, (Key, bool)>> = Lazy::new(|| {
+  let mut tokens = HashMap::new();
+  for key in Key::iter() {
+    // both `KEY_1` and `1`
+    tokens.insert(format!("{:?}", key), (key, false));
+    tokens.insert(key.to_string(), (key, false));
+  }
+  for digit in 0..10 {
+    let numpad = tokens[&format!("NUMPAD{}", digit)];
+    tokens.insert(format!("NP{}", digit), numpad);
+    tokens.insert(format!("KP{}", digit), numpad);
+  }
+  for (token, key) in SHIFTED_KEYS {
+    tokens.insert(token.to_string(), (*key, true));
+  }
+  for (alias, key) in KEY_ALIASES {
+    tokens.insert(alias.to_string(), (*key, false));
+  }
+  tokens
+});
+
+/// Builds the token lookup of [`parse_hotkey`] ahead of time, e.g. on a background thread
+/// at startup. Parsing builds it on first use otherwise.
+pub fn warm_up_parser() {
+  Lazy::force(&MODIFIER_TOKENS);
+  Lazy::force(&KEY_TOKENS);
+}
+
+pub fn parse_hotkey(hotkey_string: &str) -> Result<Hotkey> {
+  let mut modifiers = Vec::new();
+  let mut keys = Vec::new();
+  let mut shifted = false;
+  for raw in hotkey_string.to_uppercase().split('+') {
+    let token = raw.trim();
+    if token.is_empty() {
+      continue;
+    }
+    if let Some(modifier) = MODIFIER_TOKENS.get(token) {
+      // repeated modifiers, e.g. `CMD+SUPER`, press the same key
+      if !modifiers.contains(modifier) {
+        modifiers.push(*modifier);
+      }
+      continue;
+    }
+    if token.len() > 1 && token.chars().all(|c| c.is_ascii_digit()) {
+      return Err(crate::Error::InvalidHotkey(format!(
+        "numeric key must be a single digit 0-9, got {}",
+        token
+      )));
+    }
+    let (key, shifted_key) = match KEY_TOKENS.get(token) {
+      Some(key) => *key,
+      None => {
+        return Err(crate::Error::InvalidHotkey(format!(
+          "unknown key {}",
+          token
+        )))
+      }
+    };
+    if keys.contains(&key) {
+      return Err(crate::Error::InvalidHotkey(format!(
+        "duplicated key {}",
+        raw
+      )));
+    }
+    shifted |= shifted_key;
+    keys.push(key);
+  }
+
+  if shifted && !modifiers.contains(&Modifier::SHIFT) {
+    modifiers.push(Modifier::SHIFT);
+  }
+
+  match keys.len() {
+    0 => Err(Error::InvalidHotkey(
+      "hotkey has no key specified".to_string(),
+    )),
+    _ => Ok(Hotkey { modifiers, keys }),
+  }
+}
+
+/// Parses a hotkey string and returns it in the canonical form, without registering it.
+///
+/// Modifiers and keys are deduplicated and written in a fixed order, see
+/// [`Hotkey::normalize`], and aliases are replaced by their canonical names, e.g.
+/// `shift+ctrl+!` becomes `CTRL+SHIFT+1`.
+pub fn canonicalize(input: &str) -> Result<String> {
+  Ok(parse_hotkey(input)?.normalize().to_string())
+}
+
+/// A hotkey, made of modifiers and keys.
+///
+/// The order of the modifiers doesn't matter when comparing or hashing hotkeys, e.g.
+/// `CTRL+SHIFT+A` equals `SHIFT+CTRL+A`, since both grab the same OS shortcut.
+///
+/// Serializes as a struct of `modifiers` and `keys` arrays, or as a string such as
+/// `"CTRL+SHIFT+A"` with [`as_string`] or the `string-serde` feature.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "string-serde"), derive(Deserialize, Serialize))]
+pub struct Hotkey {
+  pub modifiers: Vec<Modifier>,
+  pub keys: Vec<Key>,
+}
+
+impl PartialEq for Hotkey {
+  fn eq(&self, other: &Self) -> bool {
+    self.keys == other.keys && self.canonical_modifiers() == other.canonical_modifiers()
+  }
+}
+
+impl Eq for Hotkey {}
+
+impl Hash for Hotkey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.canonical_modifiers().hash(state);
+    self.keys.hash(state);
+  }
+}
+
+impl Hotkey {
+  /// Starts building a hotkey from typed modifiers and keys, e.g.
+  /// `Hotkey::builder().modifier(Modifier::CTRL).key(Key::S).build()`.
+  pub fn builder() -> HotkeyBuilder {
+    HotkeyBuilder::new()
+  }
+
+  pub fn modifiers_as_flag(&self) -> u32 {
+    self
+      .modifiers
+      .iter()
+      .fold(0, |acc, x| acc | (x.resolve() as u32))
+  }
+
+  pub fn keys_as_flag(&self) -> u32 {
+    self.keys.iter().fold(0, |acc, x| acc | (*x as u32))
+  }
+
+  fn listener_hotkey(&self) -> ListenerHotkey {
+    ListenerHotkey::new(self.modifiers_as_flag(), self.keys_as_flag())
+  }
+
+  /// Decodes backend flags, e.g. the ones of [`Hotkey::modifiers_as_flag`] and
+  /// [`Hotkey::keys_as_flag`], back into a hotkey.
+  ///
+  /// Bits that don't map to a [`Modifier`] and key codes that don't map to a [`Key`] are
+  /// reported in the result instead of being dropped. Flags of hotkeys with several keys
+  /// can't be decoded since their key codes are merged.
+  pub fn from_flags(modifiers: u32, keys: u32) -> DecodeResult {
+    let mut unknown_modifier_bits = modifiers;
+    let mut decoded_modifiers = Vec::new();
+    for modifier in MODIFIER_ORDER
+      .iter()
+      .filter(|modifier| **modifier != Modifier::COMMAND_OR_CONTROL)
+    {
+      let bits = *modifier as u32;
+      if unknown_modifier_bits & bits == bits {
+        decoded_modifiers.push(*modifier);
+        unknown_modifier_bits &= !bits;
+      }
+    }
+    let key = Key::iter().find(|key| *key as u32 == keys);
+    DecodeResult {
+      hotkey: key.map(|key| Hotkey {
+        modifiers: decoded_modifiers,
+        keys: vec![key],
+      }),
+      unknown_modifier_bits,
+      unknown_key_bits: if key.is_some() { 0 } else { keys },
+    }
+  }
+
+  /// Determines whether both hotkeys would grab the same physical keys with the active
+  /// keyboard layout, even if their keys differ, e.g. two punctuation keys produced by the
+  /// same physical key on a non-US layout.
+  ///
+  /// Returns `false` if a key isn't produced by any physical key of the layout.
+  pub fn resolves_to_same_physical(&self, other: &Hotkey) -> bool {
+    if self.modifiers_as_flag() != other.modifiers_as_flag() {
+      return false;
+    }
+    if self.keys_as_flag() == other.keys_as_flag() {
+      return true;
+    }
+    let physical_keys = |keys: &[Key]| {
+      let mut physical = keys
+        .iter()
+        .map(|key| physical_key(*key as u32))
+        .collect::<Option<Vec<u32>>>()?;
+      physical.sort_unstable();
+      physical.dedup();
+      Some(physical)
+    };
+    match (physical_keys(&self.keys), physical_keys(&other.keys)) {
+      (Some(keys), Some(other_keys)) => keys == other_keys,
+      _ => false,
+    }
+  }
+
+  /// Checks that the hotkey's string form parses back to the same hotkey, so it can be
+  /// written to a config file and read again. Meant to be called when saving, not on hot
+  /// paths.
+  ///
+  /// Hotkeys from [`parse_hotkey`] always pass. Hand-built ones can fail, e.g. one with
+  /// neither modifiers nor keys.
+  pub fn assert_parseable(&self) -> Result<()> {
+    let rendered = self.to_string();
+    match parse_hotkey(&rendered) {
+      Ok(parsed) if parsed == *self => Ok(()),
+      Ok(parsed) => Err(Error::InvalidHotkey(format!(
+        "{} parses back as {:?} instead of {:?}",
+        rendered, parsed, self
+      ))),
+      Err(err) => Err(Error::InvalidHotkey(format!(
+        "{} doesn't parse back: {}",
+        rendered, err
+      ))),
+    }
+  }
+
+  /// Returns a key to sort hotkeys in settings lists.
+  ///
+  /// Hotkeys are ordered by their number of modifiers, then by their modifiers in the
+  /// canonical order (`CTRL`, `ALT`, `SHIFT`, `SUPER`, see [`canonicalize`]), then by
+  /// their keys in the declaration order of [`Key`]: navigation and editing keys, function
+  /// keys, numpad keys, digits, letters and punctuation.
+  pub fn ui_sort_key(&self) -> impl Ord {
+    let normalized = self.normalize();
+    let modifier_positions: Vec<usize> = normalized
+      .modifiers
+      .iter()
+      .filter_map(|modifier| MODIFIER_ORDER.iter().position(|m| m == modifier))
+      .collect();
+    let key_positions: Vec<usize> = normalized
+      .keys
+      .iter()
+      .filter_map(|key| Key::iter().position(|k| k == *key))
+      .collect();
+    (modifier_positions.len(), modifier_positions, key_positions)
+  }
+
+  /// Returns the canonical form of the hotkey, so hotkeys written in a different order
+  /// get the same representation, e.g. to deduplicate a config or as a stable map key.
+  ///
+  /// Modifiers are deduplicated and put in the canonical order (see [`canonicalize`]),
+  /// and keys are deduplicated and put in the declaration order of [`Key`]. Both orders
+  /// are the same on every platform, so the `Display` output is deterministic.
+  pub fn normalize(&self) -> Hotkey {
+    let mut keys = self.keys.clone();
+    keys.sort_by_key(|key| Key::iter().position(|k| k == *key));
+    keys.dedup();
+    Hotkey {
+      modifiers: self.canonical_modifiers(),
+      keys,
+    }
+  }
+
+  fn canonical_modifiers(&self) -> Vec<Modifier> {
+    MODIFIER_ORDER
+      .iter()
+      .filter(|modifier| self.modifiers.contains(modifier))
+      .copied()
+      .collect()
+  }
+}
+
+/// Builds a [`Hotkey`] without going through a string, see [`Hotkey::builder`].
+///
+/// [`HotkeyBuilder::build`] enforces the rules of [`parse_hotkey`]: the hotkey needs at
+/// least one key and no key may appear twice. Repeated modifiers are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct HotkeyBuilder {
+  modifiers: Vec<Modifier>,
+  keys: Vec<Key>,
+}
+
+impl HotkeyBuilder {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn modifier(mut self, modifier: Modifier) -> Self {
+    if !self.modifiers.contains(&modifier) {
+      self.modifiers.push(modifier);
+    }
+    self
+  }
+
+  /// Adds the platform's primary modifier, see [`Modifier::primary`].
+  pub fn primary(self) -> Self {
+    self.modifier(Modifier::primary())
+  }
+
+  pub fn key(mut self, key: Key) -> Self {
+    self.keys.push(key);
+    self
+  }
+
+  pub fn build(self) -> Result<Hotkey> {
+    if self.keys.is_empty() {
+      return Err(Error::InvalidHotkey(
+        "hotkey has no key specified".to_string(),
+      ));
+    }
+    for (index, key) in self.keys.iter().enumerate() {
+      if self.keys[..index].contains(key) {
+        return Err(Error::InvalidHotkey(format!("duplicated key {}", key)));
+      }
+    }
+    Ok(Hotkey {
+      modifiers: self.modifiers,
+      keys: self.keys,
+    })
+  }
+}
+
+/// Result of [`Hotkey::from_flags`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeResult {
+  /// The decoded hotkey, `None` if the key flag doesn't match any [`Key`].
+  pub hotkey: Option<Hotkey>,
+  /// Modifier bits that don't belong to any [`Modifier`].
+  pub unknown_modifier_bits: u32,
+  /// The key flag if it doesn't match any [`Key`], `0` otherwise.
+  pub unknown_key_bits: u32,
+}
+
+/// Order in which the modifiers of a canonical hotkey string are written.
+const MODIFIER_ORDER: &[Modifier] = &[
+  Modifier::COMMAND_OR_CONTROL,
+  Modifier::CTRL,
+  Modifier::ALT,
+  Modifier::ALTGR,
+  Modifier::SHIFT,
+  Modifier::SUPER,
+  #[cfg(target_os = "macos")]
+  Modifier::GLOBE,
+];
+
+/// Hotkey modifiers.
+///
+/// `ALT`, `CTRL`, `SHIFT` and `SUPER` match both the left and the right physical key on
+/// every platform: the Windows `MOD_*` flags, the X11 modifier masks and the Carbon
+/// modifier flags the backends register with don't distinguish sides.
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(
+  Debug,
+  Deserialize,
+  Copy,
+  Clone,
+  Serialize,
+  strum_macros::EnumString,
+  strum_macros::EnumIter,
+  PartialEq,
+  Hash,
+  Eq,
+)]
+#[repr(u32)]
+pub enum Modifier {
+  ALT = modifiers::ALT,
+  /// Windows reports AltGr as Ctrl+Alt, so there `ALTGR+E` and `CTRL+ALT+E` are the same
+  /// shortcut and registering both fails with [`Error::HotkeyConflict`]. Telling them
+  /// apart would need a low-level keyboard hook.
+  ALTGR = modifiers::ALT_GR,
+  CTRL = modifiers::CONTROL,
+  SHIFT = modifiers::SHIFT,
+  SUPER = modifiers::SUPER,
+  /// The Globe/Fn key of Mac keyboards, also parsed from `FN`. The Carbon backend can't
+  /// register it and returns [`HotkeyError::UnsupportedModifier`].
+  #[cfg(target_os = "macos")]
+  GLOBE = modifiers::FUNCTION,
+  /// `SUPER` on macOS and `CTRL` elsewhere, resolved at registration time.
+  /// Its value is never passed to the OS.
+  COMMAND_OR_CONTROL = 1 << 31,
+}
+
+impl Modifier {
+  /// The conventional accelerator modifier of the current platform: `SUPER` (Cmd) on macOS
+  /// and `CTRL` elsewhere.
+  ///
+  /// Unlike [`Modifier::COMMAND_OR_CONTROL`], this is resolved immediately, so the hotkey
+  /// describes the concrete modifier of the current platform.
+  pub fn primary() -> Modifier {
+    #[cfg(target_os = "macos")]
+    return Modifier::SUPER;
+    #[cfg(not(target_os = "macos"))]
+    return Modifier::CTRL;
+  }
+
+  /// Returns the modifier flag the backend of the compiled target registers, e.g.
+  /// `MOD_CONTROL` for `CTRL` on Windows, the X modifier mask on Linux and the Carbon
+  /// modifier flag on macOS. `COMMAND_OR_CONTROL` is resolved first, see
+  /// [`Modifier::resolve`]. Flags differ between platforms and aren't meant to be persisted.
+  pub fn platform_code(&self) -> u32 {
+    self.resolve() as u32
+  }
+
+  /// Resolves a logical modifier to the concrete modifier of the current platform.
+  pub fn resolve(self) -> Modifier {
+    match self {
+      #[cfg(target_os = "macos")]
+      Modifier::COMMAND_OR_CONTROL => Modifier::SUPER,
+      #[cfg(not(target_os = "macos"))]
+      Modifier::COMMAND_OR_CONTROL => Modifier::CTRL,
+      modifier => modifier,
+    }
+  }
+}
+
+impl fmt::Display for Modifier {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Modifier::COMMAND_OR_CONTROL => write!(f, "COMMANDORCONTROL"),
+      _ => write!(f, "{:?}", self),
+    }
+  }
+}
+
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(
+  Debug,
+  Deserialize,
+  Copy,
+  Clone,
+  Serialize,
+  strum_macros::EnumString,
+  strum_macros::EnumIter,
+  strum_macros::IntoStaticStr,
+  PartialEq,
+  Hash,
+  Eq,
+)]
+#[repr(u32)]
+pub enum Key {
+  BACKSPACE = keys::BACKSPACE,
+  TAB = keys::TAB,
+  ENTER = keys::ENTER,
+  CAPSLOCK = keys::CAPS_LOCK,
+  ESCAPE = keys::ESCAPE,
+  SPACE = keys::SPACEBAR,
+  PAGEUP = keys::PAGE_UP,
+  PAGEDOWN = keys::PAGE_DOWN,
+  END = keys::END,
+  HOME = keys::HOME,
+  LEFT = keys::ARROW_LEFT,
+  RIGHT = keys::ARROW_RIGHT,
+  UP = keys::ARROW_UP,
+  DOWN = keys::ARROW_DOWN,
+  // no macOS equivalent, registering it fails with `Error::Unsupported`
+  PRINTSCREEN = keys::PRINT_SCREEN,
   #[cfg(not(target_os = "macos"))]
+  INSERT = keys::INSERT,
+  CLEAR = keys::CLEAR,
+  DELETE = keys::DELETE,
+  // the F14 key of Mac keyboards, which is parsed as this key there
+  #[cfg_attr(target_os = "macos", serde(alias = "F14"))]
+  SCROLLLOCK = keys::SCROLL_LOCK,
+  HELP = keys::HELP,
+  // no macOS equivalent, registering it fails with `Error::Unsupported`
+  NUMLOCK = keys::NUMLOCK,
+  // Media
+  VOLUMEMUTE = keys::VOLUME_MUTE,
+  VOLUMEDOWN = keys::VOLUME_DOWN,
+  VOLUMEUP = keys::VOLUME_UP,
+  MEDIANEXTTRACK = keys::MEDIA_NEXT,
   MEDIAPREVIOUSTRACK = keys::MEDIA_PREV,
-  #[cfg(not(target_os = "macos"))]
+  // no macOS equivalent, registering it fails with `Error::Unsupported`
   MEDIASTOP = keys::MEDIA_STOP,
-  #[cfg(not(target_os = "macos"))]
   MEDIAPLAYPAUSE = keys::MEDIA_PLAY_PAUSE,
+  // no macOS equivalent, registering it fails with `Error::Unsupported`
+  LAUNCHMAIL = keys::LAUNCH_MAIL,
+  // Application/context menu key, macOS keyboards have no equivalent
+  #[cfg(not(target_os = "macos"))]
+  MENU = keys::MENU,
+  // F1-F24, macOS has no key codes for F21-F24 and registering them fails with
+  // `Error::Unsupported`
+  F1 = keys::F1,
+  F2 = keys::F2,
+  F3 = keys::F3,
+  F4 = keys::F4,
+  F5 = keys::F5,
+  F6 = keys::F6,
+  F7 = keys::F7,
+  F8 = keys::F8,
+  F9 = keys::F9,
+  F10 = keys::F10,
+  F11 = keys::F11,
+  F12 = keys::F12,
+  F13 = keys::F13,
+  // `SCROLLLOCK` has the F14 key code on macOS
+  #[cfg(not(target_os = "macos"))]
+  F14 = keys::F14,
+  F15 = keys::F15,
+  F16 = keys::F16,
+  F17 = keys::F17,
+  F18 = keys::F18,
+  F19 = keys::F19,
+  F20 = keys::F20,
+  F21 = keys::F21,
+  F22 = keys::F22,
+  F23 = keys::F23,
+  F24 = keys::F24,
+  // Numpad
+  NUMADD = keys::ADD,
+  NUMSUB = keys::SUBTRACT,
+  NUMMULT = keys::MULTIPLY,
+  NUMDIV = keys::DIVIDE,
+  NUMDEC = keys::DECIMAL,
+  #[cfg(target_os = "macos")]
+  NUMEQUAL = keys::KEYPAD_EQUALS,
+  NUMPAD0 = keys::NUMPAD0,
+  NUMPAD1 = keys::NUMPAD1,
+  NUMPAD2 = keys::NUMPAD2,
+  NUMPAD3 = keys::NUMPAD3,
+  NUMPAD4 = keys::NUMPAD4,
+  NUMPAD5 = keys::NUMPAD5,
+  NUMPAD6 = keys::NUMPAD6,
+  NUMPAD7 = keys::NUMPAD7,
+  NUMPAD8 = keys::NUMPAD8,
+  NUMPAD9 = keys::NUMPAD9,
+  #[serde(rename = "0")]
+  KEY_0 = keys::KEY_0,
+  #[serde(rename = "1")]
+  KEY_1 = keys::KEY_1,
+  #[serde(rename = "2")]
+  KEY_2 = keys::KEY_2,
+  #[serde(rename = "3")]
+  KEY_3 = keys::KEY_3,
+  #[serde(rename = "4")]
+  KEY_4 = keys::KEY_4,
+  #[serde(rename = "5")]
+  KEY_5 = keys::KEY_5,
+  #[serde(rename = "6")]
+  KEY_6 = keys::KEY_6,
+  #[serde(rename = "7")]
+  KEY_7 = keys::KEY_7,
+  #[serde(rename = "8")]
+  KEY_8 = keys::KEY_8,
+  #[serde(rename = "9")]
+  KEY_9 = keys::KEY_9,
+  A = keys::A,
+  B = keys::B,
+  C = keys::C,
+  D = keys::D,
+  E = keys::E,
+  F = keys::F,
+  G = keys::G,
+  H = keys::H,
+  I = keys::I,
+  J = keys::J,
+  K = keys::K,
+  L = keys::L,
+  M = keys::M,
+  N = keys::N,
+  O = keys::O,
+  P = keys::P,
+  Q = keys::Q,
+  R = keys::R,
+  S = keys::S,
+  T = keys::T,
+  U = keys::U,
+  V = keys::V,
+  W = keys::W,
+  X = keys::X,
+  Y = keys::Y,
+  Z = keys::Z,
+  #[serde(rename = "=")]
+  EQUAL = keys::EQUAL,
+  #[serde(rename = "-")]
+  MINUS = keys::MINUS,
+  #[serde(rename = "'")]
+  SINGLEQUOTE = keys::SINGLE_QUOTE,
+  #[serde(rename = ",")]
+  COMMA = keys::COMMA,
+  #[serde(rename = ".")]
+  PERIOD = keys::PERIOD,
+  #[serde(rename = ";")]
+  SEMICOLON = keys::SEMICOLON,
+  #[serde(rename = "/")]
+  SLASH = keys::SLASH,
+  #[serde(rename = "`")]
+  OPENQUOTE = keys::OPEN_QUOTE,
+  #[serde(rename = "[")]
+  OPENBRACKET = keys::OPEN_BRACKET,
+  #[serde(rename = "\\")]
+  BACKSLASH = keys::BACK_SLASH,
+  #[serde(rename = "]")]
+  CLOSEBRACKET = keys::CLOSE_BRACKET,
+}
+
+const FUNCTION_KEYS: &[Key] = &[
+  Key::F1,
+  Key::F2,
+  Key::F3,
+  Key::F4,
+  Key::F5,
+  Key::F6,
+  Key::F7,
+  Key::F8,
+  Key::F9,
+  Key::F10,
+  Key::F11,
+  Key::F12,
+  Key::F13,
+  #[cfg(not(target_os = "macos"))]
+  Key::F14,
+  #[cfg(target_os = "macos")]
+  Key::SCROLLLOCK,
+  Key::F15,
+  Key::F16,
+  Key::F17,
+  Key::F18,
+  Key::F19,
+  Key::F20,
+  #[cfg(not(target_os = "macos"))]
+  Key::F21,
+  #[cfg(not(target_os = "macos"))]
+  Key::F22,
+  #[cfg(not(target_os = "macos"))]
+  Key::F23,
+  #[cfg(not(target_os = "macos"))]
+  Key::F24,
+];
+
+impl Key {
+  /// Returns the key code the backend of the compiled target registers, e.g. the virtual
+  /// key code `0x41` for `A` on Windows, the X keysym on Linux and the Carbon key code
+  /// `0x00` on macOS. Codes differ between platforms and aren't meant to be persisted.
+  ///
+  /// Keys without an equivalent on the platform have a code with a marker bit set, see
+  /// [`Key::is_supported_on_platform`].
+  pub fn platform_code(&self) -> u32 {
+    *self as u32
+  }
+
+  /// Returns whether the key can be registered on the current platform, e.g. to grey out
+  /// keys in a hotkey picker. Registering an unsupported key, such as `PRINTSCREEN` or
+  /// `NUMLOCK` on macOS, fails with [`Error::Unsupported`].
+  pub fn is_supported_on_platform(&self) -> bool {
+    is_supported_key(*self as u32)
+  }
+}
+
+impl fmt::Display for Key {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = format!("{:?}", self);
+    // digits are written as they are typed, e.g. `1` instead of `KEY_1`
+    write!(f, "{}", name.strip_prefix("KEY_").unwrap_or(&name))
+  }
+}
+
+impl fmt::Display for Hotkey {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let modifier_string: String = self.modifiers.iter().fold(String::new(), |all, one| {
+      if !all.is_empty() {
+        format!("{}+{}", all, one)
+      } else {
+        one.to_string()
+      }
+    });
+    let hotkey_string = {
+      if !modifier_string.is_empty() {
+        format!(
+          "{}+{}",
+          modifier_string,
+          self
+            .keys
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<String>>()
+            .join("+")
+        )
+      } else {
+        self
+          .keys
+          .iter()
+          .map(|k| k.to_string())
+          .collect::<Vec<String>>()
+          .join("+")
+      }
+    };
+    write!(f, "{}", hotkey_string)
+  }
+}
+
+/// Parses a hotkey string with [`parse_hotkey`], e.g. `"CTRL+SHIFT+P".parse::<Hotkey>()`.
+impl FromStr for Hotkey {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    parse_hotkey(s)
+  }
+}
+
+/// Parses a hotkey string with [`parse_hotkey`].
+impl TryFrom<&str> for Hotkey {
+  type Error = Error;
+
+  fn try_from(s: &str) -> Result<Self> {
+    parse_hotkey(s)
+  }
+}
+
+/// Parses a hotkey string with [`parse_hotkey`].
+impl TryFrom<String> for Hotkey {
+  type Error = Error;
+
+  fn try_from(s: String) -> Result<Self> {
+    parse_hotkey(&s)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  static DISPATCH_LOCK: Lazy<Mutex<()>> = Lazy::new(Default::default);
+
+  // tests that dispatch hotkeys and check their callbacks can't run while another test
+  // pauses the dispatch
+  fn dispatch_lock() -> std::sync::MutexGuard<'static, ()> {
+    DISPATCH_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Simulates a press of the hotkey in the process-wide registry.
+  fn dispatch(hotkey: &Hotkey) {
+    GLOBAL_REGISTRY.dispatch(hotkey)
+  }
+
+  struct TestLogger {
+    lines: Lazy<Mutex<Vec<String>>>,
+  }
+
+  impl log::Log for TestLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+      true
+    }
+
+    fn log(&self, record: &log::Record) {
+      self.lines.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+  }
+
+  static TEST_LOGGER: TestLogger = TestLogger {
+    lines: Lazy::new(Default::default),
+  };
+
+  /// Captures the log lines of every test and returns the lines logged so far containing `text`.
+  fn logged_lines(text: &str) -> usize {
+    if log::set_logger(&TEST_LOGGER).is_ok() {
+      log::set_max_level(log::LevelFilter::Debug);
+    }
+    let lines = TEST_LOGGER.lines.lock().unwrap();
+    lines.iter().filter(|line| line.contains(text)).count()
+  }
+
+  #[test]
+  fn hotkey_parse() {
+    assert_eq!(
+      parse_hotkey("CTRL+P").unwrap(),
+      Hotkey {
+        modifiers: vec![Modifier::CTRL],
+        keys: vec![Key::P]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("CTRL+SHIFT+P").unwrap(),
+      Hotkey {
+        modifiers: vec![Modifier::CTRL, Modifier::SHIFT],
+        keys: vec![Key::P]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("S").unwrap(),
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::S]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("ALT+BACKSPACE").unwrap(),
+      Hotkey {
+        modifiers: vec![Modifier::ALT],
+        keys: vec![Key::BACKSPACE]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("SHIFT+SUPER+A").unwrap(),
+      Hotkey {
+        modifiers: vec![Modifier::SHIFT, Modifier::SUPER],
+        keys: vec![Key::A]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("SUPER+RIGHT").unwrap(),
+      Hotkey {
+        modifiers: vec![Modifier::SUPER],
+        keys: vec![Key::RIGHT]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("SUPER+CTRL+SHIFT+AltGr+9").unwrap(),
+      Hotkey {
+        modifiers: vec![
+          Modifier::SUPER,
+          Modifier::CTRL,
+          Modifier::SHIFT,
+          Modifier::ALTGR
+        ],
+        keys: vec![Key::KEY_9]
+      }
+    );
+    assert_eq!(
+      parse_hotkey("super+ctrl+SHIFT+alt+Up").unwrap(),
+      Hotkey {
+        modifiers: vec![
+          Modifier::SUPER,
+          Modifier::CTRL,
+          Modifier::SHIFT,
+          Modifier::ALT
+        ],
+        keys: vec![Key::UP]
+      }
+    );
+
+    assert_eq!(
+      parse_hotkey("5").unwrap(),
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::KEY_5]
+      }
+    );
+
+    assert_eq!(
+      parse_hotkey("KEY_5").unwrap(),
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::KEY_5]
+      }
+    );
+
+    assert_eq!(
+      parse_hotkey("5+5").unwrap_err().to_string(),
+      "failed to parse hotkey: duplicated key 5"
+    );
+
+    assert_eq!(
+      parse_hotkey("CTRL+").unwrap_err().to_string(),
+      "failed to parse hotkey: hotkey has no key specified"
+    );
+
+    assert_eq!(
+      parse_hotkey("").unwrap_err().to_string(),
+      "failed to parse hotkey: hotkey has no key specified"
+    );
+  }
+
+  #[test]
+  fn command_or_control() {
+    let hotkey = parse_hotkey("CmdOrCtrl+Shift+P").unwrap();
+    assert_eq!(
+      hotkey,
+      Hotkey {
+        modifiers: vec![Modifier::COMMAND_OR_CONTROL, Modifier::SHIFT],
+        keys: vec![Key::P]
+      }
+    );
+    #[cfg(target_os = "macos")]
+    assert_eq!(
+      hotkey.modifiers_as_flag(),
+      modifiers::SUPER | modifiers::SHIFT
+    );
+    #[cfg(not(target_os = "macos"))]
+    assert_eq!(
+      hotkey.modifiers_as_flag(),
+      modifiers::CONTROL | modifiers::SHIFT
+    );
+    assert_eq!(hotkey.to_string(), "COMMANDORCONTROL+SHIFT+P");
+    assert_eq!(parse_hotkey(&hotkey.to_string()).unwrap(), hotkey);
+  }
+
+  #[test]
+  fn canonical_form() {
+    for (input, canonical) in &[
+      ("shift+ctrl+!", "CTRL+SHIFT+1"),
+      ("Super+Shift+Alt+Control+a", "CTRL+ALT+SHIFT+SUPER+A"),
+      ("ctrl+ctrl+return", "CTRL+ENTER"),
+      ("Shift+CmdOrCtrl+P", "COMMANDORCONTROL+SHIFT+P"),
+      ("alt + np3", "ALT+NUMPAD3"),
+      ("CTRL+KEY_5", "CTRL+5"),
+      ("ctrl+/", "CTRL+SLASH"),
+      ("f5", "F5"),
+    ] {
+      assert_eq!(canonicalize(input).unwrap(), *canonical);
+      assert_eq!(canonicalize(canonical).unwrap(), *canonical);
+    }
+    assert!(matches!(
+      canonicalize("CTRL+NOPE"),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn same_physical_keys() {
+    let hotkey = parse_hotkey("CTRL+SHIFT+1").unwrap();
+    assert!(hotkey.resolves_to_same_physical(&parse_hotkey("ctrl+!").unwrap()));
+    assert!(hotkey.resolves_to_same_physical(&parse_hotkey("SHIFT+CTRL+KEY_1").unwrap()));
+    assert!(!hotkey.resolves_to_same_physical(&parse_hotkey("ALT+SHIFT+1").unwrap()));
+    #[cfg(not(target_os = "macos"))]
+    assert!(parse_hotkey("CmdOrCtrl+A")
+      .unwrap()
+      .resolves_to_same_physical(&parse_hotkey("CTRL+A").unwrap()));
+  }
+
+  #[test]
+  fn decode_flags() {
+    let hotkey = parse_hotkey("CTRL+SHIFT+A").unwrap();
+    assert_eq!(
+      Hotkey::from_flags(hotkey.modifiers_as_flag(), hotkey.keys_as_flag()),
+      DecodeResult {
+        hotkey: Some(hotkey.clone()),
+        unknown_modifier_bits: 0,
+        unknown_key_bits: 0,
+      }
+    );
+
+    let unknown_bit = 1 << 30;
+    assert_eq!(
+      Hotkey::from_flags(
+        hotkey.modifiers_as_flag() | unknown_bit,
+        hotkey.keys_as_flag()
+      ),
+      DecodeResult {
+        hotkey: Some(hotkey.clone()),
+        unknown_modifier_bits: unknown_bit,
+        unknown_key_bits: 0,
+      }
+    );
+
+    let unknown_key = u32::MAX - 1;
+    assert_eq!(
+      Hotkey::from_flags(hotkey.modifiers_as_flag(), unknown_key),
+      DecodeResult {
+        hotkey: None,
+        unknown_modifier_bits: 0,
+        unknown_key_bits: unknown_key,
+      }
+    );
+  }
+
+  #[cfg(target_os = "macos")]
+  #[test]
+  fn globe_modifier() {
+    for input in &["Globe+E", "fn+e"] {
+      assert_eq!(
+        parse_hotkey(input).unwrap(),
+        Hotkey {
+          modifiers: vec![Modifier::GLOBE],
+          keys: vec![Key::E],
+        }
+      );
+    }
+    assert_eq!(canonicalize("Shift+Fn+E").unwrap(), "SHIFT+GLOBE+E");
+  }
+
+  #[cfg(target_os = "macos")]
+  #[test]
+  fn keypad_equals() {
+    for input in &["CMD+NUMEQUAL", "cmd+kpequal"] {
+      assert_eq!(
+        parse_hotkey(input).unwrap(),
+        Hotkey {
+          modifiers: vec![Modifier::SUPER],
+          keys: vec![Key::NUMEQUAL],
+        }
+      );
+    }
+    assert_eq!(canonicalize("cmd+kpequal").unwrap(), "SUPER+NUMEQUAL");
+  }
+
+  #[test]
+  fn duplicated_modifiers() {
+    let hotkey = parse_hotkey("CTRL+CTRL+A").unwrap();
+    assert_eq!(hotkey.modifiers, vec![Modifier::CTRL]);
+    assert_eq!(hotkey.to_string(), "CTRL+A");
+    assert_eq!(
+      parse_hotkey("cmd+super+shift+A").unwrap().modifiers,
+      vec![Modifier::SUPER, Modifier::SHIFT]
+    );
+    // the Shift implied by `!` isn't added twice either
+    assert_eq!(
+      parse_hotkey("SHIFT+!").unwrap().modifiers,
+      vec![Modifier::SHIFT]
+    );
+  }
+
+  #[test]
+  fn short_aliases() {
+    for (alias, key) in &[
+      ("esc", Key::ESCAPE),
+      ("Del", Key::DELETE),
+      ("PGUP", Key::PAGEUP),
+      ("pgdn", Key::PAGEDOWN),
+      ("spc", Key::SPACE),
+      #[cfg(not(target_os = "macos"))]
+      ("ins", Key::INSERT),
+    ] {
+      assert_eq!(parse_hotkey(alias).unwrap().keys, vec![*key], "{}", alias);
+    }
+    #[cfg(target_os = "macos")]
+    assert!(parse_hotkey("INS").is_err());
+
+    for alias in &["win", "Windows", "META"] {
+      assert_eq!(
+        parse_hotkey(&format!("{}+DEL", alias)).unwrap(),
+        Hotkey {
+          modifiers: vec![Modifier::SUPER],
+          keys: vec![Key::DELETE],
+        }
+      );
+    }
+    assert_eq!(canonicalize("ctrl+esc").unwrap(), "CTRL+ESCAPE");
+  }
+
+  #[test]
+  fn primary_modifier() {
+    #[cfg(target_os = "macos")]
+    assert_eq!(Modifier::primary(), Modifier::SUPER);
+    #[cfg(not(target_os = "macos"))]
+    assert_eq!(Modifier::primary(), Modifier::CTRL);
+
+    let hotkey = HotkeyBuilder::new().primary().key(Key::S).build().unwrap();
+    #[cfg(target_os = "macos")]
+    assert_eq!(hotkey, parse_hotkey("SUPER+S").unwrap());
+    #[cfg(not(target_os = "macos"))]
+    assert_eq!(hotkey, parse_hotkey("CTRL+S").unwrap());
+    assert_eq!(
+      hotkey.modifiers_as_flag(),
+      parse_hotkey("CmdOrCtrl+S").unwrap().modifiers_as_flag()
+    );
+    assert!(matches!(
+      HotkeyBuilder::new().primary().build(),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn string_serde() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Config {
+      #[serde(with = "as_string")]
+      toggle: Hotkey,
+    }
+    let config = Config {
+      toggle: parse_hotkey("shift+ctrl+p").unwrap(),
+    };
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(json, r#"{"toggle":"CTRL+SHIFT+P"}"#);
+    assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+    assert_eq!(
+      serde_json::from_str::<Config>(r#"{"toggle":"CmdOrCtrl+Alt+Space"}"#)
+        .unwrap()
+        .toggle,
+      parse_hotkey("CMDORCTRL+ALT+SPACE").unwrap()
+    );
+    let err = serde_json::from_str::<Config>(r#"{"toggle":"CTRL+NOPE"}"#).unwrap_err();
+    assert!(err.to_string().contains("unknown key NOPE"), "{}", err);
+
+    let hotkey = parse_hotkey("ALT+F4").unwrap();
+    let json = serde_json::to_string(&hotkey).unwrap();
+    #[cfg(feature = "string-serde")]
+    assert_eq!(json, r#""ALT+F4""#);
+    #[cfg(not(feature = "string-serde"))]
+    assert_eq!(json, r#"{"modifiers":["ALT"],"keys":["F4"]}"#);
+    assert_eq!(serde_json::from_str::<Hotkey>(&json).unwrap(), hotkey);
+  }
+
+  #[test]
+  fn hotkey_builder() {
+    let hotkey = Hotkey::builder()
+      .modifier(Modifier::SHIFT)
+      .modifier(Modifier::CTRL)
+      .modifier(Modifier::SHIFT)
+      .key(Key::K)
+      .build()
+      .unwrap();
+    assert_eq!(hotkey, parse_hotkey("CTRL+SHIFT+K").unwrap());
+    assert_eq!(hotkey.modifiers.len(), 2);
+
+    let chord = Hotkey::builder().key(Key::A).key(Key::B).build().unwrap();
+    assert_eq!(chord, parse_hotkey("A+B").unwrap());
+    match Hotkey::builder()
+      .key(Key::A)
+      .key(Key::B)
+      .key(Key::A)
+      .build()
+    {
+      Err(err) => assert_eq!(err.to_string(), "failed to parse hotkey: duplicated key A"),
+      Ok(hotkey) => panic!("duplicated key accepted: {}", hotkey),
+    }
+    assert!(matches!(
+      Hotkey::builder().modifier(Modifier::ALT).build(),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn display_symbols() {
+    assert_eq!(Key::OPENBRACKET.display_symbol(), "[");
+    assert_eq!(Key::KEY_0.display_symbol(), "0");
+    assert_eq!(Key::UP.display_symbol(), "Arrow Up");
+    assert_eq!(Key::Q.display_symbol(), "Q");
+    assert_eq!(Key::F24.display_symbol(), "F24");
+    assert!(Key::iter().all(|key| !key.display_symbol().is_empty()));
+    assert_eq!(
+      Modifier::COMMAND_OR_CONTROL.display_symbol(),
+      Modifier::primary().display_symbol()
+    );
+    #[cfg(target_os = "macos")]
+    assert_eq!(Modifier::SUPER.display_symbol(), "⌘");
+    #[cfg(target_os = "windows")]
+    assert_eq!(Modifier::SUPER.display_symbol(), "Win");
+    #[cfg(not(target_os = "macos"))]
+    assert_eq!(Modifier::CTRL.display_symbol(), "Ctrl");
+  }
+
+  #[test]
+  fn localized_names() {
+    assert_eq!(Key::ENTER.localized_name("es"), "Intro");
+    assert_eq!(Key::ENTER.localized_name("es-MX"), "Intro");
+    assert_eq!(Key::ESCAPE.localized_name("fr_FR"), "Échap");
+    assert_eq!(Key::ENTER.localized_name("en-US"), "ENTER");
+    assert_eq!(Key::A.localized_name("de"), "A");
+    assert_eq!(
+      parse_hotkey("shift+ctrl+enter")
+        .unwrap()
+        .to_localized_string("de"),
+      "Strg+Umschalt+Eingabe"
+    );
+
+    set_localized_key_name("pt-BR", Key::SPACE, "Espaço");
+    assert_eq!(Key::SPACE.localized_name("pt"), "Espaço");
+    assert_eq!(
+      parse_hotkey("CTRL+SPACE")
+        .unwrap()
+        .to_localized_string("pt-PT"),
+      "CTRL+Espaço"
+    );
+  }
+
+  #[test]
+  fn ui_sort_order() {
+    let mut hotkeys: Vec<Hotkey> = [
+      "CTRL+SHIFT+A",
+      "SHIFT+B",
+      "F5",
+      "ALT+A",
+      "CTRL+B",
+      "SHIFT+CTRL+1",
+      "CTRL+A",
+      "ENTER",
+    ]
+    .iter()
+    .map(|hotkey| parse_hotkey(hotkey).unwrap())
+    .collect();
+    hotkeys.sort_by_key(Hotkey::ui_sort_key);
+    assert_eq!(
+      hotkeys
+        .iter()
+        .map(|hotkey| hotkey.normalize().to_string())
+        .collect::<Vec<_>>(),
+      vec![
+        "ENTER",
+        "F5",
+        "CTRL+A",
+        "CTRL+B",
+        "ALT+A",
+        "SHIFT+B",
+        "CTRL+SHIFT+1",
+        "CTRL+SHIFT+A",
+      ]
+    );
+  }
+
+  #[test]
+  fn numpad_digits() {
+    for input in &["CTRL+NP5", "CTRL+KP5", "ctrl+numpad5"] {
+      assert_eq!(
+        parse_hotkey(input).unwrap(),
+        Hotkey {
+          modifiers: vec![Modifier::CTRL],
+          keys: vec![Key::NUMPAD5]
+        }
+      );
+    }
+    assert_eq!(
+      parse_hotkey("CTRL+5").unwrap(),
+      Hotkey {
+        modifiers: vec![Modifier::CTRL],
+        keys: vec![Key::KEY_5]
+      }
+    );
+    assert!(parse_hotkey("NP10").is_err());
+  }
+
   #[cfg(not(target_os = "macos"))]
-  LAUNCHMAIL = keys::LAUNCH_MAIL,
-  // F1-F12
-  F1 = keys::F1,
-  F2 = keys::F2,
-  F3 = keys::F3,
-  F4 = keys::F4,
-  F5 = keys::F5,
-  F6 = keys::F6,
-  F7 = keys::F7,
-  F8 = keys::F8,
-  F9 = keys::F9,
-  F10 = keys::F10,
-  F11 = keys::F11,
-  F12 = keys::F12,
-  // Numpad
-  NUMADD = keys::ADD,
-  NUMSUB = keys::SUBTRACT,
-  NUMMULT = keys::MULTIPLY,
-  NUMDIV = keys::DIVIDE,
-  NUMDEC = keys::DECIMAL,
-  #[serde(rename = "0")]
-  KEY_0 = keys::KEY_0,
-  #[serde(rename = "1")]
-  KEY_1 = keys::KEY_1,
-  #[serde(rename = "2")]
-  KEY_2 = keys::KEY_2,
-  #[serde(rename = "3")]
-  KEY_3 = keys::KEY_3,
-  #[serde(rename = "4")]
-  KEY_4 = keys::KEY_4,
-  #[serde(rename = "5")]
-  KEY_5 = keys::KEY_5,
-  #[serde(rename = "6")]
-  KEY_6 = keys::KEY_6,
-  #[serde(rename = "7")]
-  KEY_7 = keys::KEY_7,
-  #[serde(rename = "8")]
-  KEY_8 = keys::KEY_8,
-  #[serde(rename = "9")]
-  KEY_9 = keys::KEY_9,
-  A = keys::A,
-  B = keys::B,
-  C = keys::C,
-  D = keys::D,
-  E = keys::E,
-  F = keys::F,
-  G = keys::G,
-  H = keys::H,
-  I = keys::I,
-  J = keys::J,
-  K = keys::K,
-  L = keys::L,
-  M = keys::M,
-  N = keys::N,
-  O = keys::O,
-  P = keys::P,
-  Q = keys::Q,
-  R = keys::R,
-  S = keys::S,
-  T = keys::T,
-  U = keys::U,
-  V = keys::V,
-  W = keys::W,
-  X = keys::X,
-  Y = keys::Y,
-  Z = keys::Z,
-  #[serde(rename = "=")]
-  EQUAL = keys::EQUAL,
-  #[serde(rename = "-")]
-  MINUS = keys::MINUS,
-  #[serde(rename = "'")]
-  SINGLEQUOTE = keys::SINGLE_QUOTE,
-  #[serde(rename = ",")]
-  COMMA = keys::COMMA,
-  #[serde(rename = ".")]
-  PERIOD = keys::PERIOD,
-  #[serde(rename = ";")]
-  SEMICOLON = keys::SEMICOLON,
-  #[serde(rename = "/")]
-  SLASH = keys::SLASH,
-  #[serde(rename = "`")]
-  OPENQUOTE = keys::OPEN_QUOTE,
-  #[serde(rename = "[")]
-  OPENBRACKET = keys::OPEN_BRACKET,
-  #[serde(rename = "\\")]
-  BACKSLASH = keys::BACK_SLASH,
-  #[serde(rename = "]")]
-  CLOSEBRACKET = keys::CLOSE_BRACKET,
-}
+  #[test]
+  fn menu_key() {
+    for token in &["MENU", "Apps", "ContextMenu"] {
+      assert_eq!(
+        parse_hotkey(&format!("SHIFT+{}", token)).unwrap(),
+        Hotkey {
+          modifiers: vec![Modifier::SHIFT],
+          keys: vec![Key::MENU]
+        }
+      );
+    }
+  }
+
+  #[cfg(target_os = "windows")]
+  #[test]
+  fn altgr_flags() {
+    let hotkey = parse_hotkey("ALTGR+E").unwrap();
+    assert_eq!(
+      hotkey.modifiers_as_flag(),
+      modifiers::CONTROL | modifiers::ALT
+    );
+  }
+
+  #[test]
+  fn altgr_conflict() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let ctrl_alt = parse_hotkey("CTRL+ALT+E").unwrap();
+    let altgr = parse_hotkey("ALTGR+E").unwrap();
+    manager.register(ctrl_alt.clone(), || {}).unwrap();
+    let result = manager.register(altgr.clone(), || {});
+    if cfg!(target_os = "windows") {
+      assert!(matches!(
+        result,
+        Err(Error::HotkeyConflict(hotkey, existing)) if hotkey == altgr && existing == ctrl_alt
+      ));
+      assert!(!manager.is_registered(&altgr));
+    } else {
+      result.unwrap();
+    }
+
+    // logical modifiers resolve to the same shortcut on every platform
+    let primary = parse_hotkey("CMDORCTRL+E").unwrap();
+    #[cfg(target_os = "macos")]
+    let resolved = parse_hotkey("SUPER+E").unwrap();
+    #[cfg(not(target_os = "macos"))]
+    let resolved = parse_hotkey("CTRL+E").unwrap();
+    manager.register(resolved.clone(), || {}).unwrap();
+    assert!(matches!(
+      manager.register(primary, || {}),
+      Err(Error::HotkeyConflict(_, existing)) if existing == resolved
+    ));
+  }
+
+  #[test]
+  fn lost_registration() {
+    let hotkey = parse_hotkey("CTRL+ALT+T").unwrap();
+    let mut manager = HotkeyManager::new();
+    manager.register(hotkey.clone(), || {}).unwrap();
+    assert!(!check_registrations().contains(&hotkey));
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    on_hotkey_lost(move |hotkey| {
+      let _ = tx.send(hotkey);
+    });
+    let _monitor = RegistrationMonitor::start(Duration::from_millis(10));
+
+    // another application takes the hotkey over, the listener still lists it as grabbed
+    GLOBAL_REGISTRY
+      .listener()
+      .lock()
+      .unwrap()
+      .take_over(hotkey.listener_hotkey());
+    assert!(check_registrations().contains(&hotkey));
+    assert_eq!(rx.recv_timeout(Duration::from_secs(1)).unwrap(), hotkey);
+    assert!(check_registrations().contains(&hotkey));
+
+    // grabbed again once the other application releases it
+    GLOBAL_REGISTRY
+      .listener()
+      .lock()
+      .unwrap()
+      .give_back(hotkey.listener_hotkey());
+    assert!(!check_registrations().contains(&hotkey));
+    manager.unregister(&hotkey).unwrap();
+  }
+
+  #[test]
+  fn polled_hotkey() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+F").unwrap();
+    let mut manager = HotkeyManager::new();
+    let flag = manager.register_polled(hotkey.clone()).unwrap();
+    assert!(!flag.take_pressed());
+    dispatch(&hotkey);
+    assert!(flag.take_pressed());
+    assert!(!flag.take_pressed());
+  }
+
+  #[test]
+  fn channel_hotkey() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+M").unwrap();
+    let wakes = Arc::new(AtomicUsize::new(0));
+    let counter = wakes.clone();
+    let mut manager = HotkeyManager::new();
+    let receiver = manager
+      .register_channel_with_waker(hotkey.clone(), move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+    dispatch(&hotkey);
+    dispatch(&hotkey);
+    assert_eq!(wakes.load(Ordering::SeqCst), 2);
+    assert_eq!(
+      receiver.try_iter().collect::<Vec<_>>(),
+      vec![hotkey.clone(); 2]
+    );
+    assert!(receiver.try_recv().is_err());
+
+    let plain = parse_hotkey("CTRL+ALT+SHIFT+M").unwrap();
+    let presses = manager.register_channel(plain.clone()).unwrap();
+    dispatch(&plain);
+    assert_eq!(presses.try_recv(), Ok(()));
+    assert!(presses.try_recv().is_err());
+    // dropping the receiver doesn't break the dispatch
+    drop(presses);
+    dispatch(&plain);
+  }
+
+  #[test]
+  fn disabled_hotkeys() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+N").unwrap();
+    let mut manager = HotkeyManager::new();
+    let flag = manager.register_polled(hotkey.clone()).unwrap();
+
+    let result = with_hotkeys_disabled(|| {
+      assert!(is_dispatch_paused());
+      dispatch(&hotkey);
+      42
+    });
+    assert_eq!(result, 42);
+    assert!(!is_dispatch_paused());
+    assert!(!flag.take_pressed());
+
+    let panicked = std::panic::catch_unwind(|| with_hotkeys_disabled(|| panic!("macro failed")));
+    assert!(panicked.is_err());
+    assert!(!is_dispatch_paused());
+    dispatch(&hotkey);
+    assert!(flag.take_pressed());
+  }
+
+  #[test]
+  fn throttled_trigger_logs() {
+    let hotkey = parse_hotkey("CTRL+ALT+O").unwrap();
+    let text = format!("hotkey {} triggered", hotkey);
+    assert_eq!(logged_lines(&text), 0);
+    for _ in 0..1000 {
+      dispatch(&hotkey);
+    }
+    let logged = logged_lines(&text);
+    assert!(logged >= 1);
+    // the bucket refills while the loop runs, so allow for a second worth of lines
+    assert!(logged <= 2 * TRIGGER_LOGS_PER_SECOND as usize);
+  }
+
+  #[test]
+  fn once_per_focus() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+P").unwrap();
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_once_per_focus(hotkey.clone(), move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+
+    dispatch(&hotkey);
+    dispatch(&hotkey);
+    assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+    set_app_focused(false);
+    dispatch(&hotkey);
+    assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+    set_app_focused(true);
+    dispatch(&hotkey);
+    dispatch(&hotkey);
+    assert_eq!(fired.load(Ordering::SeqCst), 2);
+
+    // focusing an already focused app doesn't start a new session
+    set_app_focused(true);
+    dispatch(&hotkey);
+    assert_eq!(fired.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn cheatsheets() {
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_labeled("Quick search", parse_hotkey("ALT+CTRL+Q").unwrap(), || {})
+      .unwrap();
+    manager
+      .register_labeled("Record | stop", parse_hotkey("CTRL+ALT+R").unwrap(), || {})
+      .unwrap();
+    manager
+      .register(parse_hotkey("CTRL+ALT+S").unwrap(), || {})
+      .unwrap();
+
+    assert_eq!(
+      manager.to_cheatsheet(CheatsheetFormat::PlainText),
+      "Quick search   CTRL+ALT+Q\nRecord | stop  CTRL+ALT+R\n"
+    );
+    assert_eq!(
+      manager.to_cheatsheet(CheatsheetFormat::Markdown),
+      "| Action | Shortcut |\n| --- | --- |\n\
+       | Quick search | `CTRL+ALT+Q` |\n\
+       | Record \\| stop | `CTRL+ALT+R` |\n"
+    );
+    let global = cheatsheet(CheatsheetFormat::Markdown);
+    assert!(global.contains("| Quick search | `CTRL+ALT+Q` |"));
+    assert!(!global.contains("CTRL+ALT+S"));
+  }
+
+  #[test]
+  fn metrics() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+G").unwrap();
+    let mut manager = HotkeyManager::new();
+    manager.register(hotkey.clone(), || {}).unwrap();
+
+    // other tests dispatch concurrently, so only lower bounds can be asserted
+    let before = dispatch_metrics();
+    dispatch(&hotkey);
+    let after = dispatch_metrics();
+    assert!(after.total_triggers > before.total_triggers);
+    assert!(after.total_callbacks > before.total_callbacks);
+
+    manager.unregister(&hotkey).unwrap();
+    let before = dispatch_metrics();
+    dispatch(&hotkey);
+    let after = dispatch_metrics();
+    assert!(after.total_triggers > before.total_triggers);
+    assert!(after.dropped > before.dropped);
+  }
+
+  #[test]
+  fn function_keys() {
+    let _lock = dispatch_lock();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_function_keys(move |key| {
+        let _ = tx.send(key);
+      })
+      .unwrap();
+    assert!(manager.is_registered(&parse_hotkey("F12").unwrap()));
+    assert!(manager.is_registered(&parse_hotkey("F20").unwrap()));
+
+    dispatch(&parse_hotkey("F5").unwrap());
+    assert_eq!(rx.try_recv(), Ok(Key::F5));
+    assert!(rx.try_recv().is_err());
+  }
+
+  #[test]
+  fn all_registered() {
+    let first = parse_hotkey("CTRL+SHIFT+ALT+Y").unwrap();
+    let second = parse_hotkey("CTRL+SHIFT+ALT+Z").unwrap();
+    let mut manager = HotkeyManager::new();
+    let mut other_manager = HotkeyManager::new();
+    manager.register(first.clone(), || {}).unwrap();
+    other_manager.register(second.clone(), || {}).unwrap();
+    let registered = all_registered_hotkeys();
+    assert!(registered.contains(&first));
+    assert!(registered.contains(&second));
+
+    other_manager.unregister(&second).unwrap();
+    let registered = all_registered_hotkeys();
+    assert!(registered.contains(&first));
+    assert!(!registered.contains(&second));
+  }
+
+  #[test]
+  fn extended_function_keys() {
+    assert_eq!(
+      parse_hotkey("F13").unwrap(),
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::F13],
+      }
+    );
+    let hotkey = parse_hotkey("ctrl+f24").unwrap();
+    assert_eq!(hotkey.keys, vec![Key::F24]);
+    assert_eq!(hotkey.to_string(), "CTRL+F24");
+    assert_ne!(
+      hotkey.listener_hotkey(),
+      parse_hotkey("CTRL+F23").unwrap().listener_hotkey()
+    );
+
+    let scroll_lock = parse_hotkey("SCROLLLOCK").unwrap();
+    assert_eq!(scroll_lock.keys, vec![Key::SCROLLLOCK]);
+    assert_eq!(scroll_lock.to_string(), "SCROLLLOCK");
+    // the F14 key of Mac keyboards has the Scroll Lock key code
+    #[cfg(target_os = "macos")]
+    assert_eq!(parse_hotkey("F14").unwrap(), scroll_lock);
+    #[cfg(not(target_os = "macos"))]
+    assert_eq!(parse_hotkey("F14").unwrap().keys, vec![Key::F14]);
+  }
+
+  #[test]
+  fn platform_codes() {
+    let hotkey = parse_hotkey("CMDORCTRL+SHIFT+K").unwrap();
+    let modifiers = hotkey
+      .modifiers
+      .iter()
+      .fold(0, |flags, modifier| flags | modifier.platform_code());
+    assert_eq!(modifiers, hotkey.modifiers_as_flag());
+    assert_eq!(Key::K.platform_code(), hotkey.keys_as_flag());
+    assert_eq!(Key::K.platform_code(), keys::K);
+    assert_eq!(Modifier::SHIFT.platform_code(), modifiers::SHIFT);
+    assert_eq!(
+      Modifier::COMMAND_OR_CONTROL.platform_code(),
+      Modifier::primary().platform_code()
+    );
+    #[cfg(target_os = "windows")]
+    assert_eq!(Key::A.platform_code(), 0x41);
+  }
+
+  #[test]
+  fn platform_keys() {
+    assert!(Key::A.is_supported_on_platform());
+    assert!(Key::F13.is_supported_on_platform());
+    #[cfg(target_os = "macos")]
+    for key in &[Key::PRINTSCREEN, Key::NUMLOCK, Key::LAUNCHMAIL, Key::F21] {
+      assert!(!key.is_supported_on_platform(), "{}", key);
+    }
+    #[cfg(target_os = "macos")]
+    {
+      let registry = Arc::new(HotkeyRegistry::new());
+      let mut manager = HotkeyManager::with_registry(registry);
+      let err = manager
+        .register(parse_hotkey("SHIFT+PRINTSCREEN").unwrap(), || {})
+        .unwrap_err();
+      assert_eq!(err.to_string(), "key PRINTSCREEN is not supported on macos");
+      assert!(manager.registered_hotkeys().is_empty());
+    }
+    #[cfg(not(target_os = "macos"))]
+    assert!(Key::iter().all(|key| key.is_supported_on_platform()));
+  }
+
+  #[test]
+  fn alternative_hotkeys() {
+    let _lock = dispatch_lock();
+    let up = parse_hotkey("CTRL+ALT+UP").unwrap();
+    let down = parse_hotkey("CTRL+ALT+DOWN").unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_alternatives(vec![up.clone(), down.clone()], move |hotkey| {
+        let _ = tx.send(hotkey.clone());
+      })
+      .unwrap();
+
+    dispatch(&down);
+    dispatch(&up);
+    assert_eq!(
+      rx.try_iter().collect::<Vec<_>>(),
+      vec![down.clone(), up.clone()]
+    );
+
+    // a failing alternative rolls back the ones registered before it
+    let left = parse_hotkey("CTRL+ALT+LEFT").unwrap();
+    assert!(matches!(
+      manager.register_alternatives(vec![left.clone(), up], |_| {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    assert!(!manager.is_registered(&left));
+  }
+
+  #[test]
+  fn config_entries() {
+    let _lock = dispatch_lock();
+    let entries: HashMap<String, HotkeyEntry> = toml::from_str(
+      r#"
+        open = { accelerator = "CTRL+SHIFT+ALT+O", label = "Open" }
+        [save]
+        accelerator = "CTRL+SHIFT+ALT+S"
+        label = "Save"
+      "#,
+    )
+    .unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_entries(&entries, move |action| {
+        let _ = tx.send(action.to_string());
+      })
+      .unwrap();
+
+    dispatch(&parse_hotkey("CTRL+SHIFT+ALT+S").unwrap());
+    dispatch(&parse_hotkey("CTRL+SHIFT+ALT+O").unwrap());
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["save", "open"]);
+    assert_eq!(
+      manager.to_cheatsheet(CheatsheetFormat::PlainText),
+      "Open  CTRL+ALT+SHIFT+O\nSave  CTRL+ALT+SHIFT+S\n"
+    );
+
+    // a conflicting entry rolls back the entries registered before it
+    let mut other_manager = HotkeyManager::new();
+    let mut conflicting = entries.clone();
+    conflicting.insert(
+      "quit".to_string(),
+      HotkeyEntry {
+        accelerator: "CTRL+SHIFT+ALT+Q".to_string(),
+        label: "Quit".to_string(),
+      },
+    );
+    other_manager
+      .register(parse_hotkey("CTRL+SHIFT+ALT+S").unwrap(), || {})
+      .unwrap();
+    assert!(other_manager
+      .register_entries(&conflicting, |_| {})
+      .is_err());
+    assert_eq!(other_manager.snapshot_config().len(), 1);
+  }
+
+  #[cfg(feature = "rpc")]
+  #[test]
+  fn rpc_requests() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let manager = HotkeyManager::with_registry(registry.clone());
+    let triggered = Arc::new(Mutex::new(Vec::new()));
+    let triggered_ = triggered.clone();
+    let mut server = RpcServer::new(manager, move |hotkey| {
+      triggered_.lock().unwrap().push(hotkey.clone())
+    });
+    let hotkey = |accelerator: &str| accelerator.to_string();
+
+    assert_eq!(
+      server.handle_rpc(RpcRequest::Register {
+        hotkey: hotkey("shift+ctrl+alt+n")
+      }),
+      RpcResponse::Ok
+    );
+    assert_eq!(
+      server.handle_rpc(RpcRequest::Register {
+        hotkey: hotkey("CTRL+SHIFT+ALT+P")
+      }),
+      RpcResponse::Ok
+    );
+    assert!(matches!(
+      server.handle_rpc(RpcRequest::Register {
+        hotkey: hotkey("CTRL+SHIFT+ALT+P")
+      }),
+      RpcResponse::Error(_)
+    ));
+    assert!(matches!(
+      server.handle_rpc(RpcRequest::Register {
+        hotkey: hotkey("CTRL+NOPE")
+      }),
+      RpcResponse::Error(_)
+    ));
+    assert_eq!(
+      server.handle_rpc(RpcRequest::List),
+      RpcResponse::Hotkeys(vec![
+        "CTRL+ALT+SHIFT+N".to_string(),
+        "CTRL+ALT+SHIFT+P".to_string()
+      ])
+    );
+
+    // only the server's own handler runs
+    let mut other_manager = HotkeyManager::with_registry(registry);
+    let other_fired = Arc::new(AtomicUsize::new(0));
+    let other_fired_ = other_fired.clone();
+    other_manager
+      .register(parse_hotkey("CTRL+SHIFT+ALT+N").unwrap(), move || {
+        other_fired_.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+    assert_eq!(
+      server.handle_rpc(RpcRequest::Trigger {
+        hotkey: hotkey("CTRL+SHIFT+ALT+N")
+      }),
+      RpcResponse::Ok
+    );
+    assert_eq!(
+      *triggered.lock().unwrap(),
+      vec![parse_hotkey("CTRL+SHIFT+ALT+N").unwrap().normalize()]
+    );
+    assert_eq!(other_fired.load(Ordering::SeqCst), 0);
+
+    assert_eq!(
+      server.handle_rpc(RpcRequest::Unregister {
+        hotkey: hotkey("CTRL+SHIFT+ALT+N")
+      }),
+      RpcResponse::Ok
+    );
+    assert!(matches!(
+      server.handle_rpc(RpcRequest::Trigger {
+        hotkey: hotkey("CTRL+SHIFT+ALT+N")
+      }),
+      RpcResponse::Error(_)
+    ));
+    assert!(matches!(
+      server.handle_rpc(RpcRequest::Unregister {
+        hotkey: hotkey("CTRL+SHIFT+ALT+N")
+      }),
+      RpcResponse::Error(_)
+    ));
+    assert_eq!(
+      server.handle_rpc(RpcRequest::List),
+      RpcResponse::Hotkeys(vec!["CTRL+ALT+SHIFT+P".to_string()])
+    );
+    assert_eq!(triggered.lock().unwrap().len(), 1);
+  }
+
+  #[test]
+  fn numeric_keys() {
+    for input in &["42", "CTRL+10", "00"] {
+      match parse_hotkey(input) {
+        Err(Error::InvalidHotkey(message)) => {
+          assert!(message.starts_with("numeric key must be a single digit 0-9"))
+        }
+        other => panic!("unexpected result {:?}", other),
+      }
+    }
+    assert_eq!(
+      parse_hotkey("5").unwrap(),
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::KEY_5],
+      }
+    );
+  }
+
+  #[cfg(feature = "devices")]
+  #[test]
+  fn device_hotkeys() {
+    let mut manager = HotkeyManager::new();
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+X").unwrap();
+    assert!(matches!(
+      manager.register_on_device("/dev/input/missing", hotkey.clone(), || {}),
+      Err(Error::System(_))
+    ));
+    assert!(matches!(
+      manager.unregister_on_device("/dev/input/missing", &hotkey),
+      Err(Error::HotkeyNotRegistered(_))
+    ));
+    assert!(!manager.is_registered(&hotkey));
+  }
+
+  #[test]
+  fn enabled_hotkeys() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let mut other = HotkeyManager::with_registry(registry.clone());
+    let first = parse_hotkey("CTRL+SHIFT+ALT+U").unwrap();
+    let second = parse_hotkey("CTRL+SHIFT+ALT+V").unwrap();
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    for (manager, name) in [(&mut manager, "first"), (&mut other, "other")] {
+      let fired = fired.clone();
+      manager
+        .register(first.clone(), move || fired.lock().unwrap().push(name))
+        .unwrap();
+    }
+    let fired_ = fired.clone();
+    manager
+      .register(second.clone(), move || {
+        fired_.lock().unwrap().push("second")
+      })
+      .unwrap();
+
+    manager.set_enabled(&first, false).unwrap();
+    registry.dispatch(&first);
+    assert_eq!(*fired.lock().unwrap(), vec!["other"]);
+    assert!(manager.is_registered(&first));
+    manager.set_enabled(&first, true).unwrap();
+    registry.dispatch(&first);
+    assert_eq!(fired.lock().unwrap().len(), 3);
+
+    fired.lock().unwrap().clear();
+    manager.set_all_enabled(false);
+    registry.dispatch(&first);
+    registry.dispatch(&second);
+    assert_eq!(*fired.lock().unwrap(), vec!["other"]);
+    manager.set_all_enabled(true);
+    registry.dispatch(&second);
+    assert_eq!(*fired.lock().unwrap(), vec!["other", "second"]);
+
+    assert!(matches!(
+      manager.set_enabled(&parse_hotkey("CTRL+SHIFT+ALT+W").unwrap(), false),
+      Err(Error::HotkeyNotRegistered(_))
+    ));
+  }
+
+  #[test]
+  fn display_round_trip() {
+    let hotkeys = vec![
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::F1],
+      },
+      Hotkey {
+        modifiers: vec![Modifier::CTRL, Modifier::SHIFT, Modifier::ALT],
+        keys: vec![Key::KEY_1],
+      },
+      Hotkey {
+        modifiers: vec![Modifier::COMMAND_OR_CONTROL, Modifier::SUPER],
+        keys: vec![Key::SLASH],
+      },
+      Hotkey {
+        modifiers: vec![],
+        keys: vec![Key::A, Key::B],
+      },
+      Hotkey {
+        modifiers: vec![Modifier::SHIFT, Modifier::CTRL],
+        keys: vec![Key::NUMPAD5, Key::F12, Key::SPACE],
+      },
+    ];
+    for hotkey in hotkeys {
+      assert_eq!(parse_hotkey(&hotkey.to_string()).unwrap(), hotkey);
+    }
+    for key in Key::iter() {
+      let hotkey = Hotkey {
+        modifiers: vec![Modifier::ALT],
+        keys: vec![key],
+      };
+      assert_eq!(parse_hotkey(&hotkey.to_string()).unwrap(), hotkey);
+    }
+  }
+
+  #[test]
+  fn parse_large_keymap() {
+    warm_up_parser();
+    let modifier_sets = [
+      vec![],
+      vec![Modifier::CTRL],
+      vec![Modifier::ALT, Modifier::SHIFT],
+      vec![Modifier::COMMAND_OR_CONTROL, Modifier::SUPER],
+    ];
+    let mut keymap = Vec::new();
+    for modifiers in &modifier_sets {
+      for key in Key::iter() {
+        keymap.push(Hotkey {
+          modifiers: modifiers.clone(),
+          keys: vec![key],
+        });
+      }
+    }
+    for hotkey in &keymap {
+      assert_eq!(&parse_hotkey(&hotkey.to_string()).unwrap(), hotkey);
+    }
+    for (input, expected) in &[
+      ("cmd+np5", "SUPER+NUMPAD5"),
+      ("Control+Return", "CTRL+ENTER"),
+      ("alt+?", "ALT+SHIFT+SLASH"),
+      ("CmdOrCtrl+plus", "COMMANDORCONTROL+SHIFT+EQUAL"),
+      ("key_7", "7"),
+    ] {
+      assert_eq!(canonicalize(input).unwrap(), *expected);
+    }
+  }
+
+  #[test]
+  fn hotkey_ids() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry);
+    let first = parse_hotkey("CTRL+ALT+SHIFT+F1").unwrap();
+    let second = parse_hotkey("SHIFT+ALT+CTRL+F2").unwrap();
+    let first_id = manager.register_with_id(first.clone(), || {}).unwrap();
+    let second_id = manager.register_with_id(second.clone(), || {}).unwrap();
+    assert_ne!(first_id, second_id);
+
+    manager.unregister_id(first_id).unwrap();
+    assert!(!manager.is_registered(&first));
+    assert!(manager.is_registered(&second));
+    assert!(matches!(
+      manager.unregister_id(first_id),
+      Err(Error::UnknownHotkeyId(id)) if id == first_id
+    ));
+
+    // unregistering the hotkey itself forgets its id
+    manager.unregister(&second).unwrap();
+    assert!(matches!(
+      manager.unregister_id(second_id),
+      Err(Error::UnknownHotkeyId(_))
+    ));
+
+    // so does unregister_all, even once the hotkey is registered again without an id
+    let third_id = manager.register_with_id(first.clone(), || {}).unwrap();
+    manager.unregister_all().unwrap();
+    manager.register(first.clone(), || {}).unwrap();
+    assert!(matches!(
+      manager.unregister_id(third_id),
+      Err(Error::UnknownHotkeyId(id)) if id == third_id
+    ));
+    assert!(manager.is_registered(&first));
+  }
+
+  #[test]
+  fn fallible_handlers() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("CTRL+ALT+SHIFT+E").unwrap();
+    let fail = Arc::new(AtomicBool::new(true));
+    let fail_ = fail.clone();
+    manager
+      .register_fallible(hotkey.clone(), move || {
+        if fail_.load(Ordering::Relaxed) {
+          let err = std::io::Error::other("save failed");
+          return Err(Box::new(err));
+        }
+        Ok(())
+      })
+      .unwrap();
+
+    // logged without a sink
+    let logged = logged_lines("save failed");
+    registry.dispatch(&hotkey);
+    assert_eq!(logged_lines("save failed"), logged + 1);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    manager.on_error(move |hotkey, err| {
+      let _ = tx.send(format!("{}: {}", hotkey, err));
+    });
+    registry.dispatch(&hotkey);
+    fail.store(false, Ordering::Relaxed);
+    registry.dispatch(&hotkey);
+    assert_eq!(
+      rx.try_iter().collect::<Vec<_>>(),
+      vec!["CTRL+ALT+SHIFT+E: save failed"]
+    );
+    assert_eq!(logged_lines("save failed"), logged + 1);
+  }
+
+  #[test]
+  fn register_many() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let escape = parse_hotkey("ESCAPE").unwrap();
+    let q = parse_hotkey("Q").unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    let counter_ = counter.clone();
+    let ids = manager
+      .register_many(vec![escape.clone(), q.clone()], move || {
+        counter_.fetch_add(1, Ordering::Relaxed);
+      })
+      .unwrap();
+    assert_eq!(ids.len(), 2);
+    registry.dispatch(&escape);
+    registry.dispatch(&q);
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
+
+    // a failing hotkey rolls back the ones registered before it
+    let w = parse_hotkey("W").unwrap();
+    assert!(matches!(
+      manager.register_many(vec![w.clone(), q.clone()], || {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    assert!(!manager.is_registered(&w));
+    assert_eq!(manager.registered_hotkeys(), &[escape, q]);
+
+    for id in ids {
+      manager.unregister_id(id).unwrap();
+    }
+    assert!(manager.registered_hotkeys().is_empty());
+  }
+
+  #[test]
+  fn repeating_hotkeys() {
+    assert!(ListenerConfig::default().no_repeat);
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let repeating = parse_hotkey("CTRL+ALT+DOWN").unwrap();
+    let single = parse_hotkey("CTRL+ALT+UP").unwrap();
+    manager
+      .register_with_repeat(repeating.clone(), true, || {})
+      .unwrap();
+    manager.register(single.clone(), || {}).unwrap();
+    let allows_repeat = |hotkey: &Hotkey| {
+      registry
+        .listener()
+        .lock()
+        .unwrap()
+        .allows_repeat(hotkey.listener_hotkey())
+    };
+    assert!(allows_repeat(&repeating));
+    assert!(!allows_repeat(&single));
+
+    // kept when the hotkeys are grabbed again
+    registry.set_active(false).unwrap();
+    registry.set_active(true).unwrap();
+    assert!(allows_repeat(&repeating));
+    assert!(!allows_repeat(&single));
+  }
+
+  #[test]
+  fn modifier_order_equality() {
+    use std::collections::hash_map::DefaultHasher;
+    let hash = |hotkey: &Hotkey| {
+      let mut hasher = DefaultHasher::new();
+      hotkey.hash(&mut hasher);
+      hasher.finish()
+    };
+    let hotkey = parse_hotkey("CTRL+SHIFT+A").unwrap();
+    let reordered = parse_hotkey("SHIFT+CTRL+A").unwrap();
+    assert_eq!(hotkey, reordered);
+    assert_eq!(hash(&hotkey), hash(&reordered));
+    assert_eq!(hotkey.modifiers_as_flag(), reordered.modifiers_as_flag());
+    assert_ne!(hotkey, parse_hotkey("CTRL+A").unwrap());
+    assert_ne!(hotkey, parse_hotkey("CTRL+SHIFT+B").unwrap());
+
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry);
+    manager.register(hotkey, || {}).unwrap();
+    assert!(manager.is_registered(&reordered));
+    assert!(matches!(
+      manager.register(reordered.clone(), || {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    manager.unregister(&reordered).unwrap();
+    assert!(manager.registered_hotkeys().is_empty());
+  }
+
+  #[test]
+  fn normalize_hotkeys() {
+    let hotkey = parse_hotkey("SHIFT+CTRL+B+A").unwrap();
+    let reordered = parse_hotkey("CTRL+SHIFT+A+B").unwrap();
+    assert_ne!(hotkey, reordered);
+    assert_eq!(hotkey.normalize(), reordered.normalize());
+    assert_eq!(hotkey.normalize().to_string(), "CTRL+SHIFT+A+B");
+    assert_eq!(hotkey.normalize(), hotkey.normalize().normalize());
+
+    let duplicated = Hotkey {
+      modifiers: vec![Modifier::ALT, Modifier::CTRL, Modifier::ALT],
+      keys: vec![Key::F1, Key::F1],
+    };
+    assert_eq!(duplicated.normalize().to_string(), "CTRL+ALT+F1");
+  }
+
+  #[test]
+  fn no_repeat_hotkeys() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let mut other = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("VOLUMEUP").unwrap();
+    manager.register_no_repeat(hotkey.clone(), || {}).unwrap();
+    other
+      .register_with_repeat(hotkey.clone(), true, || {})
+      .unwrap();
+    let listener = registry.listener().lock().unwrap();
+    assert!(listener.never_repeats(hotkey.listener_hotkey()));
+    assert!(!listener.allows_repeat(hotkey.listener_hotkey()));
+    drop(listener);
+    // the string form and the OS flags stay those of the plain hotkey
+    assert_eq!(hotkey.to_string(), "VOLUMEUP");
+    assert_eq!(hotkey.modifiers_as_flag(), 0);
+  }
+
+  #[test]
+  fn callback_with_hotkey() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let pressed = Arc::new(Mutex::new(Vec::new()));
+    let route = |pressed: Arc<Mutex<Vec<Hotkey>>>| {
+      move |hotkey: &Hotkey| pressed.lock().unwrap().push(hotkey.clone())
+    };
+    let copy = parse_hotkey("CTRL+C").unwrap();
+    let paste = parse_hotkey("CTRL+V").unwrap();
+    manager
+      .register_with_hotkey(copy.clone(), route(pressed.clone()))
+      .unwrap();
+    manager
+      .register_with_hotkey(paste.clone(), route(pressed.clone()))
+      .unwrap();
+
+    registry.dispatch(&paste);
+    registry.dispatch(&copy);
+    assert_eq!(*pressed.lock().unwrap(), vec![paste, copy]);
+  }
+
+  #[test]
+  fn recovery_observer() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    set_backend_recovery_observer(move |event| {
+      let _ = tx.send(event);
+    });
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+W").unwrap();
+    manager.register(hotkey.clone(), || {}).unwrap();
+
+    registry.set_active(false).unwrap();
+    registry.set_active(true).unwrap();
+    let reapplied = rx.try_iter().any(|event| {
+      event
+        == RecoveryEvent::RegistrationsReapplied {
+          restored: vec![hotkey.clone()],
+          failed: vec![],
+        }
+    });
+    assert!(reapplied);
+  }
+
+  #[test]
+  fn global_observer() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+O").unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+    manager
+      .register(hotkey.clone(), move || {
+        let _ = tx.send("callback".to_string());
+      })
+      .unwrap();
+    // the observer must be `Sync`
+    let (tx, observed) = std::sync::mpsc::channel();
+    let tx = Mutex::new(tx);
+    let observed_hotkey = hotkey.clone();
+    set_global_observer(move |pressed| {
+      if *pressed == observed_hotkey {
+        let _ = tx.lock().unwrap().send(pressed.to_string());
+      }
+    });
+
+    registry.dispatch(&hotkey);
+    assert_eq!(
+      observed.try_iter().collect::<Vec<_>>(),
+      vec![hotkey.to_string()]
+    );
+    assert_eq!(rx.try_iter().count(), 1);
+
+    pause_dispatch();
+    registry.dispatch(&hotkey);
+    resume_dispatch();
+    assert_eq!(observed.try_iter().count(), 0);
+  }
+
+  #[test]
+  fn suspend_resume() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let kept = parse_hotkey("CTRL+SHIFT+ALT+K").unwrap();
+    let taken = parse_hotkey("CTRL+SHIFT+ALT+T").unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    for hotkey in &[&kept, &taken] {
+      let counter = counter.clone();
+      manager
+        .register((*hotkey).clone(), move || {
+          counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+
+    registry.suspend().unwrap();
+    assert!(!registry.is_active());
+    assert!(registry
+      .listener()
+      .lock()
+      .unwrap()
+      .registered_hotkeys()
+      .is_empty());
+    assert_eq!(manager.registered_hotkeys(), &[kept.clone(), taken.clone()]);
+
+    // another application grabs one of the hotkeys while suspended
+    registry
+      .listener()
+      .lock()
+      .unwrap()
+      .register_hotkey(taken.listener_hotkey(), || {})
+      .unwrap();
+    assert_eq!(registry.resume(), vec![taken.clone()]);
+    assert!(registry.is_active());
+    registry.dispatch(&kept);
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+    assert!(registry.resume().is_empty());
+  }
+
+  #[test]
+  fn hotkey_release() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let mut push_to_talk = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("CTRL+ALT+SPACE").unwrap();
+    let reports_release = || {
+      registry
+        .listener()
+        .lock()
+        .unwrap()
+        .reports_release(hotkey.listener_hotkey())
+    };
+    let presses = Arc::new(AtomicUsize::new(0));
+    let presses_ = presses.clone();
+    manager
+      .register(hotkey.clone(), move || {
+        presses_.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+    assert!(!reports_release());
+
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let states_ = states.clone();
+    push_to_talk
+      .register_with_state(hotkey.clone(), move |state| {
+        states_.lock().unwrap().push(state)
+      })
+      .unwrap();
+    assert!(reports_release());
+
+    registry.dispatch(&hotkey);
+    registry.dispatch_release(&hotkey);
+    assert_eq!(
+      *states.lock().unwrap(),
+      vec![KeyState::Pressed, KeyState::Released]
+    );
+    assert_eq!(presses.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn hook_hotkey() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let mut overlay = HotkeyManager::with_registry(registry.clone());
+    let suppressed = parse_hotkey("CTRL+ALT+H").unwrap();
+    let passed = parse_hotkey("CTRL+ALT+J").unwrap();
+    let hook_suppresses = |hotkey: &Hotkey| {
+      registry
+        .listener()
+        .lock()
+        .unwrap()
+        .hook_suppresses(hotkey.listener_hotkey())
+    };
+    let presses = Arc::new(AtomicUsize::new(0));
+    let presses_ = presses.clone();
+    manager
+      .register_with_hook(suppressed.clone(), true, move || {
+        presses_.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+    manager
+      .register_with_hook(passed.clone(), false, || {})
+      .unwrap();
+    assert_eq!(hook_suppresses(&suppressed), Some(true));
+    assert_eq!(hook_suppresses(&passed), Some(false));
+
+    // the other handlers of a hooked hotkey must hook it the same way
+    assert!(matches!(
+      overlay.register(suppressed.clone(), || {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    assert!(matches!(
+      overlay.register_with_hook(suppressed.clone(), false, || {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    let presses_ = presses.clone();
+    overlay
+      .register_with_hook(suppressed.clone(), true, move || {
+        presses_.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+    registry.dispatch(&suppressed);
+    assert_eq!(presses.load(Ordering::SeqCst), 2);
+
+    manager.unregister(&suppressed).unwrap();
+    assert_eq!(hook_suppresses(&suppressed), Some(true));
+    overlay.unregister(&suppressed).unwrap();
+    assert_eq!(hook_suppresses(&suppressed), None);
+  }
+
+  #[test]
+  fn learn_mode() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let learned = Arc::new(Mutex::new(Vec::new()));
+    let learned_ = learned.clone();
+    let learning = registry
+      .learn_mode(move |hotkey| learned_.lock().unwrap().push(hotkey))
+      .unwrap();
+    assert!(matches!(
+      registry.learn_mode(|_| {}),
+      Err(Error::LearnModeActive)
+    ));
+    let pressed = parse_hotkey("SHIFT+CTRL+P").unwrap();
+    assert!(registry
+      .listener()
+      .lock()
+      .unwrap()
+      .learn(pressed.listener_hotkey()));
+    // reported in the canonical order
+    assert_eq!(learned.lock().unwrap().len(), 1);
+    assert_eq!(learned.lock().unwrap()[0].to_string(), "CTRL+SHIFT+P");
+
+    drop(learning);
+    assert!(!registry
+      .listener()
+      .lock()
+      .unwrap()
+      .learn(pressed.listener_hotkey()));
+    assert!(registry.learn_mode(|_| {}).is_ok());
+  }
+
+  #[test]
+  fn hotkey_release_unsupported() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let mut push_to_talk = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("CTRL+ALT+SHIFT+SPACE").unwrap();
+    manager.register(hotkey.clone(), || {}).unwrap();
+    registry.listener().lock().unwrap().refuse_release();
+
+    assert!(push_to_talk
+      .register_with_state(hotkey.clone(), |_| {})
+      .is_err());
+    // the hotkey is grabbed again for the handler that was already registered
+    let listener = registry.listener().lock().unwrap();
+    assert!(listener
+      .registered_hotkeys()
+      .contains(&hotkey.listener_hotkey()));
+    assert!(!listener.reports_release(hotkey.listener_hotkey()));
+    drop(listener);
+    assert!(manager.is_registered(&hotkey));
+    assert!(!push_to_talk.is_registered(&hotkey));
+  }
+
+  #[test]
+  fn momentary_hotkey() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let talk = parse_hotkey("CTRL+ALT+T").unwrap();
+    let mute = parse_hotkey("CTRL+ALT+M").unwrap();
+    let (tx, rx) = channel();
+    for (hotkey, timeout) in [(&talk, None), (&mute, Some(Duration::from_millis(50)))] {
+      let (pressed, released) = (tx.clone(), tx.clone());
+      let name = hotkey.to_string();
+      let name_ = name.clone();
+      manager
+        .register_momentary(
+          hotkey.clone(),
+          move || pressed.send((name.clone(), KeyState::Pressed)).unwrap(),
+          move || released.send((name_.clone(), KeyState::Released)).unwrap(),
+          timeout,
+        )
+        .unwrap();
+    }
+
+    // presses while held and releases without a press are ignored
+    registry.dispatch_release(&talk);
+    registry.dispatch(&talk);
+    registry.dispatch(&talk);
+    registry.dispatch_release(&talk);
+    registry.dispatch_release(&talk);
+    assert_eq!(
+      rx.try_iter().collect::<Vec<_>>(),
+      vec![
+        (talk.to_string(), KeyState::Pressed),
+        (talk.to_string(), KeyState::Released)
+      ]
+    );
+
+    // a missed release is synthesized after the timeout, once
+    registry.dispatch(&mute);
+    assert_eq!(
+      rx.try_recv().unwrap(),
+      (mute.to_string(), KeyState::Pressed)
+    );
+    assert_eq!(
+      rx.recv_timeout(Duration::from_secs(5)).unwrap(),
+      (mute.to_string(), KeyState::Released)
+    );
+    registry.dispatch_release(&mute);
+    // a release in time cancels the timeout
+    registry.dispatch(&mute);
+    registry.dispatch_release(&mute);
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(
+      rx.try_iter().collect::<Vec<_>>(),
+      vec![
+        (mute.to_string(), KeyState::Pressed),
+        (mute.to_string(), KeyState::Released)
+      ]
+    );
+  }
+
+  #[test]
+  fn enabled_where() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let hotkeys = ["J", "F2", "CTRL+J", "CTRL+SHIFT+F2"]
+      .iter()
+      .map(|input| parse_hotkey(input).unwrap())
+      .collect::<Vec<_>>();
+    for hotkey in &hotkeys {
+      let fired = fired.clone();
+      manager
+        .register_with_hotkey(hotkey.clone(), move |hotkey| {
+          fired.lock().unwrap().push(hotkey.clone())
+        })
+        .unwrap();
+    }
+
+    manager.set_enabled_where(|hotkey| hotkey.modifiers.is_empty(), false);
+    for hotkey in &hotkeys {
+      registry.dispatch(hotkey);
+    }
+    assert_eq!(*fired.lock().unwrap(), hotkeys[2..].to_vec());
+
+    fired.lock().unwrap().clear();
+    manager.set_enabled_where(|hotkey| hotkey.modifiers.is_empty(), true);
+    for hotkey in &hotkeys {
+      registry.dispatch(hotkey);
+    }
+    assert_eq!(*fired.lock().unwrap(), hotkeys);
+  }
+
+  #[cfg(feature = "mock")]
+  #[test]
+  fn mock_trigger() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let hotkey = parse_hotkey("CTRL+SHIFT+K").unwrap();
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let states_ = states.clone();
+    manager
+      .register_with_state(hotkey.clone(), move |state| {
+        states_.lock().unwrap().push(state)
+      })
+      .unwrap();
+    assert!(registry.trigger(&hotkey));
+    assert_eq!(
+      *states.lock().unwrap(),
+      vec![KeyState::Pressed, KeyState::Released]
+    );
+
+    manager.unregister(&hotkey).unwrap();
+    assert!(!registry.trigger(&hotkey));
+  }
+
+  #[cfg(feature = "keymap")]
+  #[test]
+  fn keymap_round_trip() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let save = parse_hotkey("CMDORCTRL+S").unwrap();
+    let find = Hotkey {
+      modifiers: vec![Modifier::SHIFT, Modifier::primary()],
+      keys: vec![Key::F],
+    };
+    manager
+      .register_labeled("Save", save.clone(), || {})
+      .unwrap();
+    manager
+      .register_labeled("Find", find.clone(), || {})
+      .unwrap();
+    manager
+      .register(parse_hotkey("F9").unwrap(), || {})
+      .unwrap();
+    let json = manager.export_bindings();
+    assert!(json.contains("\"COMMANDORCONTROL+S\""));
+    assert!(json.contains("\"COMMANDORCONTROL+SHIFT+F\""));
+    assert!(!json.contains("F9"));
+
+    manager.unregister_all().unwrap();
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let fired_ = fired.clone();
+    let dispatch = move |action: &str| fired_.lock().unwrap().push(action.to_string());
+    let report = manager.import_bindings(&json, dispatch.clone()).unwrap();
+    assert_eq!(report.registered, vec!["Save", "Find"]);
+    registry.dispatch(&save);
+    registry.dispatch(&parse_hotkey("CMDORCTRL+SHIFT+F").unwrap());
+    assert_eq!(*fired.lock().unwrap(), vec!["Save", "Find"]);
 
-impl fmt::Display for Key {
-  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{:?}", self)
+    let json = r#"[
+      { "action_label": "Save", "accelerator": "CmdOrCtrl+S" },
+      { "action_label": "Broken", "accelerator": "CTRL+NOPE" }
+    ]"#;
+    let report = manager.import_bindings(json, dispatch).unwrap();
+    assert!(report.registered.is_empty());
+    assert_eq!(report.conflicts, vec!["Save"]);
+    assert_eq!(report.failed[0].0, "Broken");
+    assert!(matches!(
+      manager.import_bindings("{}", |_| {}),
+      Err(Error::InvalidKeymap(_))
+    ));
   }
-}
 
-impl fmt::Display for Hotkey {
-  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    let modifier_string: String = self.modifiers.iter().fold(String::new(), |all, one| {
-      if !all.is_empty() {
-        format!("{}+{}", all, one)
-      } else {
-        one.to_string()
-      }
-    });
-    let hotkey_string = {
-      if !modifier_string.is_empty() {
-        format!(
-          "{}+{}",
-          modifier_string,
-          self
-            .keys
-            .iter()
-            .map(|k| k.to_string())
-            .collect::<Vec<String>>()
-            .join("\"")
-        )
-      } else {
-        self
-          .keys
-          .iter()
-          .map(|k| k.to_string())
-          .collect::<Vec<String>>()
-          .join("\"")
-      }
+  #[test]
+  fn parseable_hotkeys() {
+    for input in &[
+      "F1",
+      "CTRL+SHIFT+P",
+      "CmdOrCtrl+Alt+1",
+      "A+B",
+      "ctrl+shift+!",
+    ] {
+      parse_hotkey(input).unwrap().assert_parseable().unwrap();
+    }
+    let malformed = Hotkey {
+      modifiers: vec![],
+      keys: vec![],
     };
-    write!(f, "{}", hotkey_string)
+    assert!(matches!(
+      malformed.assert_parseable(),
+      Err(Error::InvalidHotkey(_))
+    ));
   }
-}
-
-#[cfg(test)]
-mod tests {
-  use super::*;
 
   #[test]
-  fn hotkey_parse() {
+  fn hotkey_alias() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let mut other = HotkeyManager::with_registry(registry.clone());
+    let f2 = parse_hotkey("F2").unwrap();
+    let f5 = parse_hotkey("F5").unwrap();
+    let presses = Arc::new(Mutex::new(Vec::new()));
+    let presses_ = presses.clone();
+    manager
+      .register(f5.clone(), move || presses_.lock().unwrap().push("reload"))
+      .unwrap();
+    manager.alias(f2.clone(), &f5).unwrap();
+
+    registry.dispatch(&f2);
+    assert_eq!(*presses.lock().unwrap(), vec!["reload"]);
+
+    // follows the handlers of the target at press time
+    manager.unregister(&f5).unwrap();
+    registry.dispatch(&f2);
+    assert_eq!(presses.lock().unwrap().len(), 1);
+    let presses_ = presses.clone();
+    other
+      .register(f5.clone(), move || presses_.lock().unwrap().push("refresh"))
+      .unwrap();
+    registry.dispatch(&f2);
+    assert_eq!(*presses.lock().unwrap(), vec!["reload", "refresh"]);
+
+    // `other` already has a handler for F5
+    assert!(matches!(
+      other.alias(f5, &f2),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+
+    // aliases don't chain, F3 aliasing the F2 alias doesn't reach F5
+    let f3 = parse_hotkey("F3").unwrap();
+    other.alias(f3.clone(), &f2).unwrap();
+    registry.dispatch(&f3);
+    assert_eq!(presses.lock().unwrap().len(), 2);
+    registry.dispatch(&f2);
     assert_eq!(
-      parse_hotkey("CTRL+P").unwrap(),
-      Hotkey {
-        modifiers: vec![Modifier::CTRL],
-        keys: vec![Key::P]
+      *presses.lock().unwrap(),
+      vec!["reload", "refresh", "refresh"]
+    );
+  }
+
+  #[test]
+  fn hotkey_from_str() {
+    let hotkey: Hotkey = "ctrl+shift+p".parse().unwrap();
+    assert_eq!(hotkey, parse_hotkey("CTRL+SHIFT+P").unwrap());
+    assert!(matches!(
+      "CTRL+NOPE".parse::<Hotkey>(),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn register_map() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut map: HashMap<String, Box<dyn FnMut() + Send>> = HashMap::new();
+    for accelerator in &["CTRL+ALT+SHIFT+1", "CTRL+ALT+SHIFT+2"] {
+      let tx = tx.clone();
+      map.insert(
+        accelerator.to_string(),
+        Box::new(move || {
+          let _ = tx.send(*accelerator);
+        }),
+      );
+    }
+    manager.register_map(map).unwrap();
+    registry.dispatch(&parse_hotkey("CTRL+ALT+SHIFT+2").unwrap());
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["CTRL+ALT+SHIFT+2"]);
+
+    let mut map: HashMap<String, Box<dyn FnMut() + Send>> = HashMap::new();
+    map.insert("CTRL+ALT+SHIFT+3".to_string(), Box::new(|| {}));
+    map.insert("CTRL+ALT+SHIFT+NOPE".to_string(), Box::new(|| {}));
+    match manager.register_map(map) {
+      Err(Error::MapEntry {
+        accelerator,
+        source,
+      }) => {
+        assert_eq!(accelerator, "CTRL+ALT+SHIFT+NOPE");
+        assert!(matches!(*source, Error::InvalidHotkey(_)));
       }
+      _ => panic!("the invalid entry wasn't reported"),
+    }
+
+    // an entry failing to register rolls back the ones registered before it
+    let mut map: HashMap<String, Box<dyn FnMut() + Send>> = HashMap::new();
+    map.insert("CTRL+ALT+SHIFT+0".to_string(), Box::new(|| {}));
+    map.insert("SHIFT+ALT+CTRL+1".to_string(), Box::new(|| {}));
+    let err = manager.register_map(map).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "hotkey SHIFT+ALT+CTRL+1: Hotkey already registered"
     );
+    assert_eq!(manager.registered_hotkeys().len(), 2);
+    assert!(!manager.is_registered(&parse_hotkey("CTRL+ALT+SHIFT+0").unwrap()));
+  }
+
+  #[test]
+  fn hotkey_try_from() {
+    let hotkey = parse_hotkey("CTRL+SHIFT+P").unwrap();
+    assert_eq!(Hotkey::try_from("shift+ctrl+p").unwrap(), hotkey);
     assert_eq!(
-      parse_hotkey("CTRL+SHIFT+P").unwrap(),
-      Hotkey {
-        modifiers: vec![Modifier::CTRL, Modifier::SHIFT],
-        keys: vec![Key::P]
+      Hotkey::try_from(String::from("CTRL+SHIFT+P")).unwrap(),
+      hotkey
+    );
+    let err: Error = Hotkey::try_from("CTRL+NOPE").unwrap_err();
+    assert!(matches!(err, Error::InvalidHotkey(_)));
+    assert!(matches!(
+      Hotkey::try_from(String::new()),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn registration_policy() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    registry.set_registration_policy(|hotkey| {
+      if hotkey.modifiers.is_empty() {
+        Err(format!("{} has no modifier", hotkey))
+      } else {
+        Ok(())
       }
+    });
+    let mut manager = HotkeyManager::with_registry(registry);
+    let bare = parse_hotkey("F7").unwrap();
+    match manager.register(bare.clone(), || {}) {
+      Err(Error::PolicyRejected(reason)) => assert_eq!(reason, "F7 has no modifier"),
+      other => panic!("unexpected result {:?}", other),
+    }
+    assert!(!manager.is_registered(&bare));
+    manager
+      .register(parse_hotkey("CTRL+F7").unwrap(), || {})
+      .unwrap();
+  }
+
+  #[test]
+  fn manager_registered_hotkeys() {
+    let mut manager = HotkeyManager::with_registry(Arc::new(HotkeyRegistry::new()));
+    let first = parse_hotkey("CTRL+SHIFT+ALT+R").unwrap();
+    let second = parse_hotkey("CTRL+SHIFT+ALT+T").unwrap();
+    assert!(manager.registered_hotkeys().is_empty());
+    manager.register(first.clone(), || {}).unwrap();
+    manager.register(second.clone(), || {}).unwrap();
+    assert_eq!(
+      manager.registered_hotkeys(),
+      &[first.clone(), second.clone()]
     );
+    manager.unregister(&first).unwrap();
+    assert_eq!(manager.registered_hotkeys(), &[second]);
+    manager.register(first, || {}).unwrap();
+    manager.unregister_all().unwrap();
+    assert!(manager.registered_hotkeys().is_empty());
+  }
+
+  #[test]
+  fn alternative_suggestions() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let desired = parse_hotkey("CTRL+ALT+F3").unwrap();
+    let shifted = parse_hotkey("CTRL+ALT+SHIFT+F3").unwrap();
+    manager.register(desired.clone(), || {}).unwrap();
+    manager.register(shifted.clone(), || {}).unwrap();
+
+    let suggestions = registry.suggest_alternatives(&desired, 8);
+    assert_eq!(suggestions.len(), 8);
+    assert_eq!(suggestions[0], parse_hotkey("CTRL+ALT+SUPER+F3").unwrap());
+    for suggestion in &suggestions {
+      assert!(
+        !manager.is_registered(suggestion),
+        "{} is taken",
+        suggestion
+      );
+      assert!(!is_reserved(suggestion), "{} is reserved", suggestion);
+      assert!(!suggestion.resolves_to_same_physical(&desired));
+    }
+    let unique = suggestions.iter().collect::<std::collections::HashSet<_>>();
+    assert_eq!(unique.len(), suggestions.len());
+
+    // moving the key skips reserved hotkeys
+    let suggestions = registry.suggest_alternatives(&parse_hotkey("ALT+F3").unwrap(), 20);
+    assert!(suggestions.contains(&parse_hotkey("ALT+F5").unwrap()));
+    assert!(suggestions
+      .iter()
+      .all(|suggestion| !is_reserved(suggestion)));
+  }
+
+  #[test]
+  fn max_handlers_per_hotkey() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    registry
+      .configure(ListenerConfig {
+        max_handlers_per_hotkey: Some(2),
+        ..Default::default()
+      })
+      .unwrap();
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+M").unwrap();
+    let mut managers = (0..3)
+      .map(|_| HotkeyManager::with_registry(registry.clone()))
+      .collect::<Vec<_>>();
+    managers[0].register(hotkey.clone(), || {}).unwrap();
+    managers[1].register(hotkey.clone(), || {}).unwrap();
+    let err = managers[2].register(hotkey.clone(), || {}).unwrap_err();
     assert_eq!(
-      parse_hotkey("S").unwrap(),
-      Hotkey {
-        modifiers: vec![],
-        keys: vec![Key::S]
-      }
+      err.to_string(),
+      "Hotkey CTRL+SHIFT+ALT+M has too many handlers"
     );
+    assert!(matches!(err, Error::TooManyHandlers(h) if h == hotkey));
+    assert!(!managers[2].is_registered(&hotkey));
+
+    managers[0].unregister(&hotkey).unwrap();
+    managers[2].register(hotkey, || {}).unwrap();
+  }
+
+  #[test]
+  fn held_hotkeys() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let held = parse_hotkey("CTRL+SHIFT+ALT+K").unwrap();
+    let released = parse_hotkey("CTRL+SHIFT+ALT+L").unwrap();
+    manager.register(held.clone(), || {}).unwrap();
+    manager.register(released.clone(), || {}).unwrap();
+
+    let query = |hotkey: ListenerHotkey| hotkey == held.listener_hotkey();
+    assert_eq!(registry.held_hotkeys_with(query), vec![held.clone()]);
+    assert!(registry.held_hotkeys_with(|_| false).is_empty());
     assert_eq!(
-      parse_hotkey("ALT+BACKSPACE").unwrap(),
-      Hotkey {
-        modifiers: vec![Modifier::ALT],
-        keys: vec![Key::BACKSPACE]
-      }
+      registry.held_hotkeys_with(|_| true),
+      vec![held.clone(), released.clone()]
     );
+
+    manager.unregister(&held).unwrap();
+    assert!(registry.held_hotkeys_with(query).is_empty());
+  }
+
+  #[test]
+  fn window_hotkeys() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry);
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+H").unwrap();
+    assert!(matches!(
+      manager.register_for_window(hotkey.clone(), 1, || {}),
+      Err(Error::System(HotkeyError::Unsupported))
+    ));
+    assert!(!manager.is_registered(&hotkey));
+  }
+
+  #[test]
+  fn double_press() {
+    let _lock = dispatch_lock();
+    static NOW: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+    let advance = |millis| *NOW.lock().unwrap() += Duration::from_millis(millis);
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+SPACE").unwrap();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    manager
+      .register_double_with_clock(
+        hotkey.clone(),
+        Duration::from_millis(300),
+        || *NOW.lock().unwrap(),
+        move || {
+          counter.fetch_add(1, Ordering::SeqCst);
+        },
+      )
+      .unwrap();
+    let fired = || fired.load(Ordering::SeqCst);
+
+    registry.dispatch(&hotkey);
+    assert_eq!(fired(), 0);
+    advance(200);
+    registry.dispatch(&hotkey);
+    assert_eq!(fired(), 1);
+
+    // a third press starts over
+    advance(100);
+    registry.dispatch(&hotkey);
+    assert_eq!(fired(), 1);
+
+    // the second press must come within the window
+    advance(400);
+    registry.dispatch(&hotkey);
+    assert_eq!(fired(), 1);
+    advance(300);
+    registry.dispatch(&hotkey);
+    assert_eq!(fired(), 2);
+  }
+
+  #[test]
+  fn hotkey_sequence() {
+    let _lock = dispatch_lock();
+    static NOW: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+    let advance = |millis| *NOW.lock().unwrap() += Duration::from_millis(millis);
+    let k = parse_hotkey("CTRL+SHIFT+ALT+K").unwrap();
+    let s = parse_hotkey("CTRL+SHIFT+ALT+S").unwrap();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    manager
+      .register_sequence_with_clock(
+        Sequence::new(vec![k.clone(), k.clone(), s.clone()]),
+        Duration::from_millis(300),
+        || *NOW.lock().unwrap(),
+        move || {
+          counter.fetch_add(1, Ordering::SeqCst);
+        },
+      )
+      .unwrap();
+    let fired = || fired.load(Ordering::SeqCst);
+    let grabbed = |hotkey: &Hotkey| {
+      registry
+        .listener()
+        .lock()
+        .unwrap()
+        .registered_hotkeys()
+        .contains(&hotkey.listener_hotkey())
+    };
+    // the following hotkeys are grabbed and released by a thread of the sequence
+    let wait_until = |condition: &dyn Fn() -> bool| {
+      let start = Instant::now();
+      while !condition() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(1));
+      }
+    };
+    assert!(grabbed(&k));
+    assert!(!grabbed(&s));
+
+    for hotkey in &[&k, &k] {
+      advance(200);
+      registry.dispatch(hotkey);
+    }
+    wait_until(&|| grabbed(&s));
+    advance(200);
+    registry.dispatch(&s);
+    assert_eq!(fired(), 1);
+    wait_until(&|| !grabbed(&s));
+
+    // an extra press falls back to the longest prefix that still matches
+    for hotkey in &[&k, &k, &k] {
+      advance(100);
+      registry.dispatch(hotkey);
+    }
+    wait_until(&|| grabbed(&s));
+    registry.dispatch(&s);
+    assert_eq!(fired(), 2);
+    wait_until(&|| !grabbed(&s));
+
+    // each press must follow the previous one within the timeout
+    registry.dispatch(&k);
+    registry.dispatch(&k);
+    wait_until(&|| grabbed(&s));
+    advance(400);
+    registry.dispatch(&s);
+    assert_eq!(fired(), 2);
+    wait_until(&|| !grabbed(&s));
+
+    // released once no press follows within the timeout
+    registry.dispatch(&k);
+    registry.dispatch(&k);
+    wait_until(&|| grabbed(&s));
+    wait_until(&|| !grabbed(&s));
+
+    // a binding of a following hotkey fires alongside the sequence while it's grabbed
+    let standalone = Arc::new(AtomicUsize::new(0));
+    let standalone_ = standalone.clone();
+    manager
+      .register(s.clone(), move || {
+        standalone_.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+    registry.dispatch(&s);
+    registry.dispatch(&k);
+    registry.dispatch(&k);
+    wait_until(&|| registry.hotkeys.lock().unwrap()[&s].len() == 2);
+    registry.dispatch(&s);
+    assert_eq!(fired(), 3);
+    assert_eq!(standalone.load(Ordering::SeqCst), 2);
+    wait_until(&|| registry.hotkeys.lock().unwrap()[&s].len() == 1);
+
+    // a binding of the first hotkey collides with the sequence
+    assert!(matches!(
+      manager.register(k.clone(), || {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    assert!(matches!(
+      manager.register_sequence(Sequence::new(vec![]), Duration::from_secs(1), || {}),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn combo_window() {
+    let _lock = dispatch_lock();
+    static NOW: Lazy<Mutex<Instant>> = Lazy::new(|| Mutex::new(Instant::now()));
+    let advance = |millis| *NOW.lock().unwrap() += Duration::from_millis(millis);
+    let a = parse_hotkey("CTRL+SHIFT+ALT+I").unwrap();
+    let b = parse_hotkey("CTRL+SHIFT+ALT+J").unwrap();
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_combo_window_with_clock(
+        vec![a.clone(), b.clone(), a.clone()],
+        Duration::from_millis(500),
+        || *NOW.lock().unwrap(),
+        move || {
+          counter.fetch_add(1, Ordering::SeqCst);
+        },
+      )
+      .unwrap();
+    let fired = || fired.load(Ordering::SeqCst);
+
+    dispatch(&a);
+    advance(200);
+    dispatch(&b);
+    assert_eq!(fired(), 1);
+
+    // any order
+    advance(1000);
+    dispatch(&b);
+    advance(300);
+    dispatch(&a);
+    assert_eq!(fired(), 2);
+
+    // presses outside the window don't count, repeating one refreshes it
+    advance(1000);
+    dispatch(&a);
+    advance(600);
+    dispatch(&b);
+    assert_eq!(fired(), 2);
+    advance(100);
+    dispatch(&a);
+    assert_eq!(fired(), 3);
+
+    // every press is forgotten after firing
+    advance(100);
+    dispatch(&b);
+    assert_eq!(fired(), 3);
+
+    assert!(matches!(
+      manager.register_combo_window(Vec::new(), Duration::from_millis(500), || {}),
+      Err(Error::InvalidHotkey(_))
+    ));
+  }
+
+  #[test]
+  fn config_diff() {
+    let first = parse_hotkey("CTRL+ALT+H").unwrap();
+    let second = parse_hotkey("CTRL+ALT+I").unwrap();
+    let third = parse_hotkey("CTRL+ALT+J").unwrap();
+    let mut manager = HotkeyManager::new();
+    manager.register(first.clone(), || {}).unwrap();
+    manager.register(second.clone(), || {}).unwrap();
+
+    let snapshot = manager.snapshot_config();
+    assert_eq!(snapshot, vec![first.clone(), second.clone()]);
+    assert_eq!(manager.diff_config(&snapshot), ConfigDiff::default());
+
+    manager.unregister(&first).unwrap();
+    manager.register(third.clone(), || {}).unwrap();
     assert_eq!(
-      parse_hotkey("SHIFT+SUPER+A").unwrap(),
-      Hotkey {
-        modifiers: vec![Modifier::SHIFT, Modifier::SUPER],
-        keys: vec![Key::A]
+      manager.diff_config(&snapshot),
+      ConfigDiff {
+        added: vec![first],
+        removed: vec![third],
       }
     );
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn scheduled_hotkey() {
+    let _lock = dispatch_lock();
+    use chrono::NaiveTime;
+
+    fn time(hour: u32, min: u32) -> NaiveTime {
+      NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    static NOW: Lazy<Mutex<NaiveTime>> = Lazy::new(|| Mutex::new(time(12, 0)));
+    let hotkey = parse_hotkey("CTRL+ALT+K").unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+    let count_ = count.clone();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_scheduled_with_clock(
+        hotkey.clone(),
+        Schedule::new().window(time(22, 0), time(6, 0)),
+        || *NOW.lock().unwrap(),
+        move || {
+          count_.fetch_add(1, Ordering::Relaxed);
+        },
+      )
+      .unwrap();
+
+    dispatch(&hotkey);
+    assert_eq!(count.load(Ordering::Relaxed), 0);
+    *NOW.lock().unwrap() = time(23, 30);
+    dispatch(&hotkey);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+    *NOW.lock().unwrap() = time(6, 0);
+    dispatch(&hotkey);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+  }
+
+  #[test]
+  fn batch_unregister_all() {
+    let hotkeys: Vec<Hotkey> = (0..10)
+      .map(|i| parse_hotkey(&format!("CTRL+SHIFT+ALT+{}", i)).unwrap())
+      .collect();
+    let mut manager = HotkeyManager::new();
+    let mut other_manager = HotkeyManager::new();
+    for hotkey in &hotkeys {
+      manager.register(hotkey.clone(), || {}).unwrap();
+    }
+    other_manager.register(hotkeys[0].clone(), || {}).unwrap();
+
+    manager.unregister_all().unwrap();
+    assert!(manager.snapshot_config().is_empty());
+    let map = GLOBAL_REGISTRY.hotkeys.lock().unwrap();
+    let held = GLOBAL_REGISTRY
+      .listener()
+      .lock()
+      .unwrap()
+      .registered_hotkeys();
+    assert_eq!(map[&hotkeys[0]].len(), 1);
+    assert!(held.contains(&hotkeys[0].listener_hotkey()));
+    for hotkey in &hotkeys[1..] {
+      assert!(!map.contains_key(hotkey));
+      assert!(!held.contains(&hotkey.listener_hotkey()));
+    }
+  }
+
+  #[test]
+  fn isolated_registries() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+U").unwrap();
+    let registries = [
+      Arc::new(HotkeyRegistry::new()),
+      Arc::new(HotkeyRegistry::new()),
+    ];
+    let fired = Arc::new(Mutex::new(Vec::new()));
+    let mut managers = Vec::new();
+    for (index, registry) in registries.iter().enumerate() {
+      let mut manager = HotkeyManager::with_registry(registry.clone());
+      let fired = fired.clone();
+      manager
+        .register(hotkey.clone(), move || fired.lock().unwrap().push(index))
+        .unwrap();
+      managers.push(manager);
+    }
+
+    registries[1].dispatch(&hotkey);
+    registries[0].dispatch(&hotkey);
+    assert_eq!(*fired.lock().unwrap(), vec![1, 0]);
+    assert!(!GLOBAL_REGISTRY
+      .hotkeys
+      .lock()
+      .unwrap()
+      .contains_key(&hotkey));
+
+    managers[0].unregister(&hotkey).unwrap();
+    assert!(!registries[0].hotkeys.lock().unwrap().contains_key(&hotkey));
+    assert!(registries[1].hotkeys.lock().unwrap().contains_key(&hotkey));
+    assert!(registries[0]
+      .listener()
+      .lock()
+      .unwrap()
+      .registered_hotkeys()
+      .is_empty());
     assert_eq!(
-      parse_hotkey("SUPER+RIGHT").unwrap(),
-      Hotkey {
-        modifiers: vec![Modifier::SUPER],
-        keys: vec![Key::RIGHT]
-      }
+      registries[1]
+        .listener()
+        .lock()
+        .unwrap()
+        .registered_hotkeys(),
+      vec![hotkey.listener_hotkey()]
     );
+  }
+
+  #[test]
+  fn usage_order() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let hotkeys: Vec<Hotkey> = ["CTRL+ALT+V", "CTRL+ALT+W", "CTRL+ALT+X", "CTRL+ALT+Y"]
+      .iter()
+      .map(|hotkey| parse_hotkey(hotkey).unwrap())
+      .collect();
+    for hotkey in &hotkeys {
+      manager.register(hotkey.clone(), || {}).unwrap();
+    }
+    let [v, w, x, y] = [&hotkeys[0], &hotkeys[1], &hotkeys[2], &hotkeys[3]];
+    for hotkey in &[v, v, w, x, x, y, v, w] {
+      registry.dispatch(hotkey);
+    }
+
+    let recent = registry.most_recently_used(3);
     assert_eq!(
-      parse_hotkey("SUPER+CTRL+SHIFT+AltGr+9").unwrap(),
-      Hotkey {
-        modifiers: vec![
-          Modifier::SUPER,
-          Modifier::CTRL,
-          Modifier::SHIFT,
-          Modifier::ALTGR
-        ],
-        keys: vec![Key::KEY_9]
-      }
+      recent.iter().map(|(hotkey, _)| hotkey).collect::<Vec<_>>(),
+      vec![w, v, y]
     );
+    assert!(recent[0].1 >= recent[1].1);
+    // w and x both fired twice, w more recently
     assert_eq!(
-      parse_hotkey("super+ctrl+SHIFT+alt+Up").unwrap(),
-      Hotkey {
-        modifiers: vec![
-          Modifier::SUPER,
-          Modifier::CTRL,
-          Modifier::SHIFT,
-          Modifier::ALT
-        ],
-        keys: vec![Key::UP]
-      }
+      registry.most_frequently_used(10),
+      vec![
+        (v.clone(), 3),
+        (w.clone(), 2),
+        (x.clone(), 2),
+        (y.clone(), 1)
+      ]
     );
 
+    manager.unregister(w).unwrap();
     assert_eq!(
-      parse_hotkey("5").unwrap(),
-      Hotkey {
-        modifiers: vec![],
-        keys: vec![Key::KEY_5]
-      }
+      registry.most_frequently_used(2),
+      vec![(v.clone(), 3), (x.clone(), 2)]
     );
+  }
+
+  #[test]
+  fn standard_shortcuts() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let (tx, rx) = std::sync::mpsc::channel();
+    let quit_tx = tx.clone();
+    let custom_find = parse_hotkey("CTRL+SHIFT+F").unwrap();
+    let shortcuts = StandardShortcuts::new()
+      .on(StandardShortcut::Quit, move || {
+        let _ = quit_tx.send("quit");
+      })
+      .on(StandardShortcut::Save, || {})
+      .on(StandardShortcut::Find, || {})
+      .on_hotkey(StandardShortcut::Find, custom_find.clone(), move || {
+        let _ = tx.send("find");
+      });
 
+    #[cfg(target_os = "macos")]
+    let (quit, save) = (
+      parse_hotkey("SUPER+Q").unwrap(),
+      parse_hotkey("SUPER+S").unwrap(),
+    );
+    #[cfg(not(target_os = "macos"))]
+    let (quit, save) = (
+      parse_hotkey("CTRL+Q").unwrap(),
+      parse_hotkey("CTRL+S").unwrap(),
+    );
     assert_eq!(
-      parse_hotkey("KEY_5").unwrap(),
-      Hotkey {
-        modifiers: vec![],
-        keys: vec![Key::KEY_5]
-      }
+      shortcuts.hotkeys(),
+      vec![
+        (StandardShortcut::Quit, quit.clone()),
+        (StandardShortcut::Save, save.clone()),
+        (StandardShortcut::Find, custom_find.clone()),
+      ]
     );
 
+    shortcuts.register(&mut manager).unwrap();
     assert_eq!(
-      parse_hotkey("5+5").unwrap_err().to_string(),
-      "failed to parse hotkey: duplicated key 5"
+      manager.snapshot_config(),
+      vec![quit.clone(), save, custom_find.clone()]
     );
+    registry.dispatch(&quit);
+    registry.dispatch(&custom_find);
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["quit", "find"]);
+  }
+
+  #[test]
+  fn registry_run_state() {
+    let _lock = dispatch_lock();
+    let registry = Arc::new(HotkeyRegistry::new());
+    let held = || registry.listener().lock().unwrap().registered_hotkeys();
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let first = parse_hotkey("CTRL+SHIFT+ALT+A").unwrap();
+    let second = parse_hotkey("CTRL+SHIFT+ALT+B").unwrap();
+    let flag = manager.register_polled(first.clone()).unwrap();
+    manager.register(second.clone(), || {}).unwrap();
+    assert!(registry.is_active());
+    assert_eq!(held().len(), 2);
+
+    registry.set_active(false).unwrap();
+    assert!(!registry.is_active());
+    assert!(held().is_empty());
+    assert!(manager.is_registered(&first));
+    assert!(registry.check_registrations().is_empty());
+
+    // registered while inactive, only grabbed once reactivated
+    let third = parse_hotkey("CTRL+SHIFT+ALT+C").unwrap();
+    manager.register(third.clone(), || {}).unwrap();
+    manager.unregister(&second).unwrap();
+    assert!(held().is_empty());
+
+    registry.set_active(true).unwrap();
+    let mut grabbed = held();
+    grabbed.sort_by_key(|hotkey| hotkey.key);
+    let mut expected = vec![first.listener_hotkey(), third.listener_hotkey()];
+    expected.sort_by_key(|hotkey| hotkey.key);
+    assert_eq!(grabbed, expected);
+    registry.dispatch(&first);
+    assert!(flag.take_pressed());
+  }
 
+  #[test]
+  fn effective_accelerator() {
+    let registry = Arc::new(HotkeyRegistry::new());
+    let mut manager = HotkeyManager::with_registry(registry.clone());
+    let requested = parse_hotkey("CmdOrCtrl+ALT+Z").unwrap();
+    let assigned = parse_hotkey("CTRL+SHIFT+Z").unwrap();
+    assert_eq!(manager.effective_accelerator(&requested), None);
+
+    manager.register(requested.clone(), || {}).unwrap();
     assert_eq!(
-      parse_hotkey("CTRL+").unwrap_err().to_string(),
-      "failed to parse hotkey: hotkey has no key specified"
+      manager.effective_accelerator(&requested),
+      Some(requested.clone())
     );
 
+    // a portal assigning another shortcut than the requested one
+    registry
+      .listener()
+      .lock()
+      .unwrap()
+      .remap(requested.listener_hotkey(), assigned.listener_hotkey());
+    assert_eq!(manager.effective_accelerator(&requested), Some(assigned));
+
+    registry.set_active(false).unwrap();
+    assert_eq!(manager.effective_accelerator(&requested), None);
+  }
+
+  #[test]
+  fn action_registry() {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum Action {
+      Open,
+      Save,
+    }
+
+    let _lock = dispatch_lock();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut actions = ActionRegistry::new(move |action| {
+      let _ = tx.send(action);
+    });
+    let open = parse_hotkey("CTRL+SHIFT+ALT+D").unwrap();
+    let save = parse_hotkey("CTRL+SHIFT+ALT+E").unwrap();
+    let rebound_save = parse_hotkey("CTRL+SHIFT+ALT+F").unwrap();
+    actions.bind(Action::Open, open.clone()).unwrap();
+    actions.bind(Action::Save, save.clone()).unwrap();
+
+    dispatch(&save);
+    dispatch(&open);
     assert_eq!(
-      parse_hotkey("").unwrap_err().to_string(),
-      "failed to parse hotkey: hotkey has no key specified"
+      rx.try_iter().collect::<Vec<_>>(),
+      vec![Action::Save, Action::Open]
     );
+
+    actions.bind(Action::Save, rebound_save.clone()).unwrap();
+    assert_eq!(actions.hotkey(Action::Save), Some(&rebound_save));
+    dispatch(&save);
+    dispatch(&rebound_save);
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![Action::Save]);
+
+    assert_eq!(actions.unbind(Action::Open).unwrap(), Some(open.clone()));
+    dispatch(&open);
+    assert!(rx.try_recv().is_err());
+  }
+
+  #[test]
+  fn weak_resource() {
+    struct Panel {
+      opened: AtomicUsize,
+    }
+
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+SHIFT+ALT+G").unwrap();
+    let panel = Arc::new(Panel {
+      opened: AtomicUsize::new(0),
+    });
+    let fired = Arc::new(AtomicUsize::new(0));
+    let counter = fired.clone();
+    let mut manager = HotkeyManager::new();
+    manager
+      .register_weak(hotkey.clone(), &panel, move |panel| {
+        panel.opened.fetch_add(1, Ordering::SeqCst);
+        counter.fetch_add(1, Ordering::SeqCst);
+      })
+      .unwrap();
+
+    dispatch(&hotkey);
+    assert_eq!(panel.opened.load(Ordering::SeqCst), 1);
+    assert!(manager.release_dropped().unwrap().is_empty());
+
+    drop(panel);
+    dispatch(&hotkey);
+    assert_eq!(fired.load(Ordering::SeqCst), 1);
+    assert!(manager.is_registered(&hotkey));
+
+    // released on the next registration
+    let other = parse_hotkey("CTRL+SHIFT+ALT+H").unwrap();
+    manager.register(other, || {}).unwrap();
+    assert!(!manager.is_registered(&hotkey));
+    assert!(manager.release_dropped().unwrap().is_empty());
+  }
+
+  #[test]
+  fn listener_config() {
+    let config = ListenerConfig {
+      poll_interval: Duration::from_millis(10),
+      thread_name: Some("hotkeys".into()),
+      no_repeat: true,
+      id_base: 0x1000,
+      ..Default::default()
+    };
+    let listener = build_listener(&config);
+    assert_eq!(listener.options().poll_interval, config.poll_interval);
+    assert_eq!(listener.options().thread_name, config.thread_name);
+    assert!(listener.options().no_repeat);
+    assert_eq!(listener.options().id_base, 0x1000);
+
+    let registry = HotkeyRegistry::new();
+    assert!(matches!(
+      registry.configure(ListenerConfig {
+        id_base: 0xC000,
+        ..Default::default()
+      }),
+      Err(Error::System(HotkeyError::InvalidIdBase(0xC000)))
+    ));
+
+    GLOBAL_REGISTRY.listener();
+    assert!(matches!(configure(config), Err(Error::ListenerInitialized)));
+  }
+
+  #[test]
+  fn global_shortcut_manager() {
+    let mut manager = GlobalShortcutManager::new();
+    assert!(!manager.is_registered("CTRL+ALT+L").unwrap());
+    manager.register("CTRL+ALT+L", || {}).unwrap();
+    assert!(manager.is_registered("ctrl+alt+l").unwrap());
+    assert!(matches!(
+      manager.register("CTRL+ALT+L", || {}),
+      Err(Error::HotkeyAlreadyRegistered(_))
+    ));
+    assert!(matches!(
+      manager.register("CTRL+ALT+NOPE", || {}),
+      Err(Error::InvalidHotkey(_))
+    ));
+    manager.unregister("CTRL+ALT+L").unwrap();
+    assert!(!manager.is_registered("CTRL+ALT+L").unwrap());
+    assert!(matches!(
+      manager.unregister("CTRL+ALT+L"),
+      Err(Error::HotkeyNotRegistered(_))
+    ));
+  }
+
+  #[test]
+  fn contexts() {
+    let _lock = dispatch_lock();
+    let hotkey = parse_hotkey("CTRL+ALT+E").unwrap();
+    let editing = Arc::new(AtomicUsize::new(0));
+    let presentation = Arc::new(AtomicUsize::new(0));
+
+    let mut editing_manager = HotkeyManager::new();
+    let editing_ = editing.clone();
+    editing_manager
+      .register_in_context("editing", hotkey.clone(), move || {
+        editing_.fetch_add(1, Ordering::Relaxed);
+      })
+      .unwrap();
+    let mut presentation_manager = HotkeyManager::new();
+    let presentation_ = presentation.clone();
+    presentation_manager
+      .register_in_context("presentation", hotkey.clone(), move || {
+        presentation_.fetch_add(1, Ordering::Relaxed);
+      })
+      .unwrap();
+
+    set_context_active("presentation", false);
+    dispatch(&hotkey);
+    assert_eq!(editing.load(Ordering::Relaxed), 1);
+    assert_eq!(presentation.load(Ordering::Relaxed), 0);
+
+    set_context_active("editing", false);
+    set_context_active("presentation", true);
+    dispatch(&hotkey);
+    assert_eq!(editing.load(Ordering::Relaxed), 1);
+    assert_eq!(presentation.load(Ordering::Relaxed), 1);
+    set_context_active("editing", true);
   }
 }
@@ -0,0 +1,260 @@
+use crate::{Hotkey, Key, Modifier};
+use once_cell::sync::Lazy;
+use std::{collections::HashMap, sync::Mutex};
+
+static CUSTOM_KEY_NAMES: Lazy<Mutex<HashMap<(String, Key), String>>> = Lazy::new(Default::default);
+
+/// Sets the localized name of a key, overriding the built-in table, e.g. to support
+/// another language. `locale` is matched by its language, so `pt` covers `pt-BR`.
+pub fn set_localized_key_name(locale: &str, key: Key, name: &str) {
+  CUSTOM_KEY_NAMES
+    .lock()
+    .unwrap()
+    .insert((language(locale), key), name.to_string());
+}
+
+/// Returns the language of a locale such as `es-ES` or `pt_BR`.
+fn language(locale: &str) -> String {
+  locale
+    .split(&['-', '_'][..])
+    .next()
+    .unwrap_or_default()
+    .to_lowercase()
+}
+
+fn builtin_key_name(language: &str, key: Key) -> Option<&'static str> {
+  let name = match (language, key) {
+    ("es", Key::ENTER) => "Intro",
+    ("es", Key::ESCAPE) => "Esc",
+    ("es", Key::SPACE) => "Espacio",
+    ("es", Key::BACKSPACE) => "Retroceso",
+    ("es", Key::TAB) => "Tabulador",
+    ("es", Key::DELETE) => "Supr",
+    ("es", Key::HOME) => "Inicio",
+    ("es", Key::END) => "Fin",
+    ("es", Key::PAGEUP) => "RePág",
+    ("es", Key::PAGEDOWN) => "AvPág",
+    ("es", Key::UP) => "Arriba",
+    ("es", Key::DOWN) => "Abajo",
+    ("es", Key::LEFT) => "Izquierda",
+    ("es", Key::RIGHT) => "Derecha",
+    ("es", Key::CAPSLOCK) => "Bloq Mayús",
+    ("de", Key::ENTER) => "Eingabe",
+    ("de", Key::ESCAPE) => "Esc",
+    ("de", Key::SPACE) => "Leertaste",
+    ("de", Key::BACKSPACE) => "Rücktaste",
+    ("de", Key::TAB) => "Tab",
+    ("de", Key::DELETE) => "Entf",
+    ("de", Key::HOME) => "Pos1",
+    ("de", Key::END) => "Ende",
+    ("de", Key::PAGEUP) => "Bild auf",
+    ("de", Key::PAGEDOWN) => "Bild ab",
+    ("de", Key::UP) => "Pfeil oben",
+    ("de", Key::DOWN) => "Pfeil unten",
+    ("de", Key::LEFT) => "Pfeil links",
+    ("de", Key::RIGHT) => "Pfeil rechts",
+    ("de", Key::CAPSLOCK) => "Feststelltaste",
+    ("fr", Key::ENTER) => "Entrée",
+    ("fr", Key::ESCAPE) => "Échap",
+    ("fr", Key::SPACE) => "Espace",
+    ("fr", Key::BACKSPACE) => "Retour arrière",
+    ("fr", Key::TAB) => "Tab",
+    ("fr", Key::DELETE) => "Suppr",
+    ("fr", Key::HOME) => "Origine",
+    ("fr", Key::END) => "Fin",
+    ("fr", Key::PAGEUP) => "Page préc.",
+    ("fr", Key::PAGEDOWN) => "Page suiv.",
+    ("fr", Key::UP) => "Haut",
+    ("fr", Key::DOWN) => "Bas",
+    ("fr", Key::LEFT) => "Gauche",
+    ("fr", Key::RIGHT) => "Droite",
+    ("fr", Key::CAPSLOCK) => "Verr. Maj",
+    _ => return None,
+  };
+  Some(name)
+}
+
+fn builtin_modifier_name(language: &str, modifier: Modifier) -> Option<&'static str> {
+  let name = match (language, modifier) {
+    ("es", Modifier::CTRL) => "Ctrl",
+    ("es", Modifier::SHIFT) => "Mayús",
+    ("es", Modifier::ALT) => "Alt",
+    ("es", Modifier::ALTGR) => "Alt Gr",
+    ("de", Modifier::CTRL) => "Strg",
+    ("de", Modifier::SHIFT) => "Umschalt",
+    ("de", Modifier::ALT) => "Alt",
+    ("de", Modifier::ALTGR) => "Alt Gr",
+    ("fr", Modifier::CTRL) => "Ctrl",
+    ("fr", Modifier::SHIFT) => "Maj",
+    ("fr", Modifier::ALT) => "Alt",
+    ("fr", Modifier::ALTGR) => "Alt Gr",
+    _ => return None,
+  };
+  Some(name)
+}
+
+impl Key {
+  /// Returns the name of the key in the given locale, e.g. `Intro` for `ENTER` in `es`.
+  ///
+  /// Names set with [`set_localized_key_name`] take precedence over the built-in table of
+  /// Spanish, German and French names. Keys without a localized name, and every key of
+  /// other locales, use the English name of [`Key`]'s `Display`. Parsing is not affected.
+  pub fn localized_name(&self, locale: &str) -> String {
+    let language = language(locale);
+    if let Some(name) = CUSTOM_KEY_NAMES
+      .lock()
+      .unwrap()
+      .get(&(language.clone(), *self))
+    {
+      return name.clone();
+    }
+    builtin_key_name(&language, *self)
+      .map(ToString::to_string)
+      .unwrap_or_else(|| self.to_string())
+  }
+}
+
+impl Modifier {
+  /// Returns the name of the modifier in the given locale, e.g. `Strg` for `CTRL` in `de`,
+  /// falling back to the English name of [`Modifier`]'s `Display`.
+  pub fn localized_name(&self, locale: &str) -> String {
+    builtin_modifier_name(&language(locale), *self)
+      .map(ToString::to_string)
+      .unwrap_or_else(|| self.to_string())
+  }
+}
+
+impl Key {
+  /// Returns the label of the key in a settings panel, following the conventions of the
+  /// current platform, e.g. `[` for `OPENBRACKET`, `Arrow Up` for `UP` and `Return` for
+  /// `ENTER` on macOS. Letters, digits and function keys are written as typed.
+  pub fn display_symbol(&self) -> &'static str {
+    match self {
+      #[cfg(target_os = "macos")]
+      Key::BACKSPACE => "Delete",
+      #[cfg(not(target_os = "macos"))]
+      Key::BACKSPACE => "Backspace",
+      Key::TAB => "Tab",
+      #[cfg(target_os = "macos")]
+      Key::ENTER => "Return",
+      #[cfg(not(target_os = "macos"))]
+      Key::ENTER => "Enter",
+      Key::CAPSLOCK => "Caps Lock",
+      Key::ESCAPE => "Esc",
+      Key::SPACE => "Space",
+      Key::PAGEUP => "Page Up",
+      Key::PAGEDOWN => "Page Down",
+      Key::END => "End",
+      Key::HOME => "Home",
+      Key::LEFT => "Arrow Left",
+      Key::RIGHT => "Arrow Right",
+      Key::UP => "Arrow Up",
+      Key::DOWN => "Arrow Down",
+      Key::PRINTSCREEN => "Print Screen",
+      #[cfg(not(target_os = "macos"))]
+      Key::INSERT => "Insert",
+      Key::CLEAR => "Clear",
+      #[cfg(target_os = "macos")]
+      Key::DELETE => "Forward Delete",
+      #[cfg(not(target_os = "macos"))]
+      Key::DELETE => "Delete",
+      #[cfg(not(target_os = "macos"))]
+      Key::SCROLLLOCK => "Scroll Lock",
+      Key::HELP => "Help",
+      Key::NUMLOCK => "Num Lock",
+      Key::VOLUMEMUTE => "Mute",
+      Key::VOLUMEDOWN => "Volume Down",
+      Key::VOLUMEUP => "Volume Up",
+      Key::MEDIANEXTTRACK => "Next Track",
+      Key::MEDIAPREVIOUSTRACK => "Previous Track",
+      Key::MEDIASTOP => "Stop",
+      Key::MEDIAPLAYPAUSE => "Play/Pause",
+      Key::LAUNCHMAIL => "Mail",
+      #[cfg(not(target_os = "macos"))]
+      Key::MENU => "Menu",
+      Key::NUMADD => "Num +",
+      Key::NUMSUB => "Num -",
+      Key::NUMMULT => "Num *",
+      Key::NUMDIV => "Num /",
+      Key::NUMDEC => "Num .",
+      #[cfg(target_os = "macos")]
+      Key::NUMEQUAL => "Num =",
+      Key::NUMPAD0 => "Num 0",
+      Key::NUMPAD1 => "Num 1",
+      Key::NUMPAD2 => "Num 2",
+      Key::NUMPAD3 => "Num 3",
+      Key::NUMPAD4 => "Num 4",
+      Key::NUMPAD5 => "Num 5",
+      Key::NUMPAD6 => "Num 6",
+      Key::NUMPAD7 => "Num 7",
+      Key::NUMPAD8 => "Num 8",
+      Key::NUMPAD9 => "Num 9",
+      Key::EQUAL => "=",
+      Key::MINUS => "-",
+      Key::SINGLEQUOTE => "'",
+      Key::COMMA => ",",
+      Key::PERIOD => ".",
+      Key::SEMICOLON => ";",
+      Key::SLASH => "/",
+      Key::OPENQUOTE => "`",
+      Key::OPENBRACKET => "[",
+      Key::BACKSLASH => "\\",
+      Key::CLOSEBRACKET => "]",
+      // letters, function keys and digits, the latter without their `KEY_` prefix
+      key => {
+        let name: &'static str = key.into();
+        name.strip_prefix("KEY_").unwrap_or(name)
+      }
+    }
+  }
+}
+
+impl Modifier {
+  /// Returns the label of the modifier in a settings panel, following the conventions of
+  /// the current platform: `⌘`, `⌥`, `⌃` and `⇧` on macOS, `Win`, `Alt`, `Ctrl` and
+  /// `Shift` on Windows, and `Super` instead of `Win` on Linux. `COMMAND_OR_CONTROL` is
+  /// resolved first.
+  pub fn display_symbol(&self) -> &'static str {
+    match self.resolve() {
+      #[cfg(target_os = "macos")]
+      Modifier::SUPER => "⌘",
+      #[cfg(target_os = "macos")]
+      Modifier::ALT | Modifier::ALTGR => "⌥",
+      #[cfg(target_os = "macos")]
+      Modifier::CTRL => "⌃",
+      #[cfg(target_os = "macos")]
+      Modifier::SHIFT => "⇧",
+      #[cfg(target_os = "macos")]
+      Modifier::GLOBE => "fn",
+      #[cfg(target_os = "windows")]
+      Modifier::SUPER => "Win",
+      #[cfg(target_os = "linux")]
+      Modifier::SUPER => "Super",
+      #[cfg(not(target_os = "macos"))]
+      Modifier::ALT => "Alt",
+      #[cfg(not(target_os = "macos"))]
+      Modifier::ALTGR => "AltGr",
+      #[cfg(not(target_os = "macos"))]
+      Modifier::CTRL => "Ctrl",
+      #[cfg(not(target_os = "macos"))]
+      Modifier::SHIFT => "Shift",
+      // never returned by `resolve`
+      Modifier::COMMAND_OR_CONTROL => "",
+    }
+  }
+}
+
+impl Hotkey {
+  /// Renders the hotkey with localized key and modifier names, e.g. `Strg+Umschalt+Eingabe`.
+  /// The modifiers are written in the canonical order.
+  pub fn to_localized_string(&self, locale: &str) -> String {
+    let normalized = self.normalize();
+    normalized
+      .modifiers
+      .iter()
+      .map(|modifier| modifier.localized_name(locale))
+      .chain(normalized.keys.iter().map(|key| key.localized_name(locale)))
+      .collect::<Vec<_>>()
+      .join("+")
+  }
+}
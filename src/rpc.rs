@@ -0,0 +1,106 @@
+use crate::{parse_hotkey, Hotkey, HotkeyManager, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// A request driving a [`RpcServer`], e.g. sent by a frontend or sidecar process.
+/// Hotkeys are accelerator strings parsed with [`parse_hotkey`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "camelCase")]
+pub enum RpcRequest {
+  Register {
+    hotkey: String,
+  },
+  Unregister {
+    hotkey: String,
+  },
+  List,
+  /// Reports a hotkey registered by the server to its `on_trigger` callback as if it was
+  /// pressed. Handlers of other managers bound to the same hotkey don't run.
+  Trigger {
+    hotkey: String,
+  },
+}
+
+/// The response to a [`RpcRequest`]. Hotkeys are listed in their canonical string form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "result", content = "value", rename_all = "camelCase")]
+pub enum RpcResponse {
+  Ok,
+  Hotkeys(Vec<String>),
+  Error(String),
+}
+
+// accelerators differing only in the modifier order name the same hotkey
+fn parse(accelerator: &str) -> Result<Hotkey> {
+  Ok(parse_hotkey(accelerator)?.normalize())
+}
+
+type TriggerCallback = Arc<Mutex<Box<dyn 'static + FnMut(&Hotkey) + Send>>>;
+
+/// Drives the hotkeys of a [`HotkeyManager`] through serializable [`RpcRequest`]s, so a
+/// separate control process can register hotkeys over any transport.
+///
+/// Callbacks can't be sent over the wire, so every hotkey registered through the server
+/// reports its presses to the single `on_trigger` callback, which can forward them back.
+pub struct RpcServer {
+  manager: HotkeyManager,
+  on_trigger: TriggerCallback,
+}
+
+impl RpcServer {
+  pub fn new<F>(manager: HotkeyManager, on_trigger: F) -> Self
+  where
+    F: 'static + FnMut(&Hotkey) + Send,
+  {
+    Self {
+      manager,
+      on_trigger: Arc::new(Mutex::new(Box::new(on_trigger))),
+    }
+  }
+
+  pub fn manager(&self) -> &HotkeyManager {
+    &self.manager
+  }
+
+  /// Handles a request, returning [`RpcResponse::Error`] with the error message on failure.
+  pub fn handle_rpc(&mut self, request: RpcRequest) -> RpcResponse {
+    match self.try_handle(request) {
+      Ok(response) => response,
+      Err(err) => RpcResponse::Error(err.to_string()),
+    }
+  }
+
+  fn try_handle(&mut self, request: RpcRequest) -> Result<RpcResponse> {
+    match request {
+      RpcRequest::Register { hotkey } => {
+        let hotkey = parse(&hotkey)?;
+        let on_trigger = self.on_trigger.clone();
+        let hotkey_ = hotkey.clone();
+        self
+          .manager
+          .register(hotkey, move || (on_trigger.lock().unwrap())(&hotkey_))?;
+      }
+      RpcRequest::Unregister { hotkey } => {
+        self.manager.unregister(&parse(&hotkey)?)?;
+      }
+      RpcRequest::List => {
+        return Ok(RpcResponse::Hotkeys(
+          self
+            .manager
+            .registered_hotkeys()
+            .iter()
+            .map(|hotkey| hotkey.to_string())
+            .collect(),
+        ));
+      }
+      RpcRequest::Trigger { hotkey } => {
+        let hotkey = parse(&hotkey)?;
+        if !self.manager.is_registered(&hotkey) {
+          return Err(crate::Error::HotkeyNotRegistered(hotkey));
+        }
+        (self.on_trigger.lock().unwrap())(&hotkey);
+      }
+    }
+    Ok(RpcResponse::Ok)
+  }
+}
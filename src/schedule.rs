@@ -0,0 +1,40 @@
+use chrono::{Local, NaiveTime};
+
+/// Time windows during which a hotkey registered with
+/// [`HotkeyManager::register_scheduled`](crate::HotkeyManager::register_scheduled) fires.
+///
+/// Times are compared against the local time of the machine.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Schedule {
+  windows: Vec<(NaiveTime, NaiveTime)>,
+}
+
+impl Schedule {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Adds an active window starting at `start` (inclusive) and ending at `end` (exclusive).
+  ///
+  /// A window ending before its start wraps around midnight, e.g. 22:00 to 06:00.
+  pub fn window(mut self, start: NaiveTime, end: NaiveTime) -> Self {
+    self.windows.push((start, end));
+    self
+  }
+
+  /// Determines whether the given time falls inside one of the windows.
+  pub fn is_active_at(&self, time: NaiveTime) -> bool {
+    self.windows.iter().any(|(start, end)| {
+      if start <= end {
+        *start <= time && time < *end
+      } else {
+        *start <= time || time < *end
+      }
+    })
+  }
+
+  /// Determines whether the current local time falls inside one of the windows.
+  pub fn is_active(&self) -> bool {
+    self.is_active_at(Local::now().time())
+  }
+}
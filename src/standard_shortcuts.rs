@@ -0,0 +1,96 @@
+use crate::{Callback, Hotkey, HotkeyBuilder, HotkeyManager, Key, Result};
+
+/// Conventional application shortcuts, bound to the platform's primary modifier
+/// (Cmd on macOS, Ctrl elsewhere).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StandardShortcut {
+  Quit,
+  Close,
+  New,
+  Open,
+  Save,
+  Find,
+  Print,
+  Undo,
+}
+
+impl StandardShortcut {
+  /// Returns the conventional hotkey of the shortcut, e.g. `Cmd+Q` or `Ctrl+Q` for `Quit`.
+  pub fn hotkey(self) -> Hotkey {
+    let key = match self {
+      StandardShortcut::Quit => Key::Q,
+      StandardShortcut::Close => Key::W,
+      StandardShortcut::New => Key::N,
+      StandardShortcut::Open => Key::O,
+      StandardShortcut::Save => Key::S,
+      StandardShortcut::Find => Key::F,
+      StandardShortcut::Print => Key::P,
+      StandardShortcut::Undo => Key::Z,
+    };
+    HotkeyBuilder::new()
+      .primary()
+      .key(key)
+      .build()
+      .expect("standard shortcuts always have a key")
+  }
+}
+
+/// Registers a set of [`StandardShortcut`]s in one call.
+///
+/// Only the shortcuts given a handler are registered, each with its conventional hotkey
+/// unless it's overridden with [`StandardShortcuts::on_hotkey`].
+#[derive(Default)]
+pub struct StandardShortcuts {
+  shortcuts: Vec<(StandardShortcut, Hotkey, Callback)>,
+}
+
+impl StandardShortcuts {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Binds the handler to the conventional hotkey of the shortcut, replacing a previous
+  /// handler of the same shortcut.
+  pub fn on<F>(self, shortcut: StandardShortcut, handler: F) -> Self
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.on_hotkey(shortcut, shortcut.hotkey(), handler)
+  }
+
+  /// Binds the handler to a custom hotkey instead of the conventional one.
+  pub fn on_hotkey<F>(mut self, shortcut: StandardShortcut, hotkey: Hotkey, handler: F) -> Self
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.shortcuts.retain(|(s, _, _)| *s != shortcut);
+    self.shortcuts.push((shortcut, hotkey, Box::new(handler)));
+    self
+  }
+
+  /// Returns the hotkeys that will be registered, in the order they were bound.
+  pub fn hotkeys(&self) -> Vec<(StandardShortcut, Hotkey)> {
+    self
+      .shortcuts
+      .iter()
+      .map(|(shortcut, hotkey, _)| (*shortcut, hotkey.clone()))
+      .collect()
+  }
+
+  /// Registers the bound shortcuts in the manager.
+  ///
+  /// Either all shortcuts are registered or none of them are.
+  pub fn register(self, manager: &mut HotkeyManager) -> Result<()> {
+    let mut registered = Vec::new();
+    for (_, hotkey, handler) in self.shortcuts {
+      if let Err(err) = manager.register(hotkey.clone(), handler) {
+        for hotkey in registered {
+          let _ = manager.unregister(&hotkey);
+        }
+        return Err(err);
+      }
+      registered.push(hotkey);
+    }
+    Ok(())
+  }
+}
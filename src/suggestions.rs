@@ -0,0 +1,179 @@
+use crate::{parse_hotkey, Hotkey, HotkeyRegistry, Key, Modifier, GLOBAL_REGISTRY};
+use once_cell::sync::Lazy;
+use strum::IntoEnumIterator;
+use tauri_hotkey_sys::ListenerHotkey;
+
+#[cfg(target_os = "windows")]
+const RESERVED_ACCELERATORS: &[&str] = &[
+  "ALT+TAB",
+  "ALT+F4",
+  "ALT+ESCAPE",
+  "CTRL+ESCAPE",
+  "CTRL+ALT+DELETE",
+  "CTRL+SHIFT+ESCAPE",
+  "SUPER+D",
+  "SUPER+E",
+  "SUPER+L",
+  "SUPER+R",
+  "SUPER+TAB",
+];
+#[cfg(target_os = "macos")]
+const RESERVED_ACCELERATORS: &[&str] = &[
+  "SUPER+TAB",
+  "SUPER+SPACE",
+  "SUPER+H",
+  "SUPER+M",
+  "SUPER+Q",
+  "ALT+SUPER+ESCAPE",
+  "CTRL+SUPER+Q",
+  "SHIFT+SUPER+3",
+  "SHIFT+SUPER+4",
+  "SHIFT+SUPER+5",
+];
+#[cfg(target_os = "linux")]
+const RESERVED_ACCELERATORS: &[&str] = &[
+  "ALT+TAB",
+  "ALT+F2",
+  "ALT+F4",
+  "CTRL+ALT+DELETE",
+  "CTRL+ALT+T",
+  "SUPER+L",
+];
+
+static RESERVED_HOTKEYS: Lazy<Vec<ListenerHotkey>> = Lazy::new(|| {
+  RESERVED_ACCELERATORS
+    .iter()
+    .map(|accelerator| parse_hotkey(accelerator).unwrap().listener_hotkey())
+    .collect()
+});
+
+// modifiers tried when adding or swapping one, most conventional first
+const SUGGESTED_MODIFIERS: &[Modifier] = &[
+  Modifier::SHIFT,
+  Modifier::ALT,
+  Modifier::CTRL,
+  Modifier::SUPER,
+];
+
+/// Determines whether the hotkey is taken by the OS or desktop environment, e.g. `ALT+F4`
+/// on Windows. The list only covers well-known system shortcuts and is not exhaustive.
+pub fn is_reserved(hotkey: &Hotkey) -> bool {
+  RESERVED_HOTKEYS.contains(&hotkey.listener_hotkey())
+}
+
+/// Returns up to `count` free hotkeys close to the given one in the process-wide registry,
+/// see [`HotkeyRegistry::suggest_alternatives`].
+pub fn suggest_alternatives(hotkey: &Hotkey, count: usize) -> Vec<Hotkey> {
+  GLOBAL_REGISTRY.suggest_alternatives(hotkey, count)
+}
+
+impl HotkeyRegistry {
+  /// Proposes up to `count` hotkeys close to the given one that are neither registered
+  /// in this registry nor [reserved](is_reserved), e.g. to offer a choice when the
+  /// desired hotkey is taken.
+  ///
+  /// Candidates come in order of preference: adding a modifier (Shift first), swapping one
+  /// modifier for another, then moving the last key to a neighbouring letter, digit or
+  /// function key. Hotkeys registered by other applications can't be detected.
+  pub fn suggest_alternatives(&self, hotkey: &Hotkey, count: usize) -> Vec<Hotkey> {
+    let taken = self
+      .hotkeys
+      .lock()
+      .unwrap()
+      .keys()
+      .map(Hotkey::listener_hotkey)
+      .collect::<Vec<_>>();
+    let mut seen = vec![hotkey.listener_hotkey()];
+    let mut suggestions = Vec::new();
+    for candidate in candidates(&hotkey.normalize()) {
+      if suggestions.len() == count {
+        break;
+      }
+      let flags = candidate.listener_hotkey();
+      if seen.contains(&flags) || taken.contains(&flags) || is_reserved(&candidate) {
+        continue;
+      }
+      seen.push(flags);
+      suggestions.push(candidate);
+    }
+    suggestions
+  }
+}
+
+fn candidates(hotkey: &Hotkey) -> Vec<Hotkey> {
+  let with_modifiers = |modifiers: Vec<Modifier>| {
+    Hotkey {
+      modifiers,
+      keys: hotkey.keys.clone(),
+    }
+    .normalize()
+  };
+  let mut candidates = Vec::new();
+
+  for modifier in SUGGESTED_MODIFIERS {
+    if !hotkey.modifiers.contains(modifier) {
+      let mut modifiers = hotkey.modifiers.clone();
+      modifiers.push(*modifier);
+      candidates.push(with_modifiers(modifiers));
+    }
+  }
+
+  for (index, _) in hotkey.modifiers.iter().enumerate() {
+    for modifier in SUGGESTED_MODIFIERS {
+      if !hotkey.modifiers.contains(modifier) {
+        let mut modifiers = hotkey.modifiers.clone();
+        modifiers[index] = *modifier;
+        candidates.push(with_modifiers(modifiers));
+      }
+    }
+  }
+
+  if let Some(last) = hotkey.keys.last() {
+    for key in neighbours(*last) {
+      let mut keys = hotkey.keys.clone();
+      *keys.last_mut().unwrap() = key;
+      if !hotkey.keys.contains(&key) {
+        candidates.push(Hotkey {
+          modifiers: hotkey.modifiers.clone(),
+          keys,
+        });
+      }
+    }
+  }
+
+  candidates
+}
+
+// letters, digits and function keys, the groups a key can be moved within
+fn key_group(key: Key) -> Option<u8> {
+  let name = key.to_string();
+  let mut chars = name.chars();
+  match (chars.next(), chars.as_str()) {
+    (Some(c), "") if c.is_ascii_alphabetic() => Some(0),
+    (Some(c), "") if c.is_ascii_digit() => Some(1),
+    (Some('F'), number) if number.parse::<u8>().is_ok() => Some(2),
+    _ => None,
+  }
+}
+
+// keys of the same group, nearest first, alternating between the next and previous ones
+fn neighbours(key: Key) -> Vec<Key> {
+  let group = match key_group(key) {
+    Some(group) => group,
+    None => return Vec::new(),
+  };
+  let keys = Key::iter()
+    .filter(|k| key_group(*k) == Some(group))
+    .collect::<Vec<_>>();
+  let position = keys.iter().position(|k| *k == key).unwrap();
+  let mut neighbours = Vec::new();
+  for distance in 1..keys.len() {
+    if let Some(next) = keys.get(position + distance) {
+      neighbours.push(*next);
+    }
+    if let Some(previous) = position.checked_sub(distance).map(|i| keys[i]) {
+      neighbours.push(previous);
+    }
+  }
+  neighbours
+}
@@ -0,0 +1,211 @@
+// In-memory listener used by the unit tests so they don't need a display server
+// or grab real keys.
+
+use tauri_hotkey_sys::{HotkeyError, HotkeyListener, KeyState, ListenerHotkey, ListenerOptions};
+
+type Callback = Box<dyn 'static + FnMut() + Send>;
+type LearnCallback = Box<dyn 'static + FnMut(ListenerHotkey) + Send>;
+
+pub struct Listener {
+  options: ListenerOptions,
+  handlers: Vec<(ListenerHotkey, Callback)>,
+  // (requested, assigned) triggers, like a portal binding a different shortcut
+  remapped: Vec<(ListenerHotkey, ListenerHotkey)>,
+  // hotkeys registered with key repeats
+  repeating: Vec<ListenerHotkey>,
+  // hotkeys registered never reporting key repeats
+  never_repeating: Vec<ListenerHotkey>,
+  // hotkeys registered reporting their releases
+  with_release: Vec<ListenerHotkey>,
+  // hotkeys matched by the keyboard hook, and whether their key events are swallowed
+  hooked: Vec<(ListenerHotkey, bool)>,
+  learner: Option<LearnCallback>,
+  // hotkeys owned by another application, refused by the OS
+  taken: Vec<ListenerHotkey>,
+  // fails to report releases, like a backend missing the API
+  refuse_release: bool,
+}
+
+impl Listener {
+  pub fn options(&self) -> &ListenerOptions {
+    &self.options
+  }
+
+  pub fn allows_repeat(&self, hotkey: ListenerHotkey) -> bool {
+    self.repeating.contains(&hotkey)
+  }
+
+  pub fn never_repeats(&self, hotkey: ListenerHotkey) -> bool {
+    self.never_repeating.contains(&hotkey)
+  }
+
+  pub fn reports_release(&self, hotkey: ListenerHotkey) -> bool {
+    self.with_release.contains(&hotkey)
+  }
+
+  pub fn hook_suppresses(&self, hotkey: ListenerHotkey) -> Option<bool> {
+    self
+      .hooked
+      .iter()
+      .find(|(h, _)| *h == hotkey)
+      .map(|(_, suppress)| *suppress)
+  }
+
+  // the user presses a combination while learning, returning whether it was reported
+  pub fn learn(&mut self, hotkey: ListenerHotkey) -> bool {
+    match &mut self.learner {
+      Some(learner) => {
+        learner(hotkey);
+        true
+      }
+      None => false,
+    }
+  }
+
+  // another application takes the hotkey over, unnoticed by the listener like on a real OS
+  pub fn take_over(&mut self, hotkey: ListenerHotkey) {
+    self.taken.push(hotkey);
+  }
+
+  pub fn give_back(&mut self, hotkey: ListenerHotkey) {
+    self.taken.retain(|h| *h != hotkey);
+  }
+
+  pub fn refuse_release(&mut self) {
+    self.refuse_release = true;
+  }
+
+  pub fn remap(&mut self, requested: ListenerHotkey, assigned: ListenerHotkey) {
+    self.remapped.retain(|(h, _)| *h != requested);
+    self.remapped.push((requested, assigned));
+  }
+}
+
+impl HotkeyListener for Listener {
+  fn with_options(options: ListenerOptions) -> Listener {
+    Listener {
+      options,
+      handlers: Vec::new(),
+      remapped: Vec::new(),
+      repeating: Vec::new(),
+      never_repeating: Vec::new(),
+      with_release: Vec::new(),
+      hooked: Vec::new(),
+      learner: None,
+      taken: Vec::new(),
+      refuse_release: false,
+    }
+  }
+
+  fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, callback: F) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey_with_repeat(hotkey, false, callback)
+  }
+
+  fn register_hotkey_with_repeat<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    allow_repeat: bool,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    if self.handlers.iter().any(|(h, _)| *h == hotkey) {
+      return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
+    }
+    if self.taken.contains(&hotkey) {
+      return Err(HotkeyError::HotkeyTakenBySystem(hotkey));
+    }
+    self.handlers.push((hotkey, Box::new(callback)));
+    if allow_repeat {
+      self.repeating.push(hotkey);
+    }
+    Ok(())
+  }
+
+  fn register_hotkey_without_repeat<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey(hotkey, callback)?;
+    self.never_repeating.push(hotkey);
+    Ok(())
+  }
+
+  fn register_hotkey_with_state<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    mut callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(KeyState) + Send,
+  {
+    if self.refuse_release {
+      return Err(HotkeyError::Unsupported);
+    }
+    self.register_hotkey(hotkey, move || callback(KeyState::Pressed))?;
+    self.with_release.push(hotkey);
+    Ok(())
+  }
+
+  fn register_hook_hotkey<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    suppress: bool,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey(hotkey, callback)?;
+    self.hooked.push((hotkey, suppress));
+    Ok(())
+  }
+
+  fn start_learning<F>(&mut self, callback: F) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(ListenerHotkey) + Send,
+  {
+    self.learner = Some(Box::new(callback));
+    Ok(())
+  }
+
+  fn stop_learning(&mut self) -> Result<(), HotkeyError> {
+    self.learner = None;
+    Ok(())
+  }
+
+  fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
+    let count = self.handlers.len();
+    self.handlers.retain(|(h, _)| *h != hotkey);
+    self.repeating.retain(|h| *h != hotkey);
+    self.never_repeating.retain(|h| *h != hotkey);
+    self.with_release.retain(|h| *h != hotkey);
+    self.hooked.retain(|(h, _)| *h != hotkey);
+    if self.handlers.len() == count {
+      return Err(HotkeyError::HotkeyNotRegistered(hotkey));
+    }
+    Ok(())
+  }
+
+  fn registered_hotkeys(&self) -> Vec<ListenerHotkey> {
+    self.handlers.iter().map(|(h, _)| *h).collect()
+  }
+
+  fn effective_hotkey(&self, hotkey: ListenerHotkey) -> Option<ListenerHotkey> {
+    if !self.registered_hotkeys().contains(&hotkey) {
+      return None;
+    }
+    match self.remapped.iter().find(|(h, _)| *h == hotkey) {
+      Some((_, assigned)) => Some(*assigned),
+      None => Some(hotkey),
+    }
+  }
+}
@@ -2,6 +2,8 @@ fn main() {
   #[cfg(target_os = "macos")]
   if std::env::var("TARGET").unwrap().contains("-apple") {
     println!("cargo:rustc-link-lib=framework=Carbon");
+    // NSEvent decodes the media key events
+    println!("cargo:rustc-link-lib=framework=AppKit");
     cc::Build::new()
       .file("src/carbon_hotkey_binding.c")
       .compile("carbon_hotkey_binding.a");
@@ -1,59 +1,83 @@
 #include "carbon_hotkey_binding.h"
 
 #include <Carbon/Carbon.h>
+#include <objc/message.h>
+#include <objc/runtime.h>
+#include <stdlib.h>
 
-HotkeyCallback saved_callback = NULL;
-void *saved_closure = NULL;
+// NSSystemDefined events and their subtype carrying the media keys
+#define NX_SYSDEFINED 14
+#define NX_SUBTYPE_AUX_CONTROL_BUTTONS 8
+
+// Namespaces our hotkey ids. Hotkeys registered by the embedding application
+// with its own signature are passed through to the next event handler.
+#define HOTKEY_SIGNATURE 'htrs'
+
+typedef struct
+{
+    HotkeyCallback callback;
+    void *closure;
+    EventHandlerRef handler_ref;
+} HotkeyHandler;
 
 int hotkey_handler(
     EventHandlerCallRef next_handler, EventRef event, void *user_data)
 {
-    (void)(next_handler);
-    (void)(user_data);
+    HotkeyHandler *handler = user_data;
     EventHotKeyID event_hotkey;
 
     int result = GetEventParameter(event, kEventParamDirectObject, typeEventHotKeyID, NULL, sizeof(event_hotkey), NULL, &event_hotkey);
-    if (result == noErr && saved_callback && saved_closure)
+    int pressed = GetEventKind(event) == kEventHotKeyPressed;
+    if (result == noErr && event_hotkey.signature == HOTKEY_SIGNATURE && handler->callback(event_hotkey.id, pressed, handler->closure))
     {
-        saved_callback(event_hotkey.id, saved_closure);
+        return noErr;
     }
-    return noErr;
+    return CallNextEventHandler(next_handler, event);
 }
 
 void *install_event_handler(HotkeyCallback callback, void *data)
 {
     if (!callback || !data)
         return NULL;
-    saved_callback = callback;
-    saved_closure = data;
-    EventTypeSpec event_type;
-    event_type.eventClass = kEventClassKeyboard;
-    event_type.eventKind = kEventHotKeyPressed;
-    EventHandlerRef handler_ref;
-    int result = InstallEventHandler(GetApplicationEventTarget(), &hotkey_handler, 1, &event_type, data, &handler_ref);
+    HotkeyHandler *handler = malloc(sizeof(HotkeyHandler));
+    if (!handler)
+        return NULL;
+    handler->callback = callback;
+    handler->closure = data;
+    EventTypeSpec event_types[2];
+    event_types[0].eventClass = kEventClassKeyboard;
+    event_types[0].eventKind = kEventHotKeyPressed;
+    event_types[1].eventClass = kEventClassKeyboard;
+    event_types[1].eventKind = kEventHotKeyReleased;
+    int result = InstallEventHandler(GetApplicationEventTarget(), &hotkey_handler, 2, event_types, handler, &handler->handler_ref);
 
     if (result == noErr)
     {
-        return handler_ref;
+        return handler;
     }
 
+    free(handler);
     return NULL;
 }
 
 int uninstall_event_handler(void *handler_ref)
 {
-    return RemoveEventHandler(handler_ref);
+    HotkeyHandler *handler = handler_ref;
+    int result = RemoveEventHandler(handler->handler_ref);
+    if (result == noErr)
+        free(handler);
+    return result;
 }
 
-void *register_hotkey(int id, int modifier, int key)
+void *register_hotkey(int id, int modifier, int key, int *status)
 {
     EventHotKeyRef hotkey_ref;
     EventHotKeyID hotkey_id;
-    hotkey_id.signature = 'htrs';
+    hotkey_id.signature = HOTKEY_SIGNATURE;
     hotkey_id.id = id;
-    int result = RegisterEventHotKey(key, modifier, hotkey_id,
-                                     GetApplicationEventTarget(), 0, &hotkey_ref);
-    if (result == noErr)
+    *status = RegisterEventHotKey(key, modifier, hotkey_id,
+                                  GetApplicationEventTarget(), 0, &hotkey_ref);
+    if (*status == noErr)
         return hotkey_ref;
 
     return NULL;
@@ -62,4 +86,75 @@ void *register_hotkey(int id, int modifier, int key)
 int unregister_hotkey(void *hotkey_ref)
 {
     return UnregisterEventHotKey(hotkey_ref);
-}
\ No newline at end of file
+}
+
+typedef struct
+{
+    MediaKeyCallback callback;
+    void *closure;
+    CFMachPortRef port;
+    CFRunLoopSourceRef source;
+} MediaKeyTap;
+
+CGEventRef media_key_handler(
+    CGEventTapProxy proxy, CGEventType type, CGEventRef event, void *user_data)
+{
+    MediaKeyTap *tap = user_data;
+    if (type == kCGEventTapDisabledByTimeout || type == kCGEventTapDisabledByUserInput)
+    {
+        CGEventTapEnable(tap->port, true);
+        return event;
+    }
+    if (type != NX_SYSDEFINED)
+        return event;
+
+    // the key type and state are only exposed through NSEvent's data1
+    id ns_event = ((id(*)(Class, SEL, CGEventRef))objc_msgSend)(
+        objc_getClass("NSEvent"), sel_registerName("eventWithCGEvent:"), event);
+    if (!ns_event)
+        return event;
+    short subtype = ((short (*)(id, SEL))objc_msgSend)(ns_event, sel_registerName("subtype"));
+    if (subtype != NX_SUBTYPE_AUX_CONTROL_BUTTONS)
+        return event;
+    long data1 = ((long (*)(id, SEL))objc_msgSend)(ns_event, sel_registerName("data1"));
+    int key_type = (data1 & 0xFFFF0000) >> 16;
+    // 0xA on press, 0xB on release
+    int key_down = ((data1 & 0xFF00) >> 8) == 0xA;
+    if (tap->callback(key_type, key_down, tap->closure))
+        return NULL;
+    return event;
+}
+
+void *install_media_key_tap(MediaKeyCallback callback, void *data)
+{
+    if (!callback || !data)
+        return NULL;
+    MediaKeyTap *tap = malloc(sizeof(MediaKeyTap));
+    if (!tap)
+        return NULL;
+    tap->callback = callback;
+    tap->closure = data;
+    // fails without the accessibility permission
+    tap->port = CGEventTapCreate(kCGSessionEventTap, kCGHeadInsertEventTap, kCGEventTapOptionDefault,
+                                 CGEventMaskBit(NX_SYSDEFINED), media_key_handler, tap);
+    if (!tap->port)
+    {
+        free(tap);
+        return NULL;
+    }
+    tap->source = CFMachPortCreateRunLoopSource(kCFAllocatorDefault, tap->port, 0);
+    // the Carbon hotkey events are delivered on the main run loop as well
+    CFRunLoopAddSource(CFRunLoopGetMain(), tap->source, kCFRunLoopCommonModes);
+    return tap;
+}
+
+void uninstall_media_key_tap(void *tap_ref)
+{
+    MediaKeyTap *tap = tap_ref;
+    CGEventTapEnable(tap->port, false);
+    CFRunLoopRemoveSource(CFRunLoopGetMain(), tap->source, kCFRunLoopCommonModes);
+    CFRelease(tap->source);
+    CFMachPortInvalidate(tap->port);
+    CFRelease(tap->port);
+    free(tap);
+}
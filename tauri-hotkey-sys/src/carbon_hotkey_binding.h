@@ -1,6 +1,15 @@
-typedef void (*HotkeyCallback)(int, void *);
+// Receives the hotkey id and whether it was pressed or released, returns non-zero when the
+// id belongs to the callback's listener.
+typedef int (*HotkeyCallback)(int, int, void *);
 
 void *install_event_handler(HotkeyCallback callback, void *data);
 int uninstall_event_handler(void *event_handler_ref);
-void *register_hotkey(int id, int modifier, int key);
-int unregister_hotkey(void *hotkey_ref);
\ No newline at end of file
+void *register_hotkey(int id, int modifier, int key, int *status);
+int unregister_hotkey(void *hotkey_ref);
+
+// Receives the NX_KEYTYPE_* of a media key press or release, returns non-zero when the
+// key is registered, which swallows the event.
+typedef int (*MediaKeyCallback)(int, int, void *);
+
+void *install_media_key_tap(MediaKeyCallback callback, void *data);
+void uninstall_media_key_tap(void *tap_ref);
\ No newline at end of file
@@ -0,0 +1,557 @@
+//! Hotkeys bound to a single keyboard, e.g. a second keyboard dedicated to macros.
+//!
+//! Support varies a lot between platforms:
+//!
+//! - Linux reads the evdev device directly, which needs read access to
+//!   `/dev/input/event*` (usually membership of the `input` group). Each watched device is
+//!   read by a single thread, however many hotkeys are watched on it.
+//! - Windows receives the keyboards' Raw Input on a single thread, without any extra
+//!   permission. A device only reports its interface path, which is used as its name.
+//! - Every other platform returns [`HotkeyError::Unsupported`] and lists no keyboards.
+//!
+//! The device isn't grabbed, so its presses still reach the focused application and the
+//! regular hotkeys.
+
+use super::traits::*;
+#[cfg(any(target_os = "linux", windows))]
+use std::sync::{
+  atomic::{AtomicBool, Ordering},
+  Arc, Mutex,
+};
+
+/// A keyboard that can be passed to [`watch_device`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+  /// Identifies the device, the evdev path such as `/dev/input/event3` on Linux or the
+  /// Raw Input device path on Windows.
+  pub id: String,
+  /// The name reported by the device, e.g. `Logitech USB Keyboard`.
+  pub name: String,
+}
+
+/// Watches a hotkey on a single device until dropped.
+pub struct DeviceWatch {
+  #[cfg_attr(not(any(target_os = "linux", windows)), allow(dead_code))]
+  id: usize,
+}
+
+#[cfg(any(target_os = "linux", windows))]
+impl Drop for DeviceWatch {
+  fn drop(&mut self) {
+    let mut devices = DEVICES.lock().unwrap();
+    devices.watchers.retain(|watcher| watcher.id != self.id);
+    let Devices {
+      watchers, pollers, ..
+    } = &mut *devices;
+    pollers.stop_unused(watchers);
+  }
+}
+
+/// Lists the connected keyboards.
+pub fn list_keyboards() -> Vec<DeviceInfo> {
+  #[cfg(any(target_os = "linux", windows))]
+  {
+    platform::list_keyboards()
+  }
+  #[cfg(not(any(target_os = "linux", windows)))]
+  {
+    Vec::new()
+  }
+}
+
+/// Calls `callback` whenever the hotkey is pressed on the given device, see [`DeviceInfo::id`].
+///
+/// The callback runs on the thread reading the device.
+#[cfg(any(target_os = "linux", windows))]
+pub fn watch_device<F>(
+  device_id: &str,
+  hotkey: ListenerHotkey,
+  callback: F,
+) -> Result<DeviceWatch, HotkeyError>
+where
+  F: 'static + FnMut() + Send,
+{
+  let matcher = DeviceKeyMatcher::new(hotkey.modifiers, platform::device_code(hotkey.key)?);
+  let mut devices = DEVICES.lock().unwrap();
+  devices.pollers.start(device_id)?;
+  devices.last_id += 1;
+  let id = devices.last_id;
+  devices.watchers.push(Watcher {
+    id,
+    device_id: device_id.to_string(),
+    matcher,
+    callback: Arc::new(Mutex::new(callback)),
+  });
+  Ok(DeviceWatch { id })
+}
+
+/// Calls `callback` whenever the hotkey is pressed on the given device, see [`DeviceInfo::id`].
+#[cfg(not(any(target_os = "linux", windows)))]
+pub fn watch_device<F>(
+  _device_id: &str,
+  _hotkey: ListenerHotkey,
+  _callback: F,
+) -> Result<DeviceWatch, HotkeyError>
+where
+  F: 'static + FnMut() + Send,
+{
+  Err(HotkeyError::Unsupported)
+}
+
+#[cfg(any(target_os = "linux", windows))]
+static DEVICES: Mutex<Devices> = Mutex::new(Devices {
+  last_id: 0,
+  watchers: Vec::new(),
+  pollers: platform::Pollers::new(),
+});
+
+#[cfg(any(target_os = "linux", windows))]
+struct Devices {
+  last_id: usize,
+  watchers: Vec<Watcher>,
+  pollers: platform::Pollers,
+}
+
+#[cfg(any(target_os = "linux", windows))]
+pub(crate) struct Watcher {
+  id: usize,
+  device_id: String,
+  matcher: DeviceKeyMatcher,
+  callback: Arc<Mutex<dyn FnMut() + Send>>,
+}
+
+// Feeds a key event read by a poller to the watchers of its device. The callbacks run
+// after the lock is released, so they can watch or drop hotkeys themselves.
+#[cfg(any(target_os = "linux", windows))]
+fn dispatch(stop: &AtomicBool, device_id: &str, code: u16, value: i32) {
+  let callbacks: Vec<_> = {
+    let mut devices = DEVICES.lock().unwrap();
+    // a stopped poller may still read the events queued before it noticed, while a new
+    // poller already reads the same device
+    if stop.load(Ordering::SeqCst) {
+      return;
+    }
+    devices
+      .watchers
+      .iter_mut()
+      .filter(|watcher| watcher.device_id == device_id)
+      .filter_map(|watcher| {
+        if watcher.matcher.feed(code, value) {
+          Some(watcher.callback.clone())
+        } else {
+          None
+        }
+      })
+      .collect()
+  };
+  for callback in callbacks {
+    (callback.lock().unwrap())();
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+  use super::*;
+  use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read},
+    mem,
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    slice, thread,
+  };
+
+  const EV_KEY: u16 = 1;
+
+  // X modifier masks to the evdev codes of their left and right keys
+  pub(crate) const MODIFIER_CODES: &[(u32, &[u16])] = &[
+    (x11_dl::xlib::ShiftMask, &[42, 54]),
+    (x11_dl::xlib::ControlMask, &[29, 97]),
+    (x11_dl::xlib::Mod1Mask, &[56, 100]),
+    (x11_dl::xlib::Mod4Mask, &[125, 126]),
+  ];
+
+  pub(crate) fn list_keyboards() -> Vec<DeviceInfo> {
+    std::fs::read_to_string("/proc/bus/input/devices")
+      .map(|devices| parse_input_devices(&devices))
+      .unwrap_or_default()
+  }
+
+  // X keycodes are the evdev ones offset by 8
+  pub(crate) fn device_code(key: u32) -> Result<u16, HotkeyError> {
+    match crate::physical_key(key) {
+      Some(keycode) if keycode >= 8 => Ok((keycode - 8) as u16),
+      _ => Err(HotkeyError::UnsupportedKey(key)),
+    }
+  }
+
+  /// The device threads, each with the flag stopping it.
+  pub(crate) struct Pollers(Vec<(String, Arc<AtomicBool>)>);
+
+  impl Pollers {
+    pub(crate) const fn new() -> Self {
+      Self(Vec::new())
+    }
+
+    pub(crate) fn start(&mut self, device_id: &str) -> Result<(), HotkeyError> {
+      if self.0.iter().any(|(id, _)| id == device_id) {
+        return Ok(());
+      }
+      let device = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(device_id)
+        .map_err(|err| HotkeyError::BackendApiError(err.raw_os_error().unwrap_or(0) as usize))?;
+      let stop = Arc::new(AtomicBool::new(false));
+      let stopped = stop.clone();
+      let id = device_id.to_string();
+      thread::Builder::new()
+        .name("hotkey device poller".into())
+        .spawn(move || poll_device(device, &id, stopped))
+        .map_err(|_| HotkeyError::Unknown)?;
+      self.0.push((device_id.to_string(), stop));
+      Ok(())
+    }
+
+    pub(crate) fn stop_unused(&mut self, watchers: &[Watcher]) {
+      self.0.retain(|(device_id, stop)| {
+        let used = watchers
+          .iter()
+          .any(|watcher| watcher.device_id == *device_id);
+        if !used {
+          stop.store(true, Ordering::SeqCst);
+        }
+        used
+      });
+    }
+  }
+
+  fn poll_device(mut device: File, device_id: &str, stop: Arc<AtomicBool>) {
+    let mut fd = libc::pollfd {
+      fd: device.as_raw_fd(),
+      events: libc::POLLIN,
+      revents: 0,
+    };
+    'poll: while !stop.load(Ordering::SeqCst) {
+      // wakes up regularly to notice that the last watch was dropped
+      if unsafe { libc::poll(&mut fd, 1, 100) } < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() == io::ErrorKind::Interrupted {
+          continue;
+        }
+        eprintln!("hotkey: failed to poll the input device {}", err);
+        break;
+      }
+      loop {
+        let mut event: libc::input_event = unsafe { mem::zeroed() };
+        let bytes = unsafe {
+          slice::from_raw_parts_mut(
+            &mut event as *mut libc::input_event as *mut u8,
+            mem::size_of::<libc::input_event>(),
+          )
+        };
+        match device.read_exact(bytes) {
+          Ok(()) => {
+            if event.type_ == EV_KEY {
+              dispatch(&stop, device_id, event.code, event.value);
+            }
+          }
+          Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+          Err(err) => {
+            eprintln!("hotkey: failed to read the input device {}", err);
+            break 'poll;
+          }
+        }
+      }
+    }
+    // forgets the poller if it failed, e.g. when the device was unplugged, so the next
+    // watch opens the device again
+    DEVICES
+      .lock()
+      .unwrap()
+      .pollers
+      .0
+      .retain(|(_, poller)| !Arc::ptr_eq(poller, &stop));
+  }
+
+  /// Parses `/proc/bus/input/devices`, keeping the devices with a keyboard handler.
+  pub(crate) fn parse_input_devices(devices: &str) -> Vec<DeviceInfo> {
+    devices
+      .split("\n\n")
+      .filter_map(|device| {
+        let mut name = None;
+        let mut handlers = Vec::new();
+        for line in device.lines() {
+          if let Some(value) = line.strip_prefix("N: Name=") {
+            name = Some(value.trim_matches('"').to_string());
+          } else if let Some(value) = line.strip_prefix("H: Handlers=") {
+            handlers = value.split_whitespace().collect();
+          }
+        }
+        if !handlers.contains(&"kbd") {
+          return None;
+        }
+        let event = handlers
+          .iter()
+          .find(|handler| handler.starts_with("event"))?;
+        Some(DeviceInfo {
+          id: format!("/dev/input/{}", event),
+          name: name.unwrap_or_default(),
+        })
+      })
+      .collect()
+  }
+}
+
+#[cfg(windows)]
+mod platform {
+  use super::*;
+  use std::{
+    collections::{HashMap, HashSet},
+    mem, ptr,
+    sync::mpsc,
+    thread,
+  };
+  use winapi::{
+    shared::{
+      minwindef::{DWORD, LPVOID, UINT},
+      winerror,
+    },
+    um::{
+      errhandlingapi::GetLastError, processthreadsapi::GetCurrentThreadId, winnt::HANDLE, winuser,
+    },
+  };
+
+  // Windows hotkey modifiers to the virtual keys Raw Input reports for them
+  pub(crate) const MODIFIER_CODES: &[(u32, &[u16])] = &[
+    (winuser::MOD_SHIFT as u32, &[winuser::VK_SHIFT as u16]),
+    (winuser::MOD_CONTROL as u32, &[winuser::VK_CONTROL as u16]),
+    (winuser::MOD_ALT as u32, &[winuser::VK_MENU as u16]),
+    (
+      winuser::MOD_WIN as u32,
+      &[winuser::VK_LWIN as u16, winuser::VK_RWIN as u16],
+    ),
+  ];
+
+  pub(crate) fn list_keyboards() -> Vec<DeviceInfo> {
+    let entry_size = mem::size_of::<winuser::RAWINPUTDEVICELIST>() as UINT;
+    let mut count = 0;
+    unsafe {
+      if winuser::GetRawInputDeviceList(ptr::null_mut(), &mut count, entry_size) == UINT::MAX {
+        return Vec::new();
+      }
+      let mut devices = vec![mem::zeroed::<winuser::RAWINPUTDEVICELIST>(); count as usize];
+      let read = winuser::GetRawInputDeviceList(devices.as_mut_ptr(), &mut count, entry_size);
+      if read == UINT::MAX {
+        return Vec::new();
+      }
+      devices.truncate(read as usize);
+      devices
+        .iter()
+        .filter(|device| device.dwType == winuser::RIM_TYPEKEYBOARD)
+        .map(|device| {
+          let id = device_name(device.hDevice);
+          DeviceInfo {
+            name: id.clone(),
+            id,
+          }
+        })
+        .collect()
+    }
+  }
+
+  // Raw Input reports virtual keys, like the hotkeys
+  pub(crate) fn device_code(key: u32) -> Result<u16, HotkeyError> {
+    if key > 0 && key <= 0xFF {
+      Ok(key as u16)
+    } else {
+      Err(HotkeyError::UnsupportedKey(key))
+    }
+  }
+
+  /// The Raw Input thread, shared by every device, with the flag stopping it.
+  pub(crate) struct Pollers(Option<(DWORD, Arc<AtomicBool>)>);
+
+  impl Pollers {
+    pub(crate) const fn new() -> Self {
+      Self(None)
+    }
+
+    pub(crate) fn start(&mut self, device_id: &str) -> Result<(), HotkeyError> {
+      if !list_keyboards().iter().any(|device| device.id == device_id) {
+        return Err(HotkeyError::BackendApiError(
+          winerror::ERROR_DEVICE_NOT_CONNECTED as usize,
+        ));
+      }
+      if self.0.is_some() {
+        return Ok(());
+      }
+      let stop = Arc::new(AtomicBool::new(false));
+      let stopped = stop.clone();
+      let (ready_sender, ready_receiver) = mpsc::channel();
+      thread::Builder::new()
+        .name("hotkey raw input".into())
+        .spawn(move || unsafe { read_raw_input(&stopped, ready_sender) })
+        .map_err(|_| HotkeyError::Unknown)?;
+      let thread_id = ready_receiver
+        .recv()
+        .map_err(|_| HotkeyError::ChannelError())??;
+      self.0 = Some((thread_id, stop));
+      Ok(())
+    }
+
+    pub(crate) fn stop_unused(&mut self, watchers: &[Watcher]) {
+      if !watchers.is_empty() {
+        return;
+      }
+      if let Some((thread_id, stop)) = self.0.take() {
+        stop.store(true, Ordering::SeqCst);
+        unsafe { winuser::PostThreadMessageW(thread_id, winuser::WM_QUIT, 0, 0) };
+      }
+    }
+  }
+
+  unsafe fn read_raw_input(stop: &AtomicBool, ready: mpsc::Sender<Result<DWORD, HotkeyError>>) {
+    let last_error = || HotkeyError::BackendApiError(GetLastError() as usize);
+    // a message-only window receiving the input of every keyboard, even in the background
+    let class: Vec<u16> = "STATIC\0".encode_utf16().collect();
+    let window = winuser::CreateWindowExW(
+      0,
+      class.as_ptr(),
+      ptr::null(),
+      0,
+      0,
+      0,
+      0,
+      0,
+      winuser::HWND_MESSAGE,
+      ptr::null_mut(),
+      ptr::null_mut(),
+      ptr::null_mut(),
+    );
+    if window.is_null() {
+      let _ = ready.send(Err(last_error()));
+      return;
+    }
+    let device_size = mem::size_of::<winuser::RAWINPUTDEVICE>() as UINT;
+    // the generic desktop keyboards
+    let mut device = winuser::RAWINPUTDEVICE {
+      usUsagePage: 0x01,
+      usUsage: 0x06,
+      dwFlags: winuser::RIDEV_INPUTSINK,
+      hwndTarget: window,
+    };
+    if winuser::RegisterRawInputDevices(&device, 1, device_size) == 0 {
+      let _ = ready.send(Err(last_error()));
+      winuser::DestroyWindow(window);
+      return;
+    }
+    if ready.send(Ok(GetCurrentThreadId())).is_ok() {
+      let mut names: HashMap<HANDLE, String> = HashMap::new();
+      let mut held = HashSet::new();
+      let mut msg: winuser::MSG = mem::zeroed();
+      while winuser::GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+        if msg.message == winuser::WM_INPUT {
+          let mut input: winuser::RAWINPUT = mem::zeroed();
+          let mut size = mem::size_of::<winuser::RAWINPUT>() as UINT;
+          let read = winuser::GetRawInputData(
+            msg.lParam as winuser::HRAWINPUT,
+            winuser::RID_INPUT,
+            &mut input as *mut winuser::RAWINPUT as LPVOID,
+            &mut size,
+            mem::size_of::<winuser::RAWINPUTHEADER>() as UINT,
+          );
+          if read != UINT::MAX && input.header.dwType == winuser::RIM_TYPEKEYBOARD {
+            let keyboard = input.data.keyboard();
+            let device = input.header.hDevice;
+            let key = (device, keyboard.VKey);
+            // Raw Input repeats the press while the key is held, unlike evdev which
+            // reports the repeats separately
+            let value = if DWORD::from(keyboard.Flags) & winuser::RI_KEY_BREAK != 0 {
+              held.remove(&key);
+              0
+            } else if held.insert(key) {
+              1
+            } else {
+              2
+            };
+            let device_id = names.entry(device).or_insert_with(|| device_name(device));
+            dispatch(stop, device_id, keyboard.VKey, value);
+          }
+        }
+        // lets the default window procedure release the input
+        winuser::TranslateMessage(&msg);
+        winuser::DispatchMessageW(&msg);
+      }
+    }
+    device.dwFlags = winuser::RIDEV_REMOVE;
+    device.hwndTarget = ptr::null_mut();
+    winuser::RegisterRawInputDevices(&device, 1, device_size);
+    winuser::DestroyWindow(window);
+  }
+
+  unsafe fn device_name(device: HANDLE) -> String {
+    let mut len = 0;
+    if winuser::GetRawInputDeviceInfoW(device, winuser::RIDI_DEVICENAME, ptr::null_mut(), &mut len)
+      == UINT::MAX
+    {
+      return String::new();
+    }
+    let mut name = vec![0u16; len as usize];
+    let read = winuser::GetRawInputDeviceInfoW(
+      device,
+      winuser::RIDI_DEVICENAME,
+      name.as_mut_ptr() as LPVOID,
+      &mut len,
+    );
+    if read == UINT::MAX {
+      return String::new();
+    }
+    name.truncate(read as usize);
+    String::from_utf16_lossy(&name)
+      .trim_end_matches('\0')
+      .to_string()
+  }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+pub(crate) use platform::parse_input_devices;
+
+/// Tracks the modifiers held on a device and reports presses of the hotkey.
+#[cfg(any(target_os = "linux", windows))]
+pub(crate) struct DeviceKeyMatcher {
+  modifiers: u32,
+  code: u16,
+  held: Vec<u16>,
+}
+
+#[cfg(any(target_os = "linux", windows))]
+impl DeviceKeyMatcher {
+  pub(crate) fn new(modifiers: u32, code: u16) -> Self {
+    Self {
+      modifiers,
+      code,
+      held: Vec::new(),
+    }
+  }
+
+  /// Feeds a key event with the evdev values, 0 for a release, 1 for a press and 2 for a
+  /// repeat, returning whether it presses the hotkey with exactly its modifiers held. Key
+  /// repeats don't count as presses.
+  pub(crate) fn feed(&mut self, code: u16, value: i32) -> bool {
+    if platform::MODIFIER_CODES
+      .iter()
+      .any(|(_, codes)| codes.contains(&code))
+    {
+      self.held.retain(|held| *held != code);
+      if value != 0 {
+        self.held.push(code);
+      }
+      return false;
+    }
+    code == self.code
+      && value == 1
+      && platform::MODIFIER_CODES.iter().all(|(mask, codes)| {
+        (self.modifiers & mask != 0) == codes.iter().any(|code| self.held.contains(code))
+      })
+  }
+}
@@ -0,0 +1,169 @@
+//! Hotkeys matched against the key events of a low-level keyboard hook instead of being
+//! grabbed with the OS hotkey API, see `HotkeyListener::register_hook_hotkey`. Only the
+//! Windows backend has such a hook, the dispatcher itself is platform independent.
+
+use std::{
+  mem,
+  time::{Duration, Instant},
+};
+
+use super::traits::*;
+
+type HookCallback = Box<dyn 'static + FnMut() + Send>;
+type LearnCallback = Box<dyn 'static + FnMut(ListenerHotkey) + Send>;
+
+// How long a modifier tapped alone applies to the next key while learning with sticky
+// modifiers, see `HookDispatcher::set_sticky_modifiers`. Each tap restarts it.
+pub(crate) const STICKY_MODIFIER_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub(crate) struct HookDispatcher {
+  // the modifier flag of each modifier key, e.g. of both Shift keys
+  modifier_keys: &'static [(u32, u32)],
+  // the modifier keys currently down
+  held_modifiers: Vec<u32>,
+  // the hotkeys and whether their key events are swallowed
+  hotkeys: Vec<(ListenerHotkey, bool, HookCallback)>,
+  // the other keys currently down and whether their press was swallowed, so their key
+  // repeats and release are too
+  pressed: Vec<(u32, bool)>,
+  // reports every combination pressed, see `HotkeyListener::start_learning`
+  learner: Option<LearnCallback>,
+  // whether tapped modifiers apply to the next key reported to the learner
+  sticky_modifiers: bool,
+  // the modifier key pressed last, until another key is pressed
+  tapped_modifier: Option<u32>,
+  // the modifiers tapped alone since the last key, and when the last one was released
+  latched: Option<(u32, Instant)>,
+}
+
+impl HookDispatcher {
+  pub(crate) fn new(modifier_keys: &'static [(u32, u32)]) -> Self {
+    Self {
+      modifier_keys,
+      held_modifiers: Vec::new(),
+      hotkeys: Vec::new(),
+      pressed: Vec::new(),
+      learner: None,
+      sticky_modifiers: false,
+      tapped_modifier: None,
+      latched: None,
+    }
+  }
+
+  pub(crate) fn register(
+    &mut self,
+    hotkey: ListenerHotkey,
+    suppress: bool,
+    callback: HookCallback,
+  ) -> Result<(), HotkeyError> {
+    if self.hotkeys.iter().any(|(h, _, _)| *h == hotkey) {
+      return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
+    }
+    self.hotkeys.push((hotkey, suppress, callback));
+    Ok(())
+  }
+
+  // returns whether the hotkey was registered
+  pub(crate) fn unregister(&mut self, hotkey: ListenerHotkey) -> bool {
+    let count = self.hotkeys.len();
+    self.hotkeys.retain(|(h, _, _)| *h != hotkey);
+    self.hotkeys.len() != count
+  }
+
+  // the registered hotkeys, in registration order
+  pub(crate) fn hotkeys(&self) -> Vec<ListenerHotkey> {
+    self.hotkeys.iter().map(|(hotkey, _, _)| *hotkey).collect()
+  }
+
+  // replaces the learner, returning whether one was set
+  pub(crate) fn set_learner(&mut self, learner: Option<LearnCallback>) -> bool {
+    mem::replace(&mut self.learner, learner).is_some()
+  }
+
+  // Lets the learner accept the event order of sticky keys, where each modifier is pressed
+  // and released before the key: modifiers tapped alone are latched and reported along
+  // with the next key pressed within `STICKY_MODIFIER_TIMEOUT`. Hotkeys are still only
+  // matched with the modifiers held down, sticky keys already hold them for the OS.
+  pub(crate) fn set_sticky_modifiers(&mut self, sticky_modifiers: bool) {
+    self.sticky_modifiers = sticky_modifiers;
+    self.latched = None;
+  }
+
+  // whether the hook is needed at all
+  pub(crate) fn is_empty(&self) -> bool {
+    self.hotkeys.is_empty() && self.learner.is_none()
+  }
+
+  fn modifiers(&self) -> u32 {
+    self
+      .modifier_keys
+      .iter()
+      .filter(|(key, _)| self.held_modifiers.contains(key))
+      .fold(0, |modifiers, (_, modifier)| modifiers | modifier)
+  }
+
+  // the modifiers latched by sticky keys, unless they expired
+  fn latched_modifiers(&self, time: Instant) -> u32 {
+    match self.latched {
+      Some((modifiers, at)) if time.saturating_duration_since(at) <= STICKY_MODIFIER_TIMEOUT => {
+        modifiers
+      }
+      _ => 0,
+    }
+  }
+
+  // Runs the callback of the hotkey pressed by a key event, returning whether the event is
+  // swallowed instead of reaching the focused application. Modifier keys always reach it.
+  pub(crate) fn key_event(&mut self, key: u32, state: KeyState) -> bool {
+    self.key_event_at(key, state, Instant::now())
+  }
+
+  pub(crate) fn key_event_at(&mut self, key: u32, state: KeyState, time: Instant) -> bool {
+    let modifier = self
+      .modifier_keys
+      .iter()
+      .find(|(modifier_key, _)| *modifier_key == key)
+      .map(|(_, modifier)| *modifier);
+    if let Some(modifier) = modifier {
+      match state {
+        KeyState::Pressed if !self.held_modifiers.contains(&key) => {
+          self.held_modifiers.push(key);
+          self.tapped_modifier = Some(key);
+        }
+        KeyState::Pressed => {}
+        KeyState::Released => {
+          self.held_modifiers.retain(|held| *held != key);
+          if self.sticky_modifiers && self.tapped_modifier.take() == Some(key) {
+            self.latched = Some((self.latched_modifiers(time) | modifier, time));
+          }
+        }
+      }
+      return false;
+    }
+    self.tapped_modifier = None;
+    let pressed = self.pressed.iter().position(|(pressed, _)| *pressed == key);
+    match (state, pressed) {
+      // key repeats
+      (KeyState::Pressed, Some(index)) => self.pressed[index].1,
+      (KeyState::Pressed, None) => {
+        let hotkey = ListenerHotkey::new(self.modifiers(), key);
+        let latched = self.latched_modifiers(time);
+        self.latched = None;
+        if let Some(learner) = &mut self.learner {
+          learner(ListenerHotkey::new(hotkey.modifiers | latched, key));
+        }
+        let suppress = match self.hotkeys.iter_mut().find(|(h, _, _)| *h == hotkey) {
+          Some((_, suppress, callback)) => {
+            callback();
+            *suppress
+          }
+          None => false,
+        };
+        self.pressed.push((key, suppress));
+        suppress
+      }
+      (KeyState::Released, Some(index)) => self.pressed.remove(index).1,
+      (KeyState::Released, None) => false,
+    }
+  }
+}
@@ -1,38 +1,87 @@
+// the platform listeners are unused while the mock replaces them
 #[cfg(target_os = "linux")]
+#[cfg_attr(feature = "mock", allow(dead_code))]
 mod linux;
 #[cfg(target_os = "macos")]
+#[cfg_attr(feature = "mock", allow(dead_code))]
 mod macos;
 #[cfg(target_os = "windows")]
+#[cfg_attr(feature = "mock", allow(dead_code))]
 mod windows;
 
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::Listener;
+
+#[cfg(feature = "devices")]
+pub mod devices;
+// only driven by the Windows keyboard hook, and the tests
+#[cfg_attr(any(not(target_os = "windows"), feature = "mock"), allow(dead_code))]
+mod hook;
 mod traits;
-pub use traits::{HotkeyError, HotkeyListener, ListenerHotkey};
+pub use traits::{
+  HotkeyError, HotkeyListener, KeyState, ListenerBuilder, ListenerHotkey, ListenerOptions,
+  MAX_HOTKEY_ID,
+};
 
+#[cfg(target_os = "linux")]
+pub use linux::is_foreground_window;
+#[cfg(target_os = "linux")]
+pub use linux::is_hotkey_held;
+#[cfg(target_os = "linux")]
+pub use linux::is_supported_key;
 #[cfg(target_os = "linux")]
 pub use linux::keys;
 #[cfg(target_os = "linux")]
 pub use linux::modifiers;
 #[cfg(target_os = "linux")]
+pub use linux::physical_key;
+#[cfg(all(target_os = "linux", feature = "simulate"))]
+pub use linux::simulate_hotkey;
+#[cfg(all(target_os = "linux", not(feature = "mock")))]
 pub use linux::Listener;
 
+#[cfg(target_os = "macos")]
+pub use macos::is_foreground_window;
+#[cfg(target_os = "macos")]
+pub use macos::is_hotkey_held;
+#[cfg(target_os = "macos")]
+pub use macos::is_supported_key;
 #[cfg(target_os = "macos")]
 pub use macos::keys;
 #[cfg(target_os = "macos")]
 pub use macos::modifiers;
 #[cfg(target_os = "macos")]
+pub use macos::physical_key;
+#[cfg(all(target_os = "macos", feature = "simulate"))]
+pub use macos::simulate_hotkey;
+#[cfg(all(target_os = "macos", not(feature = "mock")))]
 pub use macos::Listener;
 
+#[cfg(target_os = "windows")]
+pub use windows::is_foreground_window;
+#[cfg(target_os = "windows")]
+pub use windows::is_hotkey_held;
+#[cfg(target_os = "windows")]
+pub use windows::is_supported_key;
 #[cfg(target_os = "windows")]
 pub use windows::keys;
 #[cfg(target_os = "windows")]
 pub use windows::modifiers;
 #[cfg(target_os = "windows")]
+pub use windows::physical_key;
+#[cfg(all(target_os = "windows", feature = "simulate"))]
+pub use windows::simulate_hotkey;
+#[cfg(all(target_os = "windows", not(feature = "mock")))]
 pub use windows::Listener;
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  // kept as written before `registered_hotkeys` had a stable order
+  #[allow(clippy::manual_contains)]
   #[test]
   fn register_unregister_hotkey_test() {
     let mut listener = Listener::new();
@@ -67,6 +116,378 @@ mod tests {
     assert_eq!(listener.registered_hotkeys().len(), 0);
   }
 
+  #[test]
+  fn multiple_listeners_test() {
+    // hotkey ids of independent listeners must not collide
+    let mut listener1 = Listener::new();
+    let mut listener2 = Listener::new();
+    let hotkey1 = ListenerHotkey::new(modifiers::ALT, keys::C);
+    let hotkey2 = ListenerHotkey::new(modifiers::ALT, keys::D);
+    assert_eq!(listener1.register_hotkey(hotkey1, || {}), Ok(()));
+    assert_eq!(listener2.register_hotkey(hotkey2, || {}), Ok(()));
+    assert_eq!(listener1.registered_hotkeys(), vec![hotkey1]);
+    assert_eq!(listener2.registered_hotkeys(), vec![hotkey2]);
+    assert_eq!(listener1.unregister_hotkey(hotkey1), Ok(()));
+    assert_eq!(listener2.unregister_hotkey(hotkey2), Ok(()));
+  }
+
+  #[test]
+  fn registered_hotkeys_order_test() {
+    let mut listener = Listener::new();
+    let hotkeys = [
+      ListenerHotkey::new(modifiers::ALT, keys::E),
+      ListenerHotkey::new(modifiers::SHIFT, keys::F),
+      ListenerHotkey::new(modifiers::CONTROL, keys::G),
+    ];
+    for hotkey in &hotkeys {
+      assert_eq!(listener.register_hotkey(*hotkey, || {}), Ok(()));
+    }
+    let registered = listener.registered_hotkeys();
+    // ids are assigned in registration order, except on Linux where they're keycodes
+    #[cfg(not(target_os = "linux"))]
+    assert_eq!(registered, hotkeys);
+    for _ in 0..10 {
+      assert_eq!(listener.registered_hotkeys(), registered);
+    }
+    for hotkey in &hotkeys {
+      assert_eq!(listener.unregister_hotkey(*hotkey), Ok(()));
+    }
+  }
+
+  // generic modifiers must match both physical sides, so they map to the side-agnostic flags
+  #[cfg(target_os = "windows")]
+  #[test]
+  fn side_agnostic_modifiers_test() {
+    use winapi::um::winuser;
+    assert_eq!(modifiers::ALT, winuser::MOD_ALT as u32);
+    assert_eq!(modifiers::CONTROL, winuser::MOD_CONTROL as u32);
+    assert_eq!(modifiers::SHIFT, winuser::MOD_SHIFT as u32);
+    assert_eq!(modifiers::SUPER, winuser::MOD_WIN as u32);
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn side_agnostic_modifiers_test() {
+    use x11_dl::xlib;
+    assert_eq!(modifiers::ALT, xlib::Mod1Mask);
+    assert_eq!(modifiers::CONTROL, xlib::ControlMask);
+    assert_eq!(modifiers::SHIFT, xlib::ShiftMask);
+    assert_eq!(modifiers::SUPER, xlib::Mod4Mask);
+  }
+
+  #[cfg(target_os = "macos")]
+  #[test]
+  fn side_agnostic_modifiers_test() {
+    // Carbon's cmdKey, shiftKey, optionKey and controlKey
+    assert_eq!(modifiers::SUPER, 1 << 8);
+    assert_eq!(modifiers::SHIFT, 1 << 9);
+    assert_eq!(modifiers::ALT, 1 << 11);
+    assert_eq!(modifiers::CONTROL, 1 << 12);
+  }
+
+  #[cfg(target_os = "macos")]
+  #[test]
+  fn unsupported_keys_test() {
+    let mut listener = Listener::new();
+    for key in &[
+      keys::PRINT_SCREEN,
+      keys::NUMLOCK,
+      keys::MEDIA_STOP,
+      keys::LAUNCH_MAIL,
+      keys::F21,
+    ] {
+      assert_eq!(
+        listener.register_hotkey(ListenerHotkey::new(0, *key), || {}),
+        Err(HotkeyError::UnsupportedKey(*key))
+      );
+    }
+    let hotkey = ListenerHotkey::new(modifiers::SHIFT, keys::MEDIA_PLAY_PAUSE);
+    assert_eq!(
+      listener.register_hotkey(hotkey, || {}),
+      Err(HotkeyError::UnsupportedModifier(modifiers::SHIFT))
+    );
+    assert!(listener.registered_hotkeys().is_empty());
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn grab_error_test() {
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL, keys::A);
+    assert_eq!(
+      linux::grab_error(x11_dl::xlib::BadAccess, hotkey),
+      HotkeyError::HotkeyTakenBySystem(hotkey)
+    );
+    assert_eq!(
+      linux::grab_error(x11_dl::xlib::BadValue, hotkey),
+      HotkeyError::BackendApiError(x11_dl::xlib::BadValue.into())
+    );
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn held_keys_test() {
+    use x11_dl::xlib;
+    let mut held = linux::HeldKeys::default();
+    let (keycode, other_keycode) = (38, 39);
+    let hotkey = (keycode, xlib::ControlMask);
+    let registered = |id: &(i32, u32)| *id == hotkey;
+    let update = |held: &mut linux::HeldKeys, id, state| held.update(id, state, registered);
+    assert_eq!(update(&mut held, hotkey, KeyState::Pressed), Some(hotkey));
+    // key repeats aren't presses
+    assert_eq!(update(&mut held, hotkey, KeyState::Pressed), None);
+    assert_eq!(update(&mut held, hotkey, KeyState::Released), Some(hotkey));
+    assert_eq!(update(&mut held, hotkey, KeyState::Released), None);
+
+    // releasing the modifier first drops it from the state of the key release
+    assert_eq!(update(&mut held, hotkey, KeyState::Pressed), Some(hotkey));
+    assert_eq!(
+      update(&mut held, (keycode, 0), KeyState::Released),
+      Some(hotkey)
+    );
+    assert_eq!(update(&mut held, hotkey, KeyState::Pressed), Some(hotkey));
+    assert_eq!(update(&mut held, hotkey, KeyState::Released), Some(hotkey));
+
+    assert_eq!(
+      update(
+        &mut held,
+        (other_keycode, xlib::ControlMask),
+        KeyState::Pressed
+      ),
+      None
+    );
+    assert_eq!(
+      update(&mut held, (other_keycode, 0), KeyState::Released),
+      None
+    );
+  }
+
+  #[cfg(target_os = "macos")]
+  #[test]
+  fn register_error_test() {
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL, keys::A);
+    assert_eq!(
+      macos::register_error(-9878, hotkey),
+      HotkeyError::HotkeyTakenBySystem(hotkey)
+    );
+    // negative statuses are reported unchanged
+    assert_eq!(
+      macos::register_error(-9879, hotkey),
+      HotkeyError::OsStatus(-9879)
+    );
+    assert_eq!(
+      macos::register_error(-50, hotkey),
+      HotkeyError::OsStatus(-50)
+    );
+  }
+
+  #[cfg(target_os = "windows")]
+  #[test]
+  fn register_error_test() {
+    use winapi::shared::winerror;
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL, keys::A);
+    assert_eq!(
+      windows::register_error(winerror::ERROR_HOTKEY_ALREADY_REGISTERED, hotkey),
+      HotkeyError::HotkeyTakenBySystem(hotkey)
+    );
+    assert_eq!(
+      windows::register_error(winerror::ERROR_INVALID_PARAMETER, hotkey),
+      HotkeyError::BackendApiError(winerror::ERROR_INVALID_PARAMETER as usize)
+    );
+  }
+
+  #[cfg(target_os = "windows")]
+  #[test]
+  fn repeat_modifier_test() {
+    use winapi::um::winuser::MOD_NOREPEAT;
+    assert_eq!(windows::repeat_modifier(true, false), MOD_NOREPEAT as u32);
+    assert_eq!(windows::repeat_modifier(true, true), 0);
+    assert_eq!(windows::repeat_modifier(false, false), 0);
+    assert_eq!(windows::repeat_modifier(false, true), 0);
+  }
+
+  #[cfg(all(target_os = "linux", feature = "devices"))]
+  #[test]
+  fn device_key_matcher_test() {
+    use devices::DeviceKeyMatcher;
+    use x11_dl::xlib;
+    // KEY_A, KEY_LEFTCTRL, KEY_RIGHTCTRL and KEY_LEFTSHIFT
+    let (a, left_ctrl, right_ctrl, shift) = (30, 29, 97, 42);
+    let mut matcher = DeviceKeyMatcher::new(xlib::ControlMask, a);
+    assert!(!matcher.feed(a, 1));
+    assert!(!matcher.feed(a, 0));
+    assert!(!matcher.feed(left_ctrl, 1));
+    assert!(matcher.feed(a, 1));
+    // repeats and releases aren't presses
+    assert!(!matcher.feed(a, 2));
+    assert!(!matcher.feed(a, 0));
+    // extra modifiers don't match
+    assert!(!matcher.feed(shift, 1));
+    assert!(!matcher.feed(a, 1));
+    assert!(!matcher.feed(shift, 0));
+    assert!(!matcher.feed(left_ctrl, 0));
+    assert!(!matcher.feed(right_ctrl, 1));
+    assert!(matcher.feed(a, 1));
+  }
+
+  #[cfg(all(target_os = "linux", feature = "devices"))]
+  #[test]
+  fn parse_input_devices_test() {
+    let devices = "I: Bus=0011 Vendor=0001 Product=0001 Version=ab41
+N: Name=\"AT Translated Set 2 keyboard\"
+P: Phys=isa0060/serio0/input0
+H: Handlers=sysrq kbd leds event2
+B: EV=120013
+
+I: Bus=0011 Vendor=0002 Product=0013 Version=0006
+N: Name=\"VirtualPS/2 VMware VMMouse\"
+H: Handlers=mouse0 event3
+B: EV=b
+";
+    assert_eq!(
+      devices::parse_input_devices(devices),
+      vec![devices::DeviceInfo {
+        id: "/dev/input/event2".into(),
+        name: "AT Translated Set 2 keyboard".into(),
+      }]
+    );
+  }
+
+  // the mock listener doesn't receive simulated key presses
+  #[cfg(all(feature = "simulate", not(feature = "mock")))]
+  #[test]
+  fn simulate_hotkey_test() {
+    use std::time::{Duration, Instant};
+
+    let mut listener = Listener::new();
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL | modifiers::ALT, keys::H);
+    let (tx, rx) = std::sync::mpsc::channel();
+    assert_eq!(
+      listener.register_hotkey(hotkey, move || {
+        let _ = tx.send(Instant::now());
+      }),
+      Ok(())
+    );
+    let start = Instant::now();
+    assert_eq!(simulate_hotkey(hotkey), Ok(()));
+    let fired = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+    assert!(fired - start < Duration::from_millis(500));
+    assert_eq!(listener.unregister_hotkey(hotkey), Ok(()));
+  }
+
+  #[cfg(feature = "mock")]
+  #[test]
+  fn mock_trigger_test() {
+    use std::sync::{Arc, Mutex};
+
+    let mut listener = Listener::new();
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL, keys::K);
+    let states = Arc::new(Mutex::new(Vec::new()));
+    let states_ = states.clone();
+    assert_eq!(
+      listener.register_hotkey_with_state(hotkey, move |state| states_.lock().unwrap().push(state)),
+      Ok(())
+    );
+    assert!(listener.trigger(hotkey));
+    assert_eq!(
+      *states.lock().unwrap(),
+      vec![KeyState::Pressed, KeyState::Released]
+    );
+
+    assert_eq!(listener.unregister_hotkey(hotkey), Ok(()));
+    assert!(!listener.trigger(hotkey));
+    assert_eq!(states.lock().unwrap().len(), 2);
+  }
+
+  #[test]
+  fn rapid_register_unregister_test() {
+    let mut listener = Listener::new();
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL | modifiers::SHIFT, keys::J);
+    for _ in 0..100 {
+      assert_eq!(listener.register_hotkey(hotkey, || {}), Ok(()));
+      assert_eq!(listener.unregister_hotkey(hotkey), Ok(()));
+    }
+    assert!(listener.registered_hotkeys().is_empty());
+  }
+
+  #[cfg(any(target_os = "windows", target_os = "macos"))]
+  #[test]
+  fn id_base_test() {
+    let mut listener: Listener = ListenerBuilder::new().id_base(0x1000).unwrap().build();
+    let hotkeys = [
+      ListenerHotkey::new(modifiers::CONTROL | modifiers::ALT, keys::K),
+      ListenerHotkey::new(modifiers::CONTROL | modifiers::ALT, keys::L),
+    ];
+    for hotkey in &hotkeys {
+      assert_eq!(listener.register_hotkey(*hotkey, || {}), Ok(()));
+    }
+    let ids = listener.registered_ids();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.iter().all(|id| *id > 0x1000 && *id <= MAX_HOTKEY_ID));
+    // macOS ids are unique process-wide, so they're only consecutive on Windows
+    #[cfg(target_os = "windows")]
+    assert_eq!(ids, vec![0x1001, 0x1002]);
+    assert_eq!(listener.unregister_hotkeys(&hotkeys), Ok(()));
+  }
+
+  #[test]
+  fn id_base_range_test() {
+    for id_base in &[-1, MAX_HOTKEY_ID, MAX_HOTKEY_ID + 1, i32::MAX] {
+      assert_eq!(
+        ListenerBuilder::new().id_base(*id_base).unwrap_err(),
+        HotkeyError::InvalidIdBase(*id_base)
+      );
+    }
+    assert!(ListenerBuilder::new().id_base(0).is_ok());
+    assert!(ListenerBuilder::new().id_base(MAX_HOTKEY_ID - 1).is_ok());
+  }
+
+  #[test]
+  fn next_free_id_test() {
+    let base = 0x1000;
+    assert_eq!(traits::next_free_id(base, base, |_| true), Ok(base + 1));
+    // the last id given to another listener doesn't move the search below the base
+    assert_eq!(traits::next_free_id(base, 5, |_| true), Ok(base + 1));
+    assert_eq!(traits::next_free_id(base, base + 3, |_| true), Ok(base + 4));
+    // wraps around to reuse released ids
+    assert_eq!(
+      traits::next_free_id(base, MAX_HOTKEY_ID, |_| true),
+      Ok(base + 1)
+    );
+    assert_eq!(
+      traits::next_free_id(base, base + 1, |id| id == base + 1),
+      Ok(base + 1)
+    );
+    assert_eq!(
+      traits::next_free_id(MAX_HOTKEY_ID - 1, 0, |_| true),
+      Ok(MAX_HOTKEY_ID)
+    );
+    assert_eq!(
+      traits::next_free_id(MAX_HOTKEY_ID - 1, 0, |_| false),
+      Err(HotkeyError::NoFreeHotkeyId)
+    );
+  }
+
+  #[cfg(any(target_os = "windows", target_os = "macos"))]
+  #[test]
+  fn exhausted_ids_test() {
+    let mut listener: Listener = ListenerBuilder::new()
+      .id_base(MAX_HOTKEY_ID - 1)
+      .unwrap()
+      .build();
+    let hotkey1 = ListenerHotkey::new(modifiers::CONTROL | modifiers::ALT, keys::K);
+    let hotkey2 = ListenerHotkey::new(modifiers::CONTROL | modifiers::ALT, keys::L);
+    assert_eq!(listener.register_hotkey(hotkey1, || {}), Ok(()));
+    assert_eq!(
+      listener.register_hotkey(hotkey2, || {}),
+      Err(HotkeyError::NoFreeHotkeyId)
+    );
+    // the id of a released hotkey is reused
+    assert_eq!(listener.unregister_hotkey(hotkey1), Ok(()));
+    assert_eq!(listener.register_hotkey(hotkey2, || {}), Ok(()));
+    assert_eq!(listener.registered_ids(), vec![MAX_HOTKEY_ID]);
+    assert_eq!(listener.unregister_hotkey(hotkey2), Ok(()));
+  }
+
   #[test]
   fn unregister_invalid_hotkey_test() {
     let mut listener = Listener::new();
@@ -79,6 +500,24 @@ mod tests {
     assert_eq!(listener.registered_hotkeys().len(), 0);
   }
 
+  #[test]
+  fn unregister_hotkeys_test() {
+    let mut listener = Listener::new();
+    let hotkey1 = ListenerHotkey::new(modifiers::ALT, keys::H);
+    let hotkey2 = ListenerHotkey::new(modifiers::ALT, keys::J);
+    let unknown = ListenerHotkey::new(modifiers::ALT, keys::K);
+    assert_eq!(listener.register_hotkey(hotkey1, || {}), Ok(()));
+    assert_eq!(listener.register_hotkey(hotkey2, || {}), Ok(()));
+    // the registered hotkeys are released even though one of them isn't registered
+    assert_eq!(
+      listener.unregister_hotkeys(&[hotkey1, unknown, hotkey2, hotkey1]),
+      Err(HotkeyError::HotkeyNotRegistered(unknown))
+    );
+    assert!(listener.registered_hotkeys().is_empty());
+    assert_eq!(listener.register_hotkey(hotkey1, || {}), Ok(()));
+    assert_eq!(listener.unregister_hotkeys(&[hotkey1]), Ok(()));
+  }
+
   #[test]
   fn reregister_hotkey_test() {
     let mut listener = Listener::new();
@@ -96,4 +535,157 @@ mod tests {
     assert_eq!(listener.unregister_hotkey(hotkey), Ok(()));
     assert_eq!(listener.registered_hotkeys().len(), 0);
   }
+
+  #[test]
+  fn hook_dispatcher_test() {
+    use std::sync::{Arc, Mutex};
+    // fake codes of the left and right Control keys and of Shift
+    const MODIFIER_KEYS: &[(u32, u32)] = &[
+      (1, modifiers::CONTROL),
+      (2, modifiers::CONTROL),
+      (3, modifiers::SHIFT),
+    ];
+    let pressed = Arc::new(Mutex::new(Vec::new()));
+    let mut dispatcher = hook::HookDispatcher::new(MODIFIER_KEYS);
+    let suppressed = ListenerHotkey::new(modifiers::CONTROL, 10);
+    let passed = ListenerHotkey::new(modifiers::CONTROL | modifiers::SHIFT, 11);
+    for (hotkey, suppress) in [(suppressed, true), (passed, false)] {
+      let pressed = pressed.clone();
+      let callback = Box::new(move || pressed.lock().unwrap().push(hotkey));
+      assert_eq!(dispatcher.register(hotkey, suppress, callback), Ok(()));
+    }
+    assert_eq!(
+      dispatcher.register(suppressed, false, Box::new(|| {})),
+      Err(HotkeyError::HotkeyAlreadyRegistered(suppressed))
+    );
+    // the right Control key, with its press, repeats and release swallowed
+    assert!(!dispatcher.key_event(2, KeyState::Pressed));
+    assert!(dispatcher.key_event(10, KeyState::Pressed));
+    assert!(dispatcher.key_event(10, KeyState::Pressed));
+    assert!(dispatcher.key_event(10, KeyState::Released));
+    assert_eq!(*pressed.lock().unwrap(), vec![suppressed]);
+    // reaches the application along with the modifiers
+    assert!(!dispatcher.key_event(3, KeyState::Pressed));
+    assert!(!dispatcher.key_event(11, KeyState::Pressed));
+    assert!(!dispatcher.key_event(11, KeyState::Released));
+    assert!(!dispatcher.key_event(3, KeyState::Released));
+    assert!(!dispatcher.key_event(2, KeyState::Released));
+    assert_eq!(*pressed.lock().unwrap(), vec![suppressed, passed]);
+    // unregistered combinations reach the application
+    assert!(!dispatcher.key_event(10, KeyState::Pressed));
+    assert!(!dispatcher.key_event(10, KeyState::Released));
+    // the left Control key, released before the key whose release is still swallowed
+    assert!(!dispatcher.key_event(1, KeyState::Pressed));
+    assert!(dispatcher.key_event(10, KeyState::Pressed));
+    assert!(!dispatcher.key_event(1, KeyState::Released));
+    assert!(dispatcher.key_event(10, KeyState::Released));
+    assert_eq!(pressed.lock().unwrap().len(), 3);
+    assert!(dispatcher.unregister(suppressed));
+    assert!(!dispatcher.unregister(suppressed));
+    assert_eq!(dispatcher.hotkeys(), vec![passed]);
+    assert!(dispatcher.unregister(passed));
+    assert!(dispatcher.is_empty());
+  }
+
+  #[test]
+  fn hook_learner_test() {
+    use std::sync::{Arc, Mutex};
+    const MODIFIER_KEYS: &[(u32, u32)] = &[(1, modifiers::CONTROL), (2, modifiers::SHIFT)];
+    let learned = Arc::new(Mutex::new(Vec::new()));
+    let learned_ = learned.clone();
+    let mut dispatcher = hook::HookDispatcher::new(MODIFIER_KEYS);
+    assert!(dispatcher.is_empty());
+    let learner = Box::new(move |hotkey| learned_.lock().unwrap().push(hotkey));
+    assert!(!dispatcher.set_learner(Some(learner)));
+    assert!(!dispatcher.is_empty());
+    // Ctrl+Shift+P, the modifiers and the key repeat aren't reported
+    for (key, state) in [
+      (1, KeyState::Pressed),
+      (2, KeyState::Pressed),
+      (keys::P, KeyState::Pressed),
+      (keys::P, KeyState::Pressed),
+      (keys::P, KeyState::Released),
+      (2, KeyState::Released),
+      (1, KeyState::Released),
+      (keys::A, KeyState::Pressed),
+      (keys::A, KeyState::Released),
+    ] {
+      // learning doesn't swallow any key
+      assert!(!dispatcher.key_event(key, state));
+    }
+    assert_eq!(
+      *learned.lock().unwrap(),
+      vec![
+        ListenerHotkey::new(modifiers::CONTROL | modifiers::SHIFT, keys::P),
+        ListenerHotkey::new(0, keys::A),
+      ]
+    );
+    assert!(dispatcher.set_learner(None));
+    assert!(dispatcher.is_empty());
+    dispatcher.key_event(keys::B, KeyState::Pressed);
+    assert_eq!(learned.lock().unwrap().len(), 2);
+  }
+
+  #[test]
+  fn hook_sticky_modifiers_test() {
+    use std::{
+      sync::{Arc, Mutex},
+      time::{Duration, Instant},
+    };
+    const MODIFIER_KEYS: &[(u32, u32)] = &[(1, modifiers::CONTROL), (2, modifiers::SHIFT)];
+    let learned = Arc::new(Mutex::new(Vec::new()));
+    let learned_ = learned.clone();
+    let mut dispatcher = hook::HookDispatcher::new(MODIFIER_KEYS);
+    dispatcher.set_learner(Some(Box::new(move |hotkey| {
+      learned_.lock().unwrap().push(hotkey)
+    })));
+    let pressed = Arc::new(Mutex::new(0));
+    let pressed_ = pressed.clone();
+    let hotkey = ListenerHotkey::new(modifiers::CONTROL, keys::H);
+    let callback = Box::new(move || *pressed_.lock().unwrap() += 1);
+    assert_eq!(dispatcher.register(hotkey, false, callback), Ok(()));
+    let start = Instant::now();
+    let tap = |dispatcher: &mut hook::HookDispatcher, key, after: Duration| {
+      dispatcher.key_event_at(key, KeyState::Pressed, start + after);
+      dispatcher.key_event_at(key, KeyState::Released, start + after);
+    };
+
+    // Ctrl, Shift, then P, each pressed and released on its own
+    dispatcher.set_sticky_modifiers(true);
+    tap(&mut dispatcher, 1, Duration::ZERO);
+    tap(&mut dispatcher, 2, Duration::from_secs(1));
+    tap(&mut dispatcher, keys::P, Duration::from_secs(2));
+    // the latched modifiers only apply to the next key
+    tap(&mut dispatcher, keys::A, Duration::from_secs(2));
+    // a held combination is reported as usual
+    dispatcher.key_event_at(2, KeyState::Pressed, start);
+    tap(&mut dispatcher, keys::B, Duration::from_secs(3));
+    dispatcher.key_event_at(2, KeyState::Released, start);
+    // the latched Ctrl expires
+    tap(&mut dispatcher, 1, Duration::from_secs(4));
+    tap(
+      &mut dispatcher,
+      keys::C,
+      Duration::from_secs(4) + hook::STICKY_MODIFIER_TIMEOUT * 2,
+    );
+    // hotkeys are only matched with the modifiers held
+    tap(&mut dispatcher, 1, Duration::from_secs(20));
+    tap(&mut dispatcher, keys::H, Duration::from_secs(20));
+    assert_eq!(*pressed.lock().unwrap(), 0);
+    // without sticky keys a tapped modifier is forgotten
+    dispatcher.set_sticky_modifiers(false);
+    tap(&mut dispatcher, 1, Duration::from_secs(30));
+    tap(&mut dispatcher, keys::D, Duration::from_secs(30));
+    assert_eq!(
+      *learned.lock().unwrap(),
+      vec![
+        ListenerHotkey::new(modifiers::CONTROL | modifiers::SHIFT, keys::P),
+        ListenerHotkey::new(0, keys::A),
+        ListenerHotkey::new(modifiers::SHIFT, keys::B),
+        ListenerHotkey::new(0, keys::C),
+        hotkey,
+        ListenerHotkey::new(0, keys::D),
+      ]
+    );
+  }
 }
@@ -1,11 +1,15 @@
 use std::{
   collections::HashMap,
-  mem, ptr,
+  mem,
+  os::raw::c_int,
+  ptr,
   sync::{
+    atomic::{AtomicU8, Ordering},
     mpsc,
     mpsc::{Receiver, Sender},
     Arc, Mutex,
   },
+  thread,
 };
 use x11_dl::xlib;
 
@@ -52,7 +56,8 @@ pub mod keys {
   pub const MEDIA_STOP: u32 = keysym::XF86XK_AudioStop;
   pub const MEDIA_PLAY_PAUSE: u32 = keysym::XF86XK_AudioPlay;
   pub const LAUNCH_MAIL: u32 = keysym::XF86XK_Mail;
-  // F1-F12
+  pub const MENU: u32 = keysym::XK_Menu;
+  // F1-F24
   pub const F1: u32 = keysym::XK_F1;
   pub const F2: u32 = keysym::XK_F2;
   pub const F3: u32 = keysym::XK_F3;
@@ -65,6 +70,18 @@ pub mod keys {
   pub const F10: u32 = keysym::XK_F10;
   pub const F11: u32 = keysym::XK_F11;
   pub const F12: u32 = keysym::XK_F12;
+  pub const F13: u32 = keysym::XK_F13;
+  pub const F14: u32 = keysym::XK_F14;
+  pub const F15: u32 = keysym::XK_F15;
+  pub const F16: u32 = keysym::XK_F16;
+  pub const F17: u32 = keysym::XK_F17;
+  pub const F18: u32 = keysym::XK_F18;
+  pub const F19: u32 = keysym::XK_F19;
+  pub const F20: u32 = keysym::XK_F20;
+  pub const F21: u32 = keysym::XK_F21;
+  pub const F22: u32 = keysym::XK_F22;
+  pub const F23: u32 = keysym::XK_F23;
+  pub const F24: u32 = keysym::XK_F24;
   // Numpad
   pub const ADD: u32 = keysym::XK_KP_Add;
   pub const SUBTRACT: u32 = keysym::XK_KP_Subtract;
@@ -131,9 +148,9 @@ pub mod keys {
 }
 
 enum HotkeyMessage {
-  RegisterHotkey(ListenerId, u32, u32),
+  RegisterHotkey(u32, u32),
   RegisterHotkeyResult(Result<ListenerId, HotkeyError>),
-  UnregisterHotkey(ListenerId),
+  UnregisterHotkeys(Vec<ListenerId>),
   UnregisterHotkeyResult(Result<(), HotkeyError>),
   DropThread,
 }
@@ -146,96 +163,162 @@ pub struct Listener {
   receiver: Receiver<HotkeyMessage>,
 }
 
-type ListenerMap = Arc<Mutex<HashMap<ListenerId, (ListenerHotkey, Box<ListenerCallback>)>>>;
+// error code of the last failed request, reported asynchronously by the X server
+static GRAB_ERROR: AtomicU8 = AtomicU8::new(0);
+
+unsafe extern "C" fn record_grab_error(
+  _display: *mut xlib::Display,
+  event: *mut xlib::XErrorEvent,
+) -> c_int {
+  GRAB_ERROR.store((*event).error_code, Ordering::SeqCst);
+  0
+}
+
+// BadAccess means another client already grabbed the key
+pub(crate) fn grab_error(error_code: u8, hotkey: ListenerHotkey) -> HotkeyError {
+  match error_code {
+    xlib::BadAccess => HotkeyError::HotkeyTakenBySystem(hotkey),
+    error_code => HotkeyError::BackendApiError(error_code.into()),
+  }
+}
+
+// The grabbed hotkeys currently down by keycode, so key repeats aren't reported as presses.
+// The modifier state of a key release no longer has the modifiers released before the key,
+// so the release is matched by keycode to the hotkey that was pressed.
+#[derive(Default)]
+pub(crate) struct HeldKeys(HashMap<i32, ListenerId>);
+
+impl HeldKeys {
+  // Returns the hotkey pressed or released by a key event, if any.
+  pub(crate) fn update(
+    &mut self,
+    id: ListenerId,
+    state: KeyState,
+    is_registered: impl Fn(&ListenerId) -> bool,
+  ) -> Option<ListenerId> {
+    match state {
+      KeyState::Pressed => {
+        if self.0.contains_key(&id.0) || !is_registered(&id) {
+          return None;
+        }
+        self.0.insert(id.0, id);
+        Some(id)
+      }
+      KeyState::Released => self.0.remove(&id.0),
+    }
+  }
+}
+
+type StateCallback = dyn 'static + FnMut(KeyState) + Send;
+type ListenerMap = Arc<Mutex<HashMap<ListenerId, (ListenerHotkey, Box<StateCallback>)>>>;
 
 impl HotkeyListener for Listener {
-  fn new() -> Listener {
+  fn with_options(options: ListenerOptions) -> Listener {
     let hotkeys = ListenerMap::default();
 
     let hotkey_map = hotkeys.clone();
     let (method_sender, thread_receiver) = mpsc::channel();
     let (thread_sender, method_receiver) = mpsc::channel();
 
-    std::thread::spawn(move || {
-      let xlib = xlib::Xlib::open().unwrap();
-      unsafe {
-        let display = (xlib.XOpenDisplay)(ptr::null());
-        let root = (xlib.XDefaultRootWindow)(display);
-
-        // Only trigger key release at end of repeated keys
-        let mut supported_rtrn: i32 = mem::MaybeUninit::uninit().assume_init();
-        (xlib.XkbSetDetectableAutoRepeat)(display, 1, &mut supported_rtrn);
-
-        (xlib.XSelectInput)(display, root, xlib::KeyReleaseMask);
-        let mut event: xlib::XEvent = mem::MaybeUninit::uninit().assume_init();
-        loop {
-          if (xlib.XPending)(display) > 0 {
-            (xlib.XNextEvent)(display, &mut event);
-            if let xlib::KeyRelease = event.get_type() {
-              if let Some((_, handler)) = hotkey_map
-                .lock()
-                .unwrap()
-                .get_mut(&(event.key.keycode as i32, event.key.state))
-              {
-                handler();
+    let mut thread_builder = thread::Builder::new();
+    if let Some(thread_name) = options.thread_name {
+      thread_builder = thread_builder.name(thread_name);
+    }
+    let poll_interval = options.poll_interval;
+    thread_builder
+      .spawn(move || {
+        let xlib = xlib::Xlib::open().unwrap();
+        unsafe {
+          let display = (xlib.XOpenDisplay)(ptr::null());
+          let root = (xlib.XDefaultRootWindow)(display);
+
+          // Only trigger key release at end of repeated keys
+          let mut supported_rtrn: i32 = 0;
+          (xlib.XkbSetDetectableAutoRepeat)(display, 1, &mut supported_rtrn);
+
+          (xlib.XSelectInput)(display, root, xlib::KeyReleaseMask);
+          let mut event: xlib::XEvent = mem::MaybeUninit::uninit().assume_init();
+          let mut held = HeldKeys::default();
+          loop {
+            if (xlib.XPending)(display) > 0 {
+              (xlib.XNextEvent)(display, &mut event);
+              let state = match event.get_type() {
+                xlib::KeyPress => Some(KeyState::Pressed),
+                xlib::KeyRelease => Some(KeyState::Released),
+                _ => None,
+              };
+              if let Some(state) = state {
+                let mut hotkeys = hotkey_map.lock().unwrap();
+                let id = (event.key.keycode as i32, event.key.state);
+                if let Some(id) = held.update(id, state, |id| hotkeys.contains_key(id)) {
+                  // the hotkey may have been unregistered while held
+                  if let Some((_, handler)) = hotkeys.get_mut(&id) {
+                    handler(state);
+                  }
+                }
               }
             }
-          }
-          match thread_receiver.try_recv() {
-            Ok(HotkeyMessage::RegisterHotkey(_, modifiers, key)) => {
-              let keycode = (xlib.XKeysymToKeycode)(display, key.into()) as i32;
-
-              let result = (xlib.XGrabKey)(
-                display,
-                keycode,
-                modifiers,
-                root,
-                0,
-                xlib::GrabModeAsync,
-                xlib::GrabModeAsync,
-              );
-              if result == 0 {
-                if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Err(
-                  HotkeyError::BackendApiError(0),
-                ))) {
+            match thread_receiver.try_recv() {
+              Ok(HotkeyMessage::RegisterHotkey(modifiers, key)) => {
+                let keycode = (xlib.XKeysymToKeycode)(display, key.into()) as i32;
+
+                // XGrabKey fails asynchronously, e.g. with BadAccess when another client
+                // already grabbed the key, and the default error handler exits the process
+                GRAB_ERROR.store(0, Ordering::SeqCst);
+                let previous_handler = (xlib.XSetErrorHandler)(Some(record_grab_error));
+                let result = (xlib.XGrabKey)(
+                  display,
+                  keycode,
+                  modifiers,
+                  root,
+                  0,
+                  xlib::GrabModeAsync,
+                  xlib::GrabModeAsync,
+                );
+                (xlib.XSync)(display, xlib::False);
+                (xlib.XSetErrorHandler)(previous_handler);
+                let error_code = GRAB_ERROR.load(Ordering::SeqCst);
+                if result == 0 || error_code != 0 {
+                  if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Err(
+                    grab_error(error_code, ListenerHotkey::new(modifiers, key)),
+                  ))) {
+                    eprintln!("hotkey: thread_sender.send error {}", err);
+                  }
+                } else if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(
+                  Ok((keycode, modifiers)),
+                )) {
                   eprintln!("hotkey: thread_sender.send error {}", err);
                 }
-              } else if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Ok(
-                (keycode, modifiers),
-              ))) {
-                eprintln!("hotkey: thread_sender.send error {}", err);
               }
-            }
-            Ok(HotkeyMessage::UnregisterHotkey(id)) => {
-              let result = (xlib.XUngrabKey)(display, id.0, id.1, root);
-              if result == 0 {
-                if let Err(err) = thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(Err(
-                  HotkeyError::BackendApiError(0),
-                ))) {
+              Ok(HotkeyMessage::UnregisterHotkeys(ids)) => {
+                let mut result = Ok(());
+                for id in ids {
+                  if (xlib.XUngrabKey)(display, id.0, id.1, root) == 0 && result.is_ok() {
+                    result = Err(HotkeyError::BackendApiError(0));
+                  }
+                }
+                if let Err(err) = thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(result))
+                {
                   eprintln!("hotkey: thread_sender.send error {}", err);
                 }
-              } else if let Err(err) =
-                thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(Ok(())))
-              {
-                eprintln!("hotkey: thread_sender.send error {}", err);
               }
-            }
-            Ok(HotkeyMessage::DropThread) => {
-              (xlib.XCloseDisplay)(display);
-              return;
-            }
-            Err(err) => {
-              if let std::sync::mpsc::TryRecvError::Disconnected = err {
-                eprintln!("hotkey: try_recv error {}", err);
+              Ok(HotkeyMessage::DropThread) => {
+                (xlib.XCloseDisplay)(display);
+                return;
               }
-            }
-            _ => unreachable!("other message should not arrive"),
-          };
+              Err(err) => {
+                if let std::sync::mpsc::TryRecvError::Disconnected = err {
+                  eprintln!("hotkey: try_recv error {}", err);
+                }
+              }
+              _ => unreachable!("other message should not arrive"),
+            };
 
-          std::thread::sleep(std::time::Duration::from_millis(50));
+            thread::sleep(poll_interval);
+          }
         }
-      }
-    });
+      })
+      .expect("failed to spawn the hotkey listener thread");
 
     Listener {
       handlers: hotkeys,
@@ -244,9 +327,29 @@ impl HotkeyListener for Listener {
     }
   }
 
-  fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, handler: F) -> Result<(), HotkeyError>
+  fn register_hotkey<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    mut handler: F,
+  ) -> Result<(), HotkeyError>
   where
     F: 'static + FnMut() + Send,
+  {
+    // fires on release, once the key repeats are over
+    self.register_hotkey_with_state(hotkey, move |state| {
+      if state == KeyState::Released {
+        handler();
+      }
+    })
+  }
+
+  fn register_hotkey_with_state<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    handler: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(KeyState) + Send,
   {
     for (key, _) in self.handlers.lock().unwrap().values() {
       if *key == hotkey {
@@ -255,11 +358,7 @@ impl HotkeyListener for Listener {
     }
     self
       .sender
-      .send(HotkeyMessage::RegisterHotkey(
-        (0, 0),
-        hotkey.modifiers,
-        hotkey.key,
-      ))
+      .send(HotkeyMessage::RegisterHotkey(hotkey.modifiers, hotkey.key))
       .map_err(|_| HotkeyError::ChannelError())?;
     match self.receiver.recv() {
       Ok(HotkeyMessage::RegisterHotkeyResult(Ok(id))) => {
@@ -277,36 +376,33 @@ impl HotkeyListener for Listener {
   }
 
   fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
-    let mut found_id = (-1, 0);
-    for (id, (key, _)) in self.handlers.lock().unwrap().iter() {
-      if *key == hotkey {
-        found_id = *id;
-        break;
-      }
-    }
-    if found_id == (-1, 0) {
-      return Err(HotkeyError::HotkeyNotRegistered(hotkey));
+    self.unregister_hotkeys(&[hotkey])
+  }
+
+  // ungrabs every hotkey with a single round trip to the listener thread
+  fn unregister_hotkeys(&mut self, hotkeys: &[ListenerHotkey]) -> Result<(), HotkeyError> {
+    let (ids, result) = find_ids(&self.handlers.lock().unwrap(), hotkeys, |(key, _)| *key);
+    if ids.is_empty() {
+      return result;
     }
     self
       .sender
-      .send(HotkeyMessage::UnregisterHotkey(found_id))
+      .send(HotkeyMessage::UnregisterHotkeys(ids.clone()))
       .map_err(|_| HotkeyError::ChannelError())?;
-    if self.handlers.lock().unwrap().remove(&found_id).is_none() {
-      panic!("hotkey should never be none")
-    };
-    match self.receiver.recv() {
-      Ok(HotkeyMessage::UnregisterHotkeyResult(Ok(_))) => Ok(()),
-      Ok(HotkeyMessage::UnregisterHotkeyResult(Err(err))) => Err(err),
+    let mut handlers = self.handlers.lock().unwrap();
+    for id in &ids {
+      handlers.remove(id);
+    }
+    drop(handlers);
+    let released = match self.receiver.recv() {
+      Ok(HotkeyMessage::UnregisterHotkeyResult(result)) => result,
       Err(_) => Err(HotkeyError::ChannelError()),
       _ => Err(HotkeyError::Unknown),
-    }
+    };
+    result.and(released)
   }
   fn registered_hotkeys(&self) -> Vec<ListenerHotkey> {
-    let mut result = Vec::new();
-    for v in self.handlers.lock().unwrap().values() {
-      result.push(v.0);
-    }
-    result
+    sorted_by_id(&self.handlers.lock().unwrap(), |(key, _)| *key)
   }
 }
 
@@ -317,3 +413,115 @@ impl Drop for Listener {
     }
   }
 }
+
+/// Synthesizes a press and release of the hotkey through the XTest extension.
+#[cfg(feature = "simulate")]
+pub fn simulate_hotkey(hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
+  use x11_dl::{keysym, xtest};
+
+  let xlib = xlib::Xlib::open().map_err(|_| HotkeyError::BackendApiError(0))?;
+  // x11-dl names the XTest bindings after the wrong extension
+  let xtest = xtest::Xf86vmode::open().map_err(|_| HotkeyError::BackendApiError(0))?;
+  let modifier_keysyms = [
+    (xlib::ShiftMask, keysym::XK_Shift_L),
+    (xlib::ControlMask, keysym::XK_Control_L),
+    (xlib::Mod1Mask, keysym::XK_Alt_L),
+    (xlib::Mod4Mask, keysym::XK_Super_L),
+  ];
+  let mut keysyms: Vec<u32> = modifier_keysyms
+    .iter()
+    .filter(|(mask, _)| hotkey.modifiers & mask != 0)
+    .map(|(_, keysym)| *keysym)
+    .collect();
+  keysyms.push(hotkey.key);
+
+  unsafe {
+    let display = (xlib.XOpenDisplay)(ptr::null());
+    if display.is_null() {
+      return Err(HotkeyError::BackendApiError(0));
+    }
+    let keycodes: Vec<u32> = keysyms
+      .iter()
+      .map(|keysym| (xlib.XKeysymToKeycode)(display, (*keysym).into()) as u32)
+      .collect();
+    for keycode in &keycodes {
+      (xtest.XTestFakeKeyEvent)(display, *keycode, xlib::True, xlib::CurrentTime);
+    }
+    for keycode in keycodes.iter().rev() {
+      (xtest.XTestFakeKeyEvent)(display, *keycode, xlib::False, xlib::CurrentTime);
+    }
+    (xlib.XFlush)(display);
+    (xlib.XCloseDisplay)(display);
+  }
+  Ok(())
+}
+
+/// Returns whether the hotkey's key and all its modifiers are physically held right now.
+/// Either the left or right variant of a modifier counts. Returns `false` if there's
+/// no display.
+pub fn is_hotkey_held(hotkey: ListenerHotkey) -> bool {
+  use x11_dl::keysym;
+
+  let xlib = match xlib::Xlib::open() {
+    Ok(xlib) => xlib,
+    Err(_) => return false,
+  };
+  let modifier_keysyms = [
+    (xlib::ShiftMask, [keysym::XK_Shift_L, keysym::XK_Shift_R]),
+    (
+      xlib::ControlMask,
+      [keysym::XK_Control_L, keysym::XK_Control_R],
+    ),
+    (xlib::Mod1Mask, [keysym::XK_Alt_L, keysym::XK_Alt_R]),
+    (xlib::Mod4Mask, [keysym::XK_Super_L, keysym::XK_Super_R]),
+  ];
+  unsafe {
+    let display = (xlib.XOpenDisplay)(ptr::null());
+    if display.is_null() {
+      return false;
+    }
+    let mut keymap = [0 as std::os::raw::c_char; 32];
+    (xlib.XQueryKeymap)(display, keymap.as_mut_ptr());
+    let is_down = |keysym: u32| {
+      let keycode = (xlib.XKeysymToKeycode)(display, keysym.into()) as usize;
+      keycode != 0 && keymap[keycode / 8] as u8 & (1 << (keycode % 8)) != 0
+    };
+    let held = is_down(hotkey.key)
+      && modifier_keysyms
+        .iter()
+        .filter(|(mask, _)| hotkey.modifiers & mask != 0)
+        .all(|(_, keysyms)| keysyms.iter().any(|keysym| is_down(*keysym)));
+    (xlib.XCloseDisplay)(display);
+    held
+  }
+}
+
+/// Returns whether the window is the foreground window. Only Windows supports this,
+/// Linux returns [`HotkeyError::Unsupported`].
+pub fn is_foreground_window(_window: isize) -> Result<bool, HotkeyError> {
+  Err(HotkeyError::Unsupported)
+}
+
+/// Returns whether the key can be registered. Every key has an X keysym, though the
+/// active keyboard layout may not produce it, see [`physical_key`].
+pub fn is_supported_key(_key: u32) -> bool {
+  true
+}
+
+/// Returns the keycode of the physical key producing the keysym with the active keyboard
+/// layout, or `None` if no key produces it or there's no display.
+pub fn physical_key(key: u32) -> Option<u32> {
+  let xlib = xlib::Xlib::open().ok()?;
+  unsafe {
+    let display = (xlib.XOpenDisplay)(ptr::null());
+    if display.is_null() {
+      return None;
+    }
+    let keycode = (xlib.XKeysymToKeycode)(display, key.into());
+    (xlib.XCloseDisplay)(display);
+    match keycode {
+      0 => None,
+      keycode => Some(keycode.into()),
+    }
+  }
+}
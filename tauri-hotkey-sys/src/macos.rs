@@ -17,6 +17,8 @@ pub mod modifiers {
   pub const CONTROL: u32 = 4096;
   pub const SHIFT: u32 = 512;
   pub const SUPER: u32 = 256;
+  // kEventKeyModifierFnMask, the Globe/Fn key
+  pub const FUNCTION: u32 = 1 << 17;
 }
 
 pub mod keys {
@@ -34,23 +36,29 @@ pub mod keys {
   pub const ARROW_RIGHT: u32 = 0x7C;
   pub const ARROW_UP: u32 = 0x7E;
   pub const ARROW_DOWN: u32 = 0x7D;
-  pub const PRINT_SCREEN: u32 = 0xDEAD;
+  // Mac keyboards have no Print Screen key, screenshots are taken with Cmd+Shift+3
+  pub const PRINT_SCREEN: u32 = UNSUPPORTED_KEY | 8;
   pub const DELETE: u32 = 0x75;
   pub const SCROLL_LOCK: u32 = 0x6B; // F14
   pub const HELP: u32 = 0x72;
-  // TODO
-  // pub const NUMLOCK: u32 = 0;
+  // Mac keyboards have no Num Lock key
+  pub const NUMLOCK: u32 = UNSUPPORTED_KEY | 1;
   // Media
   pub const VOLUME_MUTE: u32 = 0x4A;
   pub const VOLUME_DOWN: u32 = 0x49;
   pub const VOLUME_UP: u32 = 0x48;
-  // TODO
-  /* pub const MEDIA_NEXT: u32 = 0;
-  pub const MEDIA_PREV: u32 = 0;
-  pub const MEDIA_STOP: u32 = 0;
-  pub const MEDIA_PLAY_PAUSE: u32 = 0;
-  pub const LAUNCH_MAIL: u32 = 0;*/
-  // F1-F12
+  pub const MEDIA_NEXT: u32 = MEDIA_KEY | 17; // NX_KEYTYPE_NEXT
+  pub const MEDIA_PREV: u32 = MEDIA_KEY | 18; // NX_KEYTYPE_PREVIOUS
+  pub const MEDIA_STOP: u32 = UNSUPPORTED_KEY | 2;
+  pub const MEDIA_PLAY_PAUSE: u32 = MEDIA_KEY | 16; // NX_KEYTYPE_PLAY
+  pub const LAUNCH_MAIL: u32 = UNSUPPORTED_KEY | 3;
+
+  // Media keys aren't key codes but the NX_KEYTYPE_* of NSSystemDefined events, received
+  // through an event tap instead of `RegisterEventHotKey`
+  pub(crate) const MEDIA_KEY: u32 = 1 << 16;
+  // Keys without a Mac equivalent, registering them fails with `HotkeyError::UnsupportedKey`
+  pub(crate) const UNSUPPORTED_KEY: u32 = 1 << 17;
+  // F1-F24
   pub const F1: u32 = 122;
   pub const F2: u32 = 120;
   pub const F3: u32 = 99;
@@ -63,6 +71,19 @@ pub mod keys {
   pub const F10: u32 = 109;
   pub const F11: u32 = 103;
   pub const F12: u32 = 111;
+  pub const F13: u32 = 0x69;
+  pub const F14: u32 = 0x6B;
+  pub const F15: u32 = 0x71;
+  pub const F16: u32 = 0x6A;
+  pub const F17: u32 = 0x40;
+  pub const F18: u32 = 0x4F;
+  pub const F19: u32 = 0x50;
+  pub const F20: u32 = 0x5A;
+  // macOS has no key codes past F20
+  pub const F21: u32 = UNSUPPORTED_KEY | 4;
+  pub const F22: u32 = UNSUPPORTED_KEY | 5;
+  pub const F23: u32 = UNSUPPORTED_KEY | 6;
+  pub const F24: u32 = UNSUPPORTED_KEY | 7;
   // Numpad
   pub const DECIMAL: u32 = 0x41;
   pub const MULTIPLY: u32 = 0x43;
@@ -130,34 +151,42 @@ pub mod keys {
   pub const CLOSE_BRACKET: u32 = 30;
 }
 
-type KeyCallback = unsafe extern "C" fn(c_int, *mut c_void);
+type KeyCallback = unsafe extern "C" fn(c_int, c_int, *mut c_void) -> c_int;
+type MediaKeyCallback = unsafe extern "C" fn(c_int, c_int, *mut c_void) -> c_int;
 
 #[link(name = "carbon_hotkey_binding.a", kind = "static")]
 extern "C" {
   fn install_event_handler(cb: KeyCallback, data: *mut c_void) -> *mut c_void;
   fn uninstall_event_handler(handler_ref: *mut c_void) -> c_int;
-  fn register_hotkey(id: i32, modifier: i32, key: i32) -> *mut c_void;
+  fn register_hotkey(id: i32, modifier: i32, key: i32, status: *mut c_int) -> *mut c_void;
   fn unregister_hotkey(hotkey_ref: *mut c_void) -> c_int;
+  fn install_media_key_tap(cb: MediaKeyCallback, data: *mut c_void) -> *mut c_void;
+  fn uninstall_media_key_tap(tap_ref: *mut c_void);
 }
 
-unsafe extern "C" fn trampoline<F>(result: c_int, user_data: *mut c_void)
+unsafe extern "C" fn trampoline<F>(id: c_int, pressed: c_int, user_data: *mut c_void) -> c_int
 where
-  F: FnMut(c_int) + 'static,
+  F: FnMut(c_int, KeyState) -> bool + 'static,
 {
   let user_data = &mut *(user_data as *mut F);
-  user_data(result);
+  let state = if pressed != 0 {
+    KeyState::Pressed
+  } else {
+    KeyState::Released
+  };
+  user_data(id, state) as c_int
 }
 
 fn get_trampoline<F>() -> KeyCallback
 where
-  F: FnMut(c_int) + 'static,
+  F: FnMut(c_int, KeyState) -> bool + 'static,
 {
   trampoline::<F>
 }
 
 fn register_event_handler_callback<F>(handler: *mut F) -> *mut c_void
 where
-  F: FnMut(i32) + 'static + Sync + Send,
+  F: FnMut(i32, KeyState) -> bool + 'static + Sync + Send,
 {
   unsafe {
     let cb = get_trampoline::<F>();
@@ -166,22 +195,93 @@ where
   }
 }
 
+unsafe extern "C" fn media_key_trampoline<F>(
+  key_type: c_int,
+  key_down: c_int,
+  user_data: *mut c_void,
+) -> c_int
+where
+  F: FnMut(c_int, bool) -> bool + 'static,
+{
+  let user_data = &mut *(user_data as *mut F);
+  user_data(key_type, key_down != 0) as c_int
+}
+
+fn install_media_key_callback<F>(handler: *mut F) -> *mut c_void
+where
+  F: FnMut(c_int, bool) -> bool + 'static + Send,
+{
+  unsafe { install_media_key_tap(media_key_trampoline::<F>, handler as *mut c_void) }
+}
+
+// OSStatus returned by `RegisterEventHotKey` when the combination is already
+// registered, by another application or by this process outside of the listener.
+const EVENT_HOT_KEY_EXISTS_ERR: c_int = -9878;
+
+// the listener's own hotkeys are rejected before reaching Carbon, so any other status,
+// e.g. eventHotKeyInvalidErr (-9879) or paramErr (-50), is reported as is
+pub(crate) fn register_error(status: c_int, hotkey: ListenerHotkey) -> HotkeyError {
+  match status {
+    EVENT_HOT_KEY_EXISTS_ERR => HotkeyError::HotkeyTakenBySystem(hotkey),
+    status => HotkeyError::OsStatus(status),
+  }
+}
+
 type ListenerId = i32;
 
+// Every listener installs its own Carbon event handler, and each handler sees the
+// hotkey events of all listeners, so ids are unique process-wide to avoid cross-talk.
+struct UsedIds {
+  last: ListenerId,
+  ids: Vec<ListenerId>,
+}
+
+static USED_IDS: Mutex<UsedIds> = Mutex::new(UsedIds {
+  last: 0,
+  ids: Vec::new(),
+});
+
+// claims the first free id after the last one handed out by any listener, see `next_free_id`
+fn claim_id(id_base: ListenerId) -> Result<ListenerId, HotkeyError> {
+  let mut used = USED_IDS.lock().unwrap();
+  let id = next_free_id(id_base, used.last, |id| !used.ids.contains(&id))?;
+  used.last = id;
+  used.ids.push(id);
+  Ok(id)
+}
+
+// called once the Carbon hotkey of the id is released
+fn release_id(id: ListenerId) {
+  USED_IDS.lock().unwrap().ids.retain(|used| *used != id);
+}
+
 #[derive(Debug)]
 enum HotkeyMessage {
   RegisterHotkey(ListenerId, u32, u32),
   RegisterHotkeyResult(Result<(), HotkeyError>),
-  UnregisterHotkey(ListenerId),
+  UnregisterHotkeys(Vec<ListenerId>),
   UnregisterHotkeyResult(Result<(), HotkeyError>),
   DropThread,
 }
 
-struct CarbonRef(pub *mut c_void);
+/// An owned `EventHotKeyRef`.
+///
+/// It's only registered and released on the listener thread, and releasing it consumes
+/// the wrapper so the ref can't be used after it's freed.
+struct CarbonRef(*mut c_void);
 impl CarbonRef {
-  pub fn new(start: *mut c_void) -> Self {
+  fn new(start: *mut c_void) -> Self {
     CarbonRef(start)
   }
+
+  /// Releases the Carbon hotkey, returning the `OSStatus` of `UnregisterEventHotKey`.
+  fn unregister(self) -> c_int {
+    if self.0.is_null() {
+      // the registration is still in flight or failed, there is nothing to release
+      return 0;
+    }
+    unsafe { unregister_hotkey(self.0) }
+  }
 }
 unsafe impl Sync for CarbonRef {}
 unsafe impl Send for CarbonRef {}
@@ -190,115 +290,208 @@ type ListenerMap =
   Arc<Mutex<HashMap<ListenerId, (ListenerHotkey, Box<ListenerCallback>, CarbonRef)>>>;
 
 pub struct Listener {
-  last_id: ListenerId,
   handlers: ListenerMap,
   sender: Sender<HotkeyMessage>,
   receiver: Receiver<HotkeyMessage>,
+  id_base: ListenerId,
+}
+
+impl Listener {
+  #[cfg(test)]
+  pub(crate) fn registered_ids(&self) -> Vec<ListenerId> {
+    let mut ids: Vec<_> = self.handlers.lock().unwrap().keys().copied().collect();
+    ids.sort_unstable();
+    ids
+  }
 }
 
 impl HotkeyListener for Listener {
-  fn new() -> Listener {
+  fn with_options(options: ListenerOptions) -> Listener {
     let hotkeys = ListenerMap::default();
 
     let hotkey_map = hotkeys.clone();
     let (method_sender, thread_receiver) = mpsc::channel();
     let (thread_sender, method_receiver) = mpsc::channel();
 
-    thread::spawn(move || {
-      let hotkey_map_clone = hotkey_map.clone();
-      let callback = Box::new(move |id| {
-        if let Some((_, handler, _)) = hotkey_map_clone.lock().unwrap().get_mut(&id) {
-          handler();
-        }
-      });
+    let mut thread_builder = thread::Builder::new();
+    if let Some(thread_name) = options.thread_name {
+      thread_builder = thread_builder.name(thread_name);
+    }
+    thread_builder
+      .spawn(move || {
+        let hotkey_map_clone = hotkey_map.clone();
+        let callback = Box::new(move |id, state| {
+          if let Some((_, handler, _)) = hotkey_map_clone.lock().unwrap().get_mut(&id) {
+            handler(state);
+            true
+          } else {
+            false
+          }
+        });
 
-      let saved_callback = Box::into_raw(callback);
-      let event_handler_ref = register_event_handler_callback(saved_callback);
+        let saved_callback = Box::into_raw(callback);
+        let event_handler_ref = register_event_handler_callback(saved_callback);
 
-      if event_handler_ref.is_null() {
-        eprintln!("register_event_handler_callback failed!");
-        let _ = unsafe { Box::from_raw(saved_callback) };
-        return;
-      }
+        if event_handler_ref.is_null() {
+          eprintln!("register_event_handler_callback failed!");
+          let _ = unsafe { Box::from_raw(saved_callback) };
+          return;
+        }
+
+        let media_key_map = hotkey_map.clone();
+        let media_key_callback = Box::into_raw(Box::new(move |key_type: c_int, key_down| {
+          let key = keys::MEDIA_KEY | key_type as u32;
+          let mut hotkeys = media_key_map.lock().unwrap();
+          match hotkeys
+            .values_mut()
+            .find(|(hotkey, _, _)| hotkey.key == key)
+          {
+            Some((_, handler, _)) => {
+              handler(if key_down {
+                KeyState::Pressed
+              } else {
+                KeyState::Released
+              });
+              true
+            }
+            None => false,
+          }
+        }));
+        // installed with the first media key
+        let mut media_key_tap = std::ptr::null_mut();
 
-      loop {
-        match thread_receiver.recv() {
-          Ok(HotkeyMessage::RegisterHotkey(id, modifiers, key)) => unsafe {
-            let handler_ref = register_hotkey(id, modifiers as i32, key as i32);
-            if handler_ref.is_null() {
-              if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Err(
-                HotkeyError::BackendApiError(0),
-              ))) {
+        loop {
+          match thread_receiver.recv() {
+            Ok(HotkeyMessage::RegisterHotkey(_, _, key)) if key & keys::MEDIA_KEY != 0 => {
+              if media_key_tap.is_null() {
+                media_key_tap = install_media_key_callback(media_key_callback);
+              }
+              let result = if media_key_tap.is_null() {
+                Err(HotkeyError::BackendApiError(0))
+              } else {
+                Ok(())
+              };
+              if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(result)) {
                 eprintln!("hotkey: thread_sender.send error {}", err);
               }
-              continue;
-            }
-            if let Some((_, _, handler)) = hotkey_map.lock().unwrap().get_mut(&id) {
-              *handler = CarbonRef::new(handler_ref);
-            }
-            if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Ok(()))) {
-              eprintln!("hotkey: thread_sender.send error {}", err);
             }
-          },
-          Ok(HotkeyMessage::UnregisterHotkey(id)) => unsafe {
-            if let Some((_, _, handler_ref)) = hotkey_map.lock().unwrap().remove(&id) {
-              let result = unregister_hotkey(handler_ref.0);
-              if result != 0 {
-                if let Err(err) = thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(Err(
-                  HotkeyError::BackendApiError(result as usize),
-                ))) {
+            Ok(HotkeyMessage::RegisterHotkey(id, modifiers, key)) => unsafe {
+              let mut status = 0;
+              let handler_ref = register_hotkey(id, modifiers as i32, key as i32, &mut status);
+              if handler_ref.is_null() {
+                let err = register_error(status, ListenerHotkey::new(modifiers, key));
+                if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Err(err)))
+                {
                   eprintln!("hotkey: thread_sender.send error {}", err);
                 }
-              } else if let Err(err) =
-                thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(Ok(())))
-              {
+                continue;
+              }
+              if let Some((_, _, handler)) = hotkey_map.lock().unwrap().get_mut(&id) {
+                *handler = CarbonRef::new(handler_ref);
+              }
+              if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Ok(()))) {
+                eprintln!("hotkey: thread_sender.send error {}", err);
+              }
+            },
+            Ok(HotkeyMessage::UnregisterHotkeys(ids)) => {
+              // the event handler looks ids up under the same lock, so keeping the map locked
+              // until the Carbon hotkeys are released guarantees it never sees a released hotkey
+              let mut hotkeys = hotkey_map.lock().unwrap();
+              let mut result = Ok(());
+              for id in ids {
+                if let Some((_, _, handler_ref)) = hotkeys.remove(&id) {
+                  let status = handler_ref.unregister();
+                  release_id(id);
+                  if status != 0 && result.is_ok() {
+                    result = Err(HotkeyError::OsStatus(status));
+                  }
+                } else {
+                  panic!("hotkey should be never be none");
+                }
+              }
+              drop(hotkeys);
+              if let Err(err) = thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(result)) {
                 eprintln!("hotkey: thread_sender.send error {}", err);
               }
-            } else {
-              panic!("hotkey should be never be none");
             }
-          },
-          Ok(HotkeyMessage::DropThread) => unsafe {
-            for (_, _, handler_ref) in hotkey_map.lock().unwrap().values() {
-              let result = unregister_hotkey(handler_ref.0);
+            Ok(HotkeyMessage::DropThread) => unsafe {
+              for (id, (_, _, handler_ref)) in hotkey_map.lock().unwrap().drain() {
+                let result = handler_ref.unregister();
+                release_id(id);
+                if result != 0 {
+                  eprintln!("drop: unregister_hotkey failed: {}", result);
+                }
+              }
+              let result = uninstall_event_handler(event_handler_ref);
               if result != 0 {
-                eprintln!("drop: unregister_hotkey failed: {}", result);
+                eprintln!("drop: uninstall_event_handler failed: {}", result);
               }
+              if !media_key_tap.is_null() {
+                uninstall_media_key_tap(media_key_tap);
+              }
+              let _ = Box::from_raw(saved_callback);
+              let _ = Box::from_raw(media_key_callback);
+              break;
+            },
+            Err(err) => {
+              eprintln!("hotkey: try_recv error {}", err);
             }
-            let result = uninstall_event_handler(event_handler_ref);
-            if result != 0 {
-              eprintln!("drop: uninstall_event_handler failed: {}", result);
-            }
-            let _ = Box::from_raw(saved_callback);
-            break;
-          },
-          Err(err) => {
-            eprintln!("hotkey: try_recv error {}", err);
+            _ => unreachable!("other message should not arrive"),
           }
-          _ => unreachable!("other message should not arrive"),
         }
-      }
-    });
+      })
+      .expect("failed to spawn the hotkey listener thread");
 
     Listener {
       sender: method_sender,
       receiver: method_receiver,
       handlers: hotkeys,
-      last_id: 0,
+      id_base: options.id_base,
     }
   }
 
-  fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, handler: F) -> Result<(), HotkeyError>
+  fn register_hotkey<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    mut handler: F,
+  ) -> Result<(), HotkeyError>
   where
     F: 'static + FnMut() + Send,
   {
+    self.register_hotkey_with_state(hotkey, move |state| {
+      if state == KeyState::Pressed {
+        handler();
+      }
+    })
+  }
+
+  // Carbon reports `kEventHotKeyReleased` when the key is released, releasing a modifier
+  // first doesn't count
+  fn register_hotkey_with_state<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    handler: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(KeyState) + Send,
+  {
+    // `RegisterEventHotKey` ignores the Fn modifier, the hotkey would fire without it
+    if hotkey.modifiers & modifiers::FUNCTION != 0 {
+      return Err(HotkeyError::UnsupportedModifier(modifiers::FUNCTION));
+    }
+    if hotkey.key & keys::UNSUPPORTED_KEY != 0 {
+      return Err(HotkeyError::UnsupportedKey(hotkey.key));
+    }
+    // the media key events don't carry the modifiers
+    if hotkey.key & keys::MEDIA_KEY != 0 && hotkey.modifiers != 0 {
+      return Err(HotkeyError::UnsupportedModifier(hotkey.modifiers));
+    }
     for (key, _, _) in self.handlers.lock().unwrap().values() {
       if *key == hotkey {
         return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
       }
     }
-    self.last_id += 1;
-    let id = self.last_id;
+    let id = claim_id(self.id_base)?;
     self.handlers.lock().unwrap().insert(
       id,
       (
@@ -324,39 +517,36 @@ impl HotkeyListener for Listener {
     };
     if result.is_err() {
       self.handlers.lock().unwrap().remove(&id);
+      release_id(id);
     }
     result
   }
 
   fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
-    let mut found_id = -1;
-    for (id, (key, _, _)) in self.handlers.lock().unwrap().iter() {
-      if *key == hotkey {
-        found_id = *id;
-        break;
-      }
-    }
-    if found_id == -1 {
-      return Err(HotkeyError::HotkeyNotRegistered(hotkey));
+    self.unregister_hotkeys(&[hotkey])
+  }
+
+  // releases every hotkey with a single round trip to the listener thread, which also
+  // removes them from the map
+  fn unregister_hotkeys(&mut self, hotkeys: &[ListenerHotkey]) -> Result<(), HotkeyError> {
+    let (ids, result) = find_ids(&self.handlers.lock().unwrap(), hotkeys, |(key, _, _)| *key);
+    if ids.is_empty() {
+      return result;
     }
     self
       .sender
-      .send(HotkeyMessage::UnregisterHotkey(found_id))
+      .send(HotkeyMessage::UnregisterHotkeys(ids))
       .map_err(|_| HotkeyError::ChannelError())?;
-    match self.receiver.recv() {
-      Ok(HotkeyMessage::UnregisterHotkeyResult(Ok(_))) => Ok(()),
-      Ok(HotkeyMessage::UnregisterHotkeyResult(Err(err))) => Err(err),
+    let released = match self.receiver.recv() {
+      Ok(HotkeyMessage::UnregisterHotkeyResult(result)) => result,
       Err(_) => Err(HotkeyError::ChannelError()),
       _ => Err(HotkeyError::Unknown),
-    }
+    };
+    result.and(released)
   }
 
   fn registered_hotkeys(&self) -> Vec<ListenerHotkey> {
-    let mut result = Vec::new();
-    for v in self.handlers.lock().unwrap().values() {
-      result.push(v.0);
-    }
-    result
+    sorted_by_id(&self.handlers.lock().unwrap(), |(key, _, _)| *key)
   }
 }
 
@@ -367,3 +557,96 @@ impl Drop for Listener {
     }
   }
 }
+
+#[cfg(feature = "simulate")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+  fn CGEventCreateKeyboardEvent(source: *const c_void, keycode: u16, key_down: bool)
+    -> *mut c_void;
+  fn CGEventSetFlags(event: *mut c_void, flags: u64);
+  fn CGEventPost(tap: u32, event: *mut c_void);
+}
+
+#[cfg(feature = "simulate")]
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+  fn CFRelease(cf: *const c_void);
+}
+
+/// Synthesizes a press and release of the hotkey with `CGEventPost`.
+#[cfg(feature = "simulate")]
+pub fn simulate_hotkey(hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
+  if hotkey.key & (keys::MEDIA_KEY | keys::UNSUPPORTED_KEY) != 0 {
+    return Err(HotkeyError::UnsupportedKey(hotkey.key));
+  }
+  // Carbon modifiers to kCGEventFlagMask* flags
+  let modifier_flags = [
+    (modifiers::SUPER, 0x10_0000),
+    (modifiers::SHIFT, 0x2_0000),
+    (modifiers::ALT, 0x8_0000),
+    (modifiers::CONTROL, 0x4_0000),
+  ];
+  let flags = modifier_flags
+    .iter()
+    .filter(|(modifier, _)| hotkey.modifiers & modifier != 0)
+    .fold(0, |acc, (_, flag)| acc | flag);
+  for key_down in &[true, false] {
+    unsafe {
+      let event = CGEventCreateKeyboardEvent(std::ptr::null(), hotkey.key as u16, *key_down);
+      if event.is_null() {
+        return Err(HotkeyError::BackendApiError(0));
+      }
+      CGEventSetFlags(event, flags);
+      // kCGHIDEventTap
+      CGEventPost(0, event);
+      CFRelease(event);
+    }
+  }
+  Ok(())
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+  fn CGEventSourceKeyState(state: i32, key: u16) -> bool;
+}
+
+/// Returns whether the hotkey's key and all its modifiers are physically held right now.
+/// Either the left or right variant of a modifier counts.
+pub fn is_hotkey_held(hotkey: ListenerHotkey) -> bool {
+  // Carbon modifiers to the key codes of their left and right keys
+  let modifier_keys = [
+    (modifiers::SUPER, &[0x37, 0x36][..]),
+    (modifiers::SHIFT, &[0x38, 0x3C][..]),
+    (modifiers::ALT, &[0x3A, 0x3D][..]),
+    (modifiers::CONTROL, &[0x3B, 0x3E][..]),
+    (modifiers::FUNCTION, &[0x3F][..]),
+  ];
+  if hotkey.key & (keys::MEDIA_KEY | keys::UNSUPPORTED_KEY) != 0 {
+    return false;
+  }
+  // kCGEventSourceStateCombinedSessionState
+  let is_down = |key: u16| unsafe { CGEventSourceKeyState(0, key) };
+  is_down(hotkey.key as u16)
+    && modifier_keys
+      .iter()
+      .filter(|(modifier, _)| hotkey.modifiers & modifier != 0)
+      .all(|(_, keys)| keys.iter().any(|key| is_down(*key)))
+}
+
+/// Returns whether the window is the foreground window. Only Windows supports this,
+/// macOS returns [`HotkeyError::Unsupported`].
+pub fn is_foreground_window(_window: isize) -> Result<bool, HotkeyError> {
+  Err(HotkeyError::Unsupported)
+}
+
+/// Returns whether the key can be registered, which isn't the case of the keys without a
+/// Mac equivalent such as `PRINT_SCREEN` or `NUMLOCK`.
+pub fn is_supported_key(key: u32) -> bool {
+  key & keys::UNSUPPORTED_KEY == 0
+}
+
+/// Returns the physical key of the key code. Carbon key codes are already layout
+/// independent, so this is the key code itself.
+pub fn physical_key(key: u32) -> Option<u32> {
+  Some(key)
+}
@@ -0,0 +1,117 @@
+//! In-memory listener replacing the platform one when the `mock` feature is enabled, so
+//! hotkey logic can be tested on headless CI runners. It doesn't start a thread or grab
+//! any key, hotkeys only fire through [`Listener::trigger`].
+
+use super::traits::*;
+
+type StateCallback = Box<dyn 'static + FnMut(KeyState) + Send>;
+type LearnCallback = Box<dyn 'static + FnMut(ListenerHotkey) + Send>;
+
+pub struct Listener {
+  options: ListenerOptions,
+  handlers: Vec<(ListenerHotkey, StateCallback)>,
+  learner: Option<LearnCallback>,
+}
+
+impl Listener {
+  /// Returns the options the listener was built with.
+  pub fn options(&self) -> &ListenerOptions {
+    &self.options
+  }
+
+  /// Runs the callback of a registered hotkey as if it was pressed and released, returning
+  /// whether the hotkey is registered. The hotkey is reported while learning, see
+  /// [`HotkeyListener::start_learning`], even if it isn't registered.
+  pub fn trigger(&mut self, hotkey: ListenerHotkey) -> bool {
+    if let Some(learner) = &mut self.learner {
+      learner(hotkey);
+    }
+    match self.handlers.iter_mut().find(|(h, _)| *h == hotkey) {
+      Some((_, callback)) => {
+        callback(KeyState::Pressed);
+        callback(KeyState::Released);
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+impl HotkeyListener for Listener {
+  fn with_options(options: ListenerOptions) -> Listener {
+    Listener {
+      options,
+      handlers: Vec::new(),
+      learner: None,
+    }
+  }
+
+  fn register_hotkey<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    mut callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey_with_state(hotkey, move |state| {
+      if state == KeyState::Pressed {
+        callback();
+      }
+    })
+  }
+
+  fn register_hotkey_with_state<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(KeyState) + Send,
+  {
+    if self.handlers.iter().any(|(h, _)| *h == hotkey) {
+      return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
+    }
+    self.handlers.push((hotkey, Box::new(callback)));
+    Ok(())
+  }
+
+  // there's no key event to suppress, so it's a normal hotkey
+  fn register_hook_hotkey<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    _suppress: bool,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey(hotkey, callback)
+  }
+
+  fn start_learning<F>(&mut self, callback: F) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(ListenerHotkey) + Send,
+  {
+    self.learner = Some(Box::new(callback));
+    Ok(())
+  }
+
+  fn stop_learning(&mut self) -> Result<(), HotkeyError> {
+    self.learner = None;
+    Ok(())
+  }
+
+  fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
+    let count = self.handlers.len();
+    self.handlers.retain(|(h, _)| *h != hotkey);
+    if self.handlers.len() == count {
+      return Err(HotkeyError::HotkeyNotRegistered(hotkey));
+    }
+    Ok(())
+  }
+
+  fn registered_hotkeys(&self) -> Vec<ListenerHotkey> {
+    self.handlers.iter().map(|(h, _)| *h).collect()
+  }
+}
@@ -1,6 +1,30 @@
+use std::{
+  collections::HashMap,
+  hash::Hash,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  thread,
+  time::Duration,
+};
 use thiserror::Error;
 
-pub type ListenerCallback = dyn 'static + FnMut() + Send;
+// the callbacks of the Windows and macOS backends, which report presses and releases like
+// the X11 one
+#[cfg(not(target_os = "linux"))]
+pub type ListenerCallback = dyn 'static + FnMut(KeyState) + Send;
+
+/// Whether a hotkey registered with [`HotkeyListener::register_hotkey_with_state`] was
+/// pressed or released.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum KeyState {
+  Pressed,
+  Released,
+}
+
+// how often the key state is polled after a press by listeners without release events
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct ListenerHotkey {
@@ -14,13 +38,231 @@ impl ListenerHotkey {
   }
 }
 
+/// Options used to construct a [`HotkeyListener`], see [`ListenerBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerOptions {
+  /// How often the listener thread polls for events and requests. Only used on
+  /// Linux, the other backends wait for events.
+  pub poll_interval: Duration,
+  /// Name of the listener thread.
+  pub thread_name: Option<String>,
+  /// Reports a held hotkey once instead of on every key repeat (`MOD_NOREPEAT` on Windows),
+  /// the default. Hotkeys registered with [`HotkeyListener::register_hotkey_with_repeat`]
+  /// can still ask for repeats. The Linux and macOS backends never report key repeats.
+  pub no_repeat: bool,
+  /// Offset of the hotkey ids passed to `RegisterHotKey` on Windows and `RegisterEventHotKey`
+  /// on macOS, so they don't collide with the ids of hotkeys the embedding application
+  /// registers itself and e.g. inspects in its own `WM_HOTKEY` handling. Unused on Linux.
+  pub id_base: i32,
+}
+
+impl Default for ListenerOptions {
+  fn default() -> Self {
+    Self {
+      poll_interval: Duration::from_millis(50),
+      thread_name: None,
+      no_repeat: true,
+      id_base: 0,
+    }
+  }
+}
+
+/// Largest id an application may pass to `RegisterHotKey`, the ids above are reserved for
+/// shared libraries. The ids of every backend are kept in this range so a configuration
+/// works on all of them.
+pub const MAX_HOTKEY_ID: i32 = 0xBFFF;
+
+#[derive(Debug, Default, Clone)]
+pub struct ListenerBuilder {
+  options: ListenerOptions,
+}
+
+impl ListenerBuilder {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+    self.options.poll_interval = poll_interval;
+    self
+  }
+
+  pub fn thread_name<S: Into<String>>(mut self, thread_name: S) -> Self {
+    self.options.thread_name.replace(thread_name.into());
+    self
+  }
+
+  pub fn no_repeat(mut self, no_repeat: bool) -> Self {
+    self.options.no_repeat = no_repeat;
+    self
+  }
+
+  /// Sets [`ListenerOptions::id_base`], failing with [`HotkeyError::InvalidIdBase`] unless
+  /// it's within `0..MAX_HOTKEY_ID`, so at least one id is left above it.
+  pub fn id_base(mut self, id_base: i32) -> Result<Self, HotkeyError> {
+    if !(0..MAX_HOTKEY_ID).contains(&id_base) {
+      return Err(HotkeyError::InvalidIdBase(id_base));
+    }
+    self.options.id_base = id_base;
+    Ok(self)
+  }
+
+  pub fn build<L: HotkeyListener>(self) -> L {
+    L::with_options(self.options)
+  }
+}
+
 pub trait HotkeyListener {
-  fn new() -> Self;
+  fn new() -> Self
+  where
+    Self: Sized,
+  {
+    Self::with_options(ListenerOptions::default())
+  }
+  fn with_options(options: ListenerOptions) -> Self;
   fn register_hotkey<F>(&mut self, hotkey: ListenerHotkey, callback: F) -> Result<(), HotkeyError>
   where
     F: 'static + FnMut() + Send;
+  /// Registers a hotkey, reporting the OS key repeats while it's held if `allow_repeat` is
+  /// set, regardless of [`ListenerOptions::no_repeat`]. Only the Windows backend reports
+  /// key repeats, the others register the hotkey as usual.
+  fn register_hotkey_with_repeat<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    _allow_repeat: bool,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey(hotkey, callback)
+  }
+  /// Registers a hotkey that never reports the OS key repeats while it's held, even if the
+  /// listener was built without [`ListenerOptions::no_repeat`]. Only the Windows backend
+  /// reports key repeats, the others register the hotkey as usual.
+  fn register_hotkey_without_repeat<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    self.register_hotkey(hotkey, callback)
+  }
+  /// Registers a hotkey reporting both its press and its release, e.g. for push-to-talk.
+  ///
+  /// The X11 backend reports the key events it receives and macOS the
+  /// `kEventHotKeyReleased` event, which ignores the modifiers. `RegisterHotKey` only
+  /// reports presses, so the Windows backend watches the key releases with a low-level
+  /// keyboard hook while the hotkey is held, where releasing a modifier first counts as
+  /// releasing the hotkey.
+  ///
+  /// Listeners without release events can rely on this default, which polls
+  /// [`is_hotkey_held`](crate::is_hotkey_held) on a thread after a press, reporting the
+  /// release up to 10 ms late. Presses while the hotkey is still held, e.g. key repeats,
+  /// are ignored.
+  fn register_hotkey_with_state<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(KeyState) + Send,
+  {
+    let callback = Arc::new(Mutex::new(callback));
+    let held = Arc::new(AtomicBool::new(false));
+    self.register_hotkey(hotkey, move || {
+      if held.swap(true, Ordering::SeqCst) {
+        return;
+      }
+      (callback.lock().unwrap())(KeyState::Pressed);
+      let callback = callback.clone();
+      let released = held.clone();
+      let watch = thread::Builder::new()
+        .name("hotkey release watch".into())
+        .spawn(move || {
+          while crate::is_hotkey_held(hotkey) {
+            thread::sleep(RELEASE_POLL_INTERVAL);
+          }
+          (callback.lock().unwrap())(KeyState::Released);
+          released.store(false, Ordering::SeqCst);
+        });
+      if let Err(err) = watch {
+        eprintln!("hotkey: failed to watch the hotkey release {}", err);
+        held.store(false, Ordering::SeqCst);
+      }
+    })
+  }
+  /// Registers a hotkey seen by a low-level keyboard hook instead of being grabbed with the
+  /// OS hotkey API. With `suppress` its key events are swallowed like those of a grabbed
+  /// hotkey, otherwise they still reach the focused application, e.g. for an overlay
+  /// reacting to a key without blocking normal typing. The hook doesn't report releases,
+  /// and key repeats of the hotkey are suppressed along with its press.
+  ///
+  /// Only the Windows backend has such a hook (`WH_KEYBOARD_LL`), where the callback runs
+  /// inside the hook and Windows silently removes the hook if it takes longer than the
+  /// `LowLevelHooksTimeout`. The other backends fail with [`HotkeyError::Unsupported`].
+  /// Hook hotkeys are unregistered like the others.
+  fn register_hook_hotkey<F>(
+    &mut self,
+    _hotkey: ListenerHotkey,
+    _suppress: bool,
+    _callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    Err(HotkeyError::Unsupported)
+  }
+  /// Reports the combination of every key pressed from now on, modifiers included, until
+  /// [`HotkeyListener::stop_learning`] is called, e.g. for a settings UI showing what the
+  /// user presses. Modifier keys alone and key repeats aren't reported, and the key events
+  /// still reach the focused application. Starting again replaces the callback.
+  ///
+  /// While the OS sticky keys are on (`SPI_GETSTICKYKEYS`), a modifier pressed and released
+  /// alone is reported with the next key pressed within 3 seconds, e.g. tapping Ctrl, then
+  /// Shift, then P reports Ctrl+Shift+P like holding them would. The sticky keys setting is
+  /// read when learning starts.
+  ///
+  /// Like [`HotkeyListener::register_hook_hotkey`], this needs the keyboard hook of the
+  /// Windows backend, the other backends fail with [`HotkeyError::Unsupported`].
+  fn start_learning<F>(&mut self, _callback: F) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(ListenerHotkey) + Send,
+  {
+    Err(HotkeyError::Unsupported)
+  }
+  /// Stops reporting the keys pressed, see [`HotkeyListener::start_learning`].
+  fn stop_learning(&mut self) -> Result<(), HotkeyError> {
+    Ok(())
+  }
   fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError>;
+  /// Unregisters several hotkeys at once, returning the first error after trying all of them.
+  /// The platform backends release them with a single request to their listener thread.
+  fn unregister_hotkeys(&mut self, hotkeys: &[ListenerHotkey]) -> Result<(), HotkeyError> {
+    let mut result = Ok(());
+    for hotkey in hotkeys {
+      if let Err(err) = self.unregister_hotkey(*hotkey) {
+        if result.is_ok() {
+          result = Err(err);
+        }
+      }
+    }
+    result
+  }
+  /// Returns the registered hotkeys, in a stable order.
   fn registered_hotkeys(&self) -> Vec<ListenerHotkey>;
+  /// Returns the hotkey the OS actually bound for a registered hotkey, or `None` if it isn't
+  /// registered. Backends binding exactly the requested hotkey use this default, others,
+  /// e.g. where the compositor may assign or let the user rebind the trigger, override it.
+  fn effective_hotkey(&self, hotkey: ListenerHotkey) -> Option<ListenerHotkey> {
+    if self.registered_hotkeys().contains(&hotkey) {
+      Some(hotkey)
+    } else {
+      None
+    }
+  }
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -31,8 +273,93 @@ pub enum HotkeyError {
   HotkeyAlreadyRegistered(ListenerHotkey),
   #[error("hotkey not registered: `{0:?}`")]
   HotkeyNotRegistered(ListenerHotkey),
+  /// The OS refused the hotkey because another application or the system owns it, unlike
+  /// [`HotkeyError::HotkeyAlreadyRegistered`] which is reported for this listener's own hotkeys.
+  #[error("hotkey taken by the system or another application: `{0:?}`")]
+  HotkeyTakenBySystem(ListenerHotkey),
+  #[error("modifier not supported by the backend: `{0}`")]
+  UnsupportedModifier(u32),
+  #[error("key not supported by the backend: `{0}`")]
+  UnsupportedKey(u32),
+  #[error("not supported by the backend")]
+  Unsupported,
   #[error("backend api error: `{0}`")]
   BackendApiError(usize),
+  /// A macOS Carbon call failed with this `OSStatus`, which is negative for most errors.
+  #[error("macOS error: OSStatus `{0}`")]
+  OsStatus(i32),
+  #[error("hotkey id base outside 0..0xBFFF: `{0}`")]
+  InvalidIdBase(i32),
+  /// Every hotkey id between the id base and [`MAX_HOTKEY_ID`] is in use.
+  #[error("no hotkey id left above the id base")]
+  NoFreeHotkeyId,
   #[error("unknown error")]
   Unknown,
 }
+
+// Returns the hotkeys sorted by their listener id, so the order doesn't depend on the
+// map's iteration order.
+#[cfg_attr(feature = "mock", allow(dead_code))]
+pub(crate) fn sorted_by_id<I, V>(
+  handlers: &HashMap<I, V>,
+  hotkey_of: impl Fn(&V) -> ListenerHotkey,
+) -> Vec<ListenerHotkey>
+where
+  I: Copy + Ord,
+{
+  let mut result: Vec<_> = handlers
+    .iter()
+    .map(|(id, value)| (*id, hotkey_of(value)))
+    .collect();
+  result.sort_by_key(|(id, _)| *id);
+  result.into_iter().map(|(_, hotkey)| hotkey).collect()
+}
+
+// The first id after `last_id` for which `is_free` holds, wrapping around to the id base so
+// the ids of released hotkeys are reused once the range up to `MAX_HOTKEY_ID` is exhausted.
+#[cfg_attr(any(target_os = "linux", feature = "mock"), allow(dead_code))]
+pub(crate) fn next_free_id(
+  id_base: i32,
+  last_id: i32,
+  is_free: impl Fn(i32) -> bool,
+) -> Result<i32, HotkeyError> {
+  let count = MAX_HOTKEY_ID - id_base;
+  let start = last_id.max(id_base) - id_base;
+  (0..count)
+    .map(|offset| id_base + 1 + (start + offset) % count)
+    .find(|id| is_free(*id))
+    .ok_or(HotkeyError::NoFreeHotkeyId)
+}
+
+// Looks up the listener ids of the hotkeys, each id once, along with a
+// `HotkeyNotRegistered` error for the first hotkey that isn't registered.
+#[cfg_attr(feature = "mock", allow(dead_code))]
+pub(crate) fn find_ids<I, V>(
+  handlers: &HashMap<I, V>,
+  hotkeys: &[ListenerHotkey],
+  hotkey_of: impl Fn(&V) -> ListenerHotkey,
+) -> (Vec<I>, Result<(), HotkeyError>)
+where
+  I: Copy + Eq + Hash,
+{
+  let mut ids = Vec::new();
+  let mut result = Ok(());
+  for hotkey in hotkeys {
+    match handlers
+      .iter()
+      .find(|(_, value)| hotkey_of(value) == *hotkey)
+    {
+      Some((id, _)) => {
+        if !ids.contains(id) {
+          ids.push(*id);
+        }
+      }
+      None => {
+        if result.is_ok() {
+          result = Err(HotkeyError::HotkeyNotRegistered(*hotkey));
+        }
+      }
+    }
+  }
+  (ids, result)
+}
@@ -1,6 +1,7 @@
 use std::{
+  cell::RefCell,
   collections::HashMap,
-  mem,
+  mem, ptr,
   sync::{
     mpsc,
     mpsc::{Receiver, Sender},
@@ -8,14 +9,23 @@ use std::{
   },
   thread,
 };
-use winapi::{shared::windef::HWND, um::winuser};
+use winapi::{
+  ctypes::c_int,
+  shared::{
+    minwindef::{DWORD, LPARAM, LRESULT, UINT, WPARAM},
+    windef::{HHOOK, HWND},
+    winerror,
+  },
+  um::{processthreadsapi::GetCurrentThreadId, winuser},
+};
 
-use super::traits::*;
+use super::{hook::HookDispatcher, traits::*};
 
 pub mod modifiers {
   use winapi::um::winuser;
   pub const ALT: u32 = winuser::MOD_ALT as u32;
-  pub const ALT_GR: u32 = winuser::VK_RMENU as u32;
+  // Windows reports AltGr as Ctrl+Alt
+  pub const ALT_GR: u32 = (winuser::MOD_CONTROL | winuser::MOD_ALT) as u32;
   pub const CONTROL: u32 = winuser::MOD_CONTROL as u32;
   pub const SHIFT: u32 = winuser::MOD_SHIFT as u32;
   pub const SUPER: u32 = winuser::MOD_WIN as u32;
@@ -53,7 +63,8 @@ pub mod keys {
   pub const MEDIA_STOP: u32 = winuser::VK_MEDIA_STOP as u32;
   pub const MEDIA_PLAY_PAUSE: u32 = winuser::VK_MEDIA_PLAY_PAUSE as u32;
   pub const LAUNCH_MAIL: u32 = winuser::VK_LAUNCH_MAIL as u32;
-  // F1-F12
+  pub const MENU: u32 = winuser::VK_APPS as u32;
+  // F1-F24
   pub const F1: u32 = winuser::VK_F1 as u32;
   pub const F2: u32 = winuser::VK_F2 as u32;
   pub const F3: u32 = winuser::VK_F3 as u32;
@@ -66,6 +77,18 @@ pub mod keys {
   pub const F10: u32 = winuser::VK_F10 as u32;
   pub const F11: u32 = winuser::VK_F11 as u32;
   pub const F12: u32 = winuser::VK_F12 as u32;
+  pub const F13: u32 = winuser::VK_F13 as u32;
+  pub const F14: u32 = winuser::VK_F14 as u32;
+  pub const F15: u32 = winuser::VK_F15 as u32;
+  pub const F16: u32 = winuser::VK_F16 as u32;
+  pub const F17: u32 = winuser::VK_F17 as u32;
+  pub const F18: u32 = winuser::VK_F18 as u32;
+  pub const F19: u32 = winuser::VK_F19 as u32;
+  pub const F20: u32 = winuser::VK_F20 as u32;
+  pub const F21: u32 = winuser::VK_F21 as u32;
+  pub const F22: u32 = winuser::VK_F22 as u32;
+  pub const F23: u32 = winuser::VK_F23 as u32;
+  pub const F24: u32 = winuser::VK_F24 as u32;
   // Numpad
   pub const ADD: u32 = winuser::VK_ADD as u32;
   pub const SUBTRACT: u32 = winuser::VK_SUBTRACT as u32;
@@ -132,88 +155,443 @@ pub mod keys {
 }
 
 type ListenerId = i32;
+// posted to the listener thread when a request was sent
+const WAKE_MESSAGE: UINT = winuser::WM_APP;
+
+// the listener's own hotkeys are rejected before reaching `RegisterHotKey`, so
+// `ERROR_HOTKEY_ALREADY_REGISTERED` means another application owns the combination
+pub(crate) fn register_error(error_code: DWORD, hotkey: ListenerHotkey) -> HotkeyError {
+  match error_code {
+    winerror::ERROR_HOTKEY_ALREADY_REGISTERED => HotkeyError::HotkeyTakenBySystem(hotkey),
+    error_code => HotkeyError::BackendApiError(error_code as usize),
+  }
+}
+
+// `MOD_NOREPEAT` unless the listener or the hotkey asks for key repeats
+pub(crate) fn repeat_modifier(no_repeat: bool, allow_repeat: bool) -> u32 {
+  if no_repeat && !allow_repeat {
+    winuser::MOD_NOREPEAT as u32
+  } else {
+    0
+  }
+}
+
 enum HotkeyMessage {
-  RegisterHotkey(ListenerId, ListenerHotkey),
+  // the hotkey and its `repeat_modifier`
+  RegisterHotkey(ListenerId, ListenerHotkey, u32),
   RegisterHotkeyResult(Result<(), HotkeyError>),
-  UnregisterHotkey(ListenerId),
+  UnregisterHotkeys(Vec<ListenerId>),
   UnregisterHotkeyResult(Result<(), HotkeyError>),
+  // installs or removes the keyboard hook of `register_hook_hotkey` as needed
+  UpdateKeyHook,
+  UpdateKeyHookResult(Result<(), HotkeyError>),
   DropThread,
 }
-type ListenerMap = Arc<Mutex<HashMap<ListenerId, (ListenerHotkey, Box<ListenerCallback>)>>>;
+// the callback of each hotkey and whether it reports the release
+type ListenerMap = Arc<Mutex<HashMap<ListenerId, (ListenerHotkey, Box<ListenerCallback>, bool)>>>;
+
+// The held hotkeys registered with `register_hotkey_with_state`. `RegisterHotKey` only
+// reports presses, so a low-level keyboard hook watches the key releases on the listener
+// thread while any of them is held.
+struct HeldHotkeys {
+  handlers: Option<ListenerMap>,
+  ids: Vec<(ListenerId, ListenerHotkey)>,
+  hook: HHOOK,
+}
+
+thread_local! {
+  static HELD_HOTKEYS: RefCell<HeldHotkeys> = RefCell::new(HeldHotkeys {
+    handlers: None,
+    ids: Vec::new(),
+    hook: ptr::null_mut(),
+  });
+}
+
+// Reports the press of a hotkey reporting its release, returning false while it's already
+// held, i.e. for its key repeats.
+unsafe fn watch_release(id: ListenerId, hotkey: ListenerHotkey) -> bool {
+  HELD_HOTKEYS.with(|held| {
+    // a callback pumping messages may re-enter the hook
+    let mut held = match held.try_borrow_mut() {
+      Ok(held) => held,
+      Err(_) => return true,
+    };
+    if held.ids.iter().any(|(held_id, _)| *held_id == id) {
+      return false;
+    }
+    if held.hook.is_null() {
+      held.hook = winuser::SetWindowsHookExW(
+        winuser::WH_KEYBOARD_LL,
+        Some(release_hook),
+        ptr::null_mut(),
+        0,
+      );
+      if held.hook.is_null() {
+        eprintln!(
+          "hotkey: failed to watch the hotkey release {}",
+          winapi::um::errhandlingapi::GetLastError()
+        );
+        return true;
+      }
+    }
+    held.ids.push((id, hotkey));
+    true
+  })
+}
+
+// the modifier of each virtual key the low-level hook reports, which tells the left and
+// right modifiers apart
+const MODIFIER_KEYS: &[(u32, u32)] = &[
+  (winuser::VK_LSHIFT as u32, modifiers::SHIFT),
+  (winuser::VK_RSHIFT as u32, modifiers::SHIFT),
+  (winuser::VK_LCONTROL as u32, modifiers::CONTROL),
+  (winuser::VK_RCONTROL as u32, modifiers::CONTROL),
+  (winuser::VK_LMENU as u32, modifiers::ALT),
+  (winuser::VK_RMENU as u32, modifiers::ALT),
+  (winuser::VK_LWIN as u32, modifiers::SUPER),
+  (winuser::VK_RWIN as u32, modifiers::SUPER),
+];
+
+// Whether releasing the virtual key releases the hotkey. Releasing a modifier first counts.
+fn releases(hotkey: ListenerHotkey, vk_code: DWORD) -> bool {
+  vk_code == hotkey.key
+    || MODIFIER_KEYS
+      .iter()
+      .any(|(key, modifier)| *key == vk_code && hotkey.modifiers & modifier != 0)
+}
+
+unsafe extern "system" fn release_hook(code: c_int, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+  let key_up = w_param as UINT == winuser::WM_KEYUP || w_param as UINT == winuser::WM_SYSKEYUP;
+  if code == winuser::HC_ACTION && key_up {
+    let vk_code = (*(l_param as *const winuser::KBDLLHOOKSTRUCT)).vkCode;
+    HELD_HOTKEYS.with(|held| {
+      if let Ok(mut held) = held.try_borrow_mut() {
+        let HeldHotkeys { handlers, ids, .. } = &mut *held;
+        if let Some(handlers) = handlers {
+          let mut handlers = handlers.lock().unwrap();
+          ids.retain(|(id, hotkey)| {
+            if !releases(*hotkey, vk_code) {
+              return true;
+            }
+            // the hotkey may have been unregistered while held
+            if let Some((_, handler, _)) = handlers.get_mut(id) {
+              handler(KeyState::Released);
+            }
+            false
+          });
+        }
+        if ids.is_empty() {
+          // wakes up the message loop, which removes the hook
+          winuser::PostThreadMessageW(GetCurrentThreadId(), WAKE_MESSAGE, 0, 0);
+        }
+      }
+    });
+  }
+  winuser::CallNextHookEx(ptr::null_mut(), code, w_param, l_param)
+}
+
+// Removes the hook once no hotkey is held, forgetting the unregistered ones.
+unsafe fn unhook_released(handlers: &ListenerMap) {
+  HELD_HOTKEYS.with(|held| {
+    let mut held = held.borrow_mut();
+    let handlers = handlers.lock().unwrap();
+    held.ids.retain(|(id, _)| handlers.contains_key(id));
+    if held.ids.is_empty() && !held.hook.is_null() {
+      winuser::UnhookWindowsHookEx(held.hook);
+      held.hook = ptr::null_mut();
+    }
+  })
+}
+
+// The hotkeys registered with `register_hook_hotkey`, matched by a low-level keyboard hook
+// installed on the listener thread while any of them is registered.
+struct KeyHook {
+  dispatcher: Option<Arc<Mutex<HookDispatcher>>>,
+  hook: HHOOK,
+}
+
+thread_local! {
+  static KEY_HOOK: RefCell<KeyHook> = RefCell::new(KeyHook {
+    dispatcher: None,
+    hook: ptr::null_mut(),
+  });
+}
+
+unsafe extern "system" fn keyboard_hook(code: c_int, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+  let state = match w_param as UINT {
+    winuser::WM_KEYDOWN | winuser::WM_SYSKEYDOWN => Some(KeyState::Pressed),
+    winuser::WM_KEYUP | winuser::WM_SYSKEYUP => Some(KeyState::Released),
+    _ => None,
+  };
+  if let (winuser::HC_ACTION, Some(state)) = (code, state) {
+    let vk_code = (*(l_param as *const winuser::KBDLLHOOKSTRUCT)).vkCode;
+    let suppress = KEY_HOOK.with(|key_hook| match key_hook.try_borrow() {
+      Ok(key_hook) => match &key_hook.dispatcher {
+        Some(dispatcher) => dispatcher.lock().unwrap().key_event(vk_code, state),
+        None => false,
+      },
+      // a callback pumping messages may re-enter the hook
+      Err(_) => false,
+    });
+    if suppress {
+      return 1;
+    }
+  }
+  winuser::CallNextHookEx(ptr::null_mut(), code, w_param, l_param)
+}
+
+// Installs the keyboard hook while a hook hotkey is registered, and removes it otherwise.
+unsafe fn update_key_hook() -> Result<(), HotkeyError> {
+  KEY_HOOK.with(|key_hook| {
+    let mut key_hook = key_hook.borrow_mut();
+    let needed = match &key_hook.dispatcher {
+      Some(dispatcher) => !dispatcher.lock().unwrap().is_empty(),
+      None => false,
+    };
+    if needed && key_hook.hook.is_null() {
+      key_hook.hook = winuser::SetWindowsHookExW(
+        winuser::WH_KEYBOARD_LL,
+        Some(keyboard_hook),
+        ptr::null_mut(),
+        0,
+      );
+      if key_hook.hook.is_null() {
+        return Err(HotkeyError::BackendApiError(
+          winapi::um::errhandlingapi::GetLastError() as usize,
+        ));
+      }
+    } else if !needed && !key_hook.hook.is_null() {
+      winuser::UnhookWindowsHookEx(key_hook.hook);
+      key_hook.hook = ptr::null_mut();
+    }
+    Ok(())
+  })
+}
+
+// `STICKYKEYS` and `SKF_STICKYKEYSON` of winuser.h, which winapi doesn't bind
+#[repr(C)]
+struct StickyKeys {
+  cb_size: UINT,
+  dw_flags: DWORD,
+}
+const SKF_STICKYKEYSON: DWORD = 0x0000_0001;
+
+// whether the user turned on sticky keys in the accessibility settings
+unsafe fn sticky_keys_on() -> bool {
+  let mut sticky_keys = StickyKeys {
+    cb_size: mem::size_of::<StickyKeys>() as UINT,
+    dw_flags: 0,
+  };
+  winuser::SystemParametersInfoW(
+    winuser::SPI_GETSTICKYKEYS,
+    sticky_keys.cb_size,
+    &mut sticky_keys as *mut StickyKeys as *mut _,
+    0,
+  ) != 0
+    && sticky_keys.dw_flags & SKF_STICKYKEYSON != 0
+}
 
 pub struct Listener {
+  thread_id: DWORD,
+  id_base: ListenerId,
   last_id: ListenerId,
+  // see `ListenerOptions::no_repeat`
+  no_repeat: bool,
   handlers: ListenerMap,
+  hook_hotkeys: Arc<Mutex<HookDispatcher>>,
   sender: Sender<HotkeyMessage>,
   receiver: Receiver<HotkeyMessage>,
 }
 
+impl Listener {
+  fn send(&self, message: HotkeyMessage) -> Result<(), HotkeyError> {
+    self
+      .sender
+      .send(message)
+      .map_err(|_| HotkeyError::ChannelError())?;
+    if unsafe { winuser::PostThreadMessageW(self.thread_id, WAKE_MESSAGE, 0, 0) } == 0 {
+      return Err(HotkeyError::BackendApiError(unsafe {
+        winapi::um::errhandlingapi::GetLastError() as usize
+      }));
+    }
+    Ok(())
+  }
+
+  fn next_id(&mut self) -> Result<ListenerId, HotkeyError> {
+    let handlers = self.handlers.lock().unwrap();
+    let id = next_free_id(self.id_base, self.last_id, |id| !handlers.contains_key(&id))?;
+    self.last_id = id;
+    Ok(id)
+  }
+
+  fn register(
+    &mut self,
+    hotkey: ListenerHotkey,
+    repeat: u32,
+    with_release: bool,
+    handler: Box<ListenerCallback>,
+  ) -> Result<(), HotkeyError> {
+    let hooked = self
+      .hook_hotkeys
+      .lock()
+      .unwrap()
+      .hotkeys()
+      .contains(&hotkey);
+    if hooked
+      || self
+        .handlers
+        .lock()
+        .unwrap()
+        .values()
+        .any(|(key, _, _)| *key == hotkey)
+    {
+      return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
+    }
+    let id = self.next_id()?;
+    self.send(HotkeyMessage::RegisterHotkey(id, hotkey, repeat))?;
+    match self.receiver.recv() {
+      Ok(HotkeyMessage::RegisterHotkeyResult(Ok(_))) => {
+        self
+          .handlers
+          .lock()
+          .unwrap()
+          .insert(id, (hotkey, handler, with_release));
+        Ok(())
+      }
+      Ok(HotkeyMessage::RegisterHotkeyResult(Err(err))) => Err(err),
+      Err(_) => Err(HotkeyError::ChannelError()),
+      _ => Err(HotkeyError::Unknown),
+    }
+  }
+
+  fn update_key_hook(&self) -> Result<(), HotkeyError> {
+    self.send(HotkeyMessage::UpdateKeyHook)?;
+    match self.receiver.recv() {
+      Ok(HotkeyMessage::UpdateKeyHookResult(result)) => result,
+      Err(_) => Err(HotkeyError::ChannelError()),
+      _ => Err(HotkeyError::Unknown),
+    }
+  }
+
+  #[cfg(test)]
+  pub(crate) fn registered_ids(&self) -> Vec<ListenerId> {
+    let mut ids: Vec<_> = self.handlers.lock().unwrap().keys().copied().collect();
+    ids.sort_unstable();
+    ids
+  }
+}
+
 impl HotkeyListener for Listener {
-  fn new() -> Listener {
+  fn with_options(options: ListenerOptions) -> Listener {
     let hotkeys = ListenerMap::default();
+    let hook_hotkeys = Arc::new(Mutex::new(HookDispatcher::new(MODIFIER_KEYS)));
 
     let hotkey_map = hotkeys.clone();
+    let key_hotkeys = hook_hotkeys.clone();
     let (method_sender, thread_receiver) = mpsc::channel();
     let (thread_sender, method_receiver) = mpsc::channel();
 
-    thread::spawn(move || unsafe {
-      loop {
-        let mut msg = mem::MaybeUninit::uninit().assume_init();
-        while winuser::PeekMessageW(&mut msg, 0 as HWND, 0, 0, 1) > 0 {
-          if msg.wParam != 0 {
-            if let Some((_, handler)) = hotkey_map.lock().unwrap().get_mut(&(msg.wParam as i32)) {
-              handler();
-            }
-          }
+    let mut thread_builder = thread::Builder::new();
+    if let Some(thread_name) = options.thread_name {
+      thread_builder = thread_builder.name(thread_name);
+    }
+    let (ready_sender, ready_receiver) = mpsc::channel();
+    thread_builder
+      .spawn(move || unsafe {
+        // `PeekMessageW` and `GetMessageW` only fill the struct when they return a message,
+        // zeroing it keeps it initialized when the queue is empty
+        let mut msg: winuser::MSG = mem::zeroed();
+        // creates the message queue, so requests can wake up the thread
+        winuser::PeekMessageW(&mut msg, 0 as HWND, 0, 0, winuser::PM_NOREMOVE);
+        if ready_sender.send(GetCurrentThreadId()).is_err() {
+          return;
         }
-        match thread_receiver.try_recv() {
-          Ok(HotkeyMessage::RegisterHotkey(id, hotkey)) => {
-            let result = winuser::RegisterHotKey(0 as HWND, id, hotkey.modifiers, hotkey.key);
-            if result == 0 {
-              if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Err(
-                HotkeyError::BackendApiError(winapi::um::errhandlingapi::GetLastError() as usize),
-              ))) {
-                eprintln!("hotkey: thread_sender.send error {}", err);
+        HELD_HOTKEYS.with(|held| held.borrow_mut().handlers = Some(hotkey_map.clone()));
+        KEY_HOOK.with(|key_hook| key_hook.borrow_mut().dispatcher = Some(key_hotkeys));
+        // blocks until a hotkey is pressed or `Listener::send` posts a wake up message
+        while winuser::GetMessageW(&mut msg, 0 as HWND, 0, 0) > 0 {
+          match msg.message {
+            winuser::WM_HOTKEY => {
+              let id = msg.wParam as ListenerId;
+              if let Some((hotkey, handler, with_release)) = hotkey_map.lock().unwrap().get_mut(&id)
+              {
+                if !*with_release || watch_release(id, *hotkey) {
+                  handler(KeyState::Pressed);
+                }
               }
-            } else if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Ok(())))
-            {
-              eprintln!("hotkey: thread_sender.send error {}", err);
             }
-          }
-          Ok(HotkeyMessage::UnregisterHotkey(id)) => {
-            let result = winuser::UnregisterHotKey(0 as HWND, id);
-            if result == 0 {
-              if let Err(err) = thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(Err(
-                HotkeyError::BackendApiError(winapi::um::errhandlingapi::GetLastError() as usize),
-              ))) {
-                eprintln!("hotkey: thread_sender.send error {}", err);
+            WAKE_MESSAGE => loop {
+              match thread_receiver.try_recv() {
+                Ok(HotkeyMessage::RegisterHotkey(id, hotkey, repeat)) => {
+                  let result =
+                    winuser::RegisterHotKey(0 as HWND, id, hotkey.modifiers | repeat, hotkey.key);
+                  if result == 0 {
+                    if let Err(err) = thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Err(
+                      register_error(winapi::um::errhandlingapi::GetLastError(), hotkey),
+                    ))) {
+                      eprintln!("hotkey: thread_sender.send error {}", err);
+                    }
+                  } else if let Err(err) =
+                    thread_sender.send(HotkeyMessage::RegisterHotkeyResult(Ok(())))
+                  {
+                    eprintln!("hotkey: thread_sender.send error {}", err);
+                  }
+                }
+                Ok(HotkeyMessage::UnregisterHotkeys(ids)) => {
+                  let mut result = Ok(());
+                  for id in ids {
+                    if winuser::UnregisterHotKey(0 as HWND, id) == 0 && result.is_ok() {
+                      result = Err(HotkeyError::BackendApiError(
+                        winapi::um::errhandlingapi::GetLastError() as usize,
+                      ));
+                    }
+                  }
+                  if let Err(err) =
+                    thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(result))
+                  {
+                    eprintln!("hotkey: thread_sender.send error {}", err);
+                  }
+                }
+                Ok(HotkeyMessage::UpdateKeyHook) => {
+                  let result = update_key_hook();
+                  if let Err(err) = thread_sender.send(HotkeyMessage::UpdateKeyHookResult(result)) {
+                    eprintln!("hotkey: thread_sender.send error {}", err);
+                  }
+                }
+                Ok(HotkeyMessage::DropThread) => {
+                  HELD_HOTKEYS.with(|held| held.borrow_mut().ids.clear());
+                  unhook_released(&hotkey_map);
+                  KEY_HOOK.with(|key_hook| key_hook.borrow_mut().dispatcher = None);
+                  let _ = update_key_hook();
+                  return;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                Err(err) => {
+                  eprintln!("hotkey: try_recv error {}", err);
+                  return;
+                }
+                _ => unreachable!("other message should not arrive"),
               }
-            } else if let Err(err) =
-              thread_sender.send(HotkeyMessage::UnregisterHotkeyResult(Ok(())))
-            {
-              eprintln!("hotkey: thread_sender.send error {}", err);
-            }
-          }
-          Ok(HotkeyMessage::DropThread) => {
-            return;
+            },
+            _ => {}
           }
-          Err(err) => {
-            if let std::sync::mpsc::TryRecvError::Disconnected = err {
-              eprintln!("hotkey: try_recv error {}", err);
-            }
-          }
-          _ => unreachable!("other message should not arrive"),
+          unhook_released(&hotkey_map);
         }
-
-        std::thread::sleep(std::time::Duration::from_millis(50));
-      }
-    });
+      })
+      .expect("failed to spawn the hotkey listener thread");
+    let thread_id = ready_receiver
+      .recv()
+      .expect("the hotkey listener thread exited");
 
     Listener {
+      thread_id,
       sender: method_sender,
       receiver: method_receiver,
-      last_id: 0,
+      id_base: options.id_base,
+      last_id: options.id_base,
+      no_repeat: options.no_repeat,
       handlers: hotkeys,
+      hook_hotkeys,
     }
   }
 
@@ -221,70 +599,256 @@ impl HotkeyListener for Listener {
   where
     F: 'static + FnMut() + Send,
   {
-    for (key, _) in self.handlers.lock().unwrap().values() {
-      if *key == hotkey {
-        return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
+    self.register_hotkey_with_repeat(hotkey, false, handler)
+  }
+
+  fn register_hotkey_with_repeat<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    allow_repeat: bool,
+    mut handler: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    let handler = move |state: KeyState| {
+      if state == KeyState::Pressed {
+        handler();
+      }
+    };
+    let repeat = repeat_modifier(self.no_repeat, allow_repeat);
+    self.register(hotkey, repeat, false, Box::new(handler))
+  }
+
+  fn register_hotkey_without_repeat<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    mut handler: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    let handler = move |state: KeyState| {
+      if state == KeyState::Pressed {
+        handler();
       }
+    };
+    let repeat = winuser::MOD_NOREPEAT as u32;
+    self.register(hotkey, repeat, false, Box::new(handler))
+  }
+
+  // the key repeats of a held hotkey are ignored, it's pressed again once released
+  fn register_hotkey_with_state<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    handler: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(KeyState) + Send,
+  {
+    let repeat = repeat_modifier(self.no_repeat, false);
+    self.register(hotkey, repeat, true, Box::new(handler))
+  }
+
+  // the hook is installed while a hook hotkey is registered
+  fn register_hook_hotkey<F>(
+    &mut self,
+    hotkey: ListenerHotkey,
+    suppress: bool,
+    callback: F,
+  ) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut() + Send,
+  {
+    if self
+      .handlers
+      .lock()
+      .unwrap()
+      .values()
+      .any(|(key, _, _)| *key == hotkey)
+    {
+      return Err(HotkeyError::HotkeyAlreadyRegistered(hotkey));
     }
-    self.last_id += 1;
-    let id = self.last_id;
     self
-      .sender
-      .send(HotkeyMessage::RegisterHotkey(id, hotkey))
-      .map_err(|_| HotkeyError::ChannelError())?;
-    match self.receiver.recv() {
-      Ok(HotkeyMessage::RegisterHotkeyResult(Ok(_))) => {
-        self
-          .handlers
-          .lock()
-          .unwrap()
-          .insert(id, (hotkey, Box::new(handler)));
-        Ok(())
-      }
-      Ok(HotkeyMessage::RegisterHotkeyResult(Err(err))) => Err(err),
-      Err(_) => Err(HotkeyError::ChannelError()),
-      _ => Err(HotkeyError::Unknown),
+      .hook_hotkeys
+      .lock()
+      .unwrap()
+      .register(hotkey, suppress, Box::new(callback))?;
+    if let Err(err) = self.update_key_hook() {
+      self.hook_hotkeys.lock().unwrap().unregister(hotkey);
+      return Err(err);
     }
+    Ok(())
   }
 
-  fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
-    let mut found_id = -1;
-    for (id, (key, _)) in self.handlers.lock().unwrap().iter() {
-      if *key == hotkey {
-        found_id = *id;
-        break;
-      }
+  fn start_learning<F>(&mut self, callback: F) -> Result<(), HotkeyError>
+  where
+    F: 'static + FnMut(ListenerHotkey) + Send,
+  {
+    let replaced = {
+      let mut hook_hotkeys = self.hook_hotkeys.lock().unwrap();
+      hook_hotkeys.set_sticky_modifiers(unsafe { sticky_keys_on() });
+      hook_hotkeys.set_learner(Some(Box::new(callback)))
+    };
+    if replaced {
+      return Ok(());
     }
-    if found_id == -1 {
-      return Err(HotkeyError::HotkeyNotRegistered(hotkey));
+    if let Err(err) = self.update_key_hook() {
+      self.hook_hotkeys.lock().unwrap().set_learner(None);
+      return Err(err);
     }
-    self
-      .sender
-      .send(HotkeyMessage::UnregisterHotkey(found_id))
-      .map_err(|_| HotkeyError::ChannelError())?;
-    if self.handlers.lock().unwrap().remove(&found_id).is_none() {
-      panic!("hotkey should never be none")
+    Ok(())
+  }
+
+  fn stop_learning(&mut self) -> Result<(), HotkeyError> {
+    if self.hook_hotkeys.lock().unwrap().set_learner(None) {
+      self.update_key_hook()?;
+    }
+    Ok(())
+  }
+
+  fn unregister_hotkey(&mut self, hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
+    self.unregister_hotkeys(&[hotkey])
+  }
+
+  // releases every hotkey with a single round trip to the listener thread
+  fn unregister_hotkeys(&mut self, hotkeys: &[ListenerHotkey]) -> Result<(), HotkeyError> {
+    let mut unhooked = Vec::new();
+    let hotkeys = {
+      let mut hook_hotkeys = self.hook_hotkeys.lock().unwrap();
+      hotkeys
+        .iter()
+        .copied()
+        .filter(|hotkey| {
+          if unhooked.contains(hotkey) || hook_hotkeys.unregister(*hotkey) {
+            unhooked.push(*hotkey);
+            return false;
+          }
+          true
+        })
+        .collect::<Vec<_>>()
     };
-    match self.receiver.recv() {
-      Ok(HotkeyMessage::UnregisterHotkeyResult(Ok(_))) => Ok(()),
-      Ok(HotkeyMessage::UnregisterHotkeyResult(Err(err))) => Err(err),
+    if !unhooked.is_empty() {
+      // only removes the hook, which doesn't fail
+      let _ = self.update_key_hook();
+    }
+    let (ids, result) = find_ids(&self.handlers.lock().unwrap(), &hotkeys, |(key, _, _)| *key);
+    if ids.is_empty() {
+      return result;
+    }
+    self.send(HotkeyMessage::UnregisterHotkeys(ids.clone()))?;
+    let mut handlers = self.handlers.lock().unwrap();
+    for id in &ids {
+      handlers.remove(id);
+    }
+    drop(handlers);
+    let released = match self.receiver.recv() {
+      Ok(HotkeyMessage::UnregisterHotkeyResult(result)) => result,
       Err(_) => Err(HotkeyError::ChannelError()),
       _ => Err(HotkeyError::Unknown),
-    }
+    };
+    result.and(released)
   }
+  // the hook hotkeys come last, in registration order
   fn registered_hotkeys(&self) -> Vec<ListenerHotkey> {
-    let mut result = Vec::new();
-    for v in self.handlers.lock().unwrap().values() {
-      result.push(v.0);
-    }
-    result
+    let mut hotkeys = sorted_by_id(&self.handlers.lock().unwrap(), |(key, _, _)| *key);
+    hotkeys.extend(self.hook_hotkeys.lock().unwrap().hotkeys());
+    hotkeys
   }
 }
 
 impl Drop for Listener {
   fn drop(&mut self) {
-    if let Err(err) = self.sender.send(HotkeyMessage::DropThread) {
+    if let Err(err) = self.send(HotkeyMessage::DropThread) {
       eprintln!("hotkey: cant send close thread message {}", err);
     }
   }
 }
+
+/// Synthesizes a press and release of the hotkey with `SendInput`.
+#[cfg(feature = "simulate")]
+pub fn simulate_hotkey(hotkey: ListenerHotkey) -> Result<(), HotkeyError> {
+  let modifier_keys = [
+    (winuser::MOD_SHIFT, winuser::VK_SHIFT),
+    (winuser::MOD_CONTROL, winuser::VK_CONTROL),
+    (winuser::MOD_ALT, winuser::VK_MENU),
+    (winuser::MOD_WIN, winuser::VK_LWIN),
+  ];
+  let mut keys: Vec<u16> = modifier_keys
+    .iter()
+    .filter(|(modifier, _)| hotkey.modifiers & *modifier as u32 != 0)
+    .map(|(_, key)| *key as u16)
+    .collect();
+  keys.push(hotkey.key as u16);
+
+  let events = keys.iter().map(|key| (*key, 0)).chain(
+    keys
+      .iter()
+      .rev()
+      .map(|key| (*key, winuser::KEYEVENTF_KEYUP)),
+  );
+  let mut inputs: Vec<winuser::INPUT> = events
+    .map(|(key, flags)| unsafe {
+      let mut input: winuser::INPUT = mem::zeroed();
+      input.type_ = winuser::INPUT_KEYBOARD;
+      *input.u.ki_mut() = winuser::KEYBDINPUT {
+        wVk: key,
+        wScan: 0,
+        dwFlags: flags,
+        time: 0,
+        dwExtraInfo: 0,
+      };
+      input
+    })
+    .collect();
+  let sent = unsafe {
+    winuser::SendInput(
+      inputs.len() as u32,
+      inputs.as_mut_ptr(),
+      mem::size_of::<winuser::INPUT>() as i32,
+    )
+  };
+  if sent as usize != inputs.len() {
+    return Err(HotkeyError::BackendApiError(unsafe {
+      winapi::um::errhandlingapi::GetLastError() as usize
+    }));
+  }
+  Ok(())
+}
+
+/// Returns whether the hotkey's key and all its modifiers are physically held right now.
+/// Either the left or right variant of a modifier counts.
+pub fn is_hotkey_held(hotkey: ListenerHotkey) -> bool {
+  let modifier_keys = [
+    (modifiers::SHIFT, &[winuser::VK_SHIFT][..]),
+    (modifiers::CONTROL, &[winuser::VK_CONTROL][..]),
+    (modifiers::ALT, &[winuser::VK_MENU][..]),
+    (modifiers::SUPER, &[winuser::VK_LWIN, winuser::VK_RWIN][..]),
+  ];
+  let is_down = |key: i32| unsafe { winuser::GetAsyncKeyState(key) as u16 & 0x8000 != 0 };
+  is_down(hotkey.key as i32)
+    && modifier_keys
+      .iter()
+      .filter(|(modifier, _)| hotkey.modifiers & modifier != 0)
+      .all(|(_, keys)| keys.iter().any(|key| is_down(*key)))
+}
+
+/// Returns whether the window, an `HWND`, is the foreground window.
+pub fn is_foreground_window(window: isize) -> Result<bool, HotkeyError> {
+  Ok(unsafe { winuser::GetForegroundWindow() } as isize == window)
+}
+
+/// Returns whether the key can be registered. Every key has a virtual key code, though the
+/// active keyboard layout may not map it, see [`physical_key`].
+pub fn is_supported_key(_key: u32) -> bool {
+  true
+}
+
+/// Returns the scan code of the physical key mapped to the virtual key with the active
+/// keyboard layout, or `None` if the virtual key isn't mapped.
+pub fn physical_key(key: u32) -> Option<u32> {
+  match unsafe { winuser::MapVirtualKeyW(key, winuser::MAPVK_VK_TO_VSC) } {
+    0 => None,
+    scan_code => Some(scan_code),
+  }
+}